@@ -36,8 +36,11 @@ fn main() {
                         .expect("Unable to create parent directories for output file");
                 }
 
-                bindings.write_to_file(out_dir.join("FirmNetter.h"));
+                let header = PathBuf::from(&crate_dir).join(&out_dir).join("FirmNetter.h");
+                bindings.write_to_file(&header);
                 println!("Successfully generated lib_tool.h in {}", out_dir.display());
+                // 供测试检查生成的头文件能否被 C/C++ 编译器接受
+                println!("cargo:rustc-env=FIRMNETTER_HEADER={}", header.display());
             }
             Err(e) => {
                 eprintln!("Failed to generate bindings: {:?}", e);
@@ -72,8 +75,11 @@ fn main() {
                         .expect("Unable to create parent directories for output file");
                 }
 
-                bindings.write_to_file(out_dir.join("FirmNetter.h"));
+                let header = PathBuf::from(&crate_dir).join(&out_dir).join("FirmNetter.h");
+                bindings.write_to_file(&header);
                 println!("Successfully generated lib_tool.h in {}", out_dir.display());
+                // 供测试检查生成的头文件能否被 C/C++ 编译器接受
+                println!("cargo:rustc-env=FIRMNETTER_HEADER={}", header.display());
             }
             Err(e) => {
                 eprintln!("Failed to generate bindings: {:?}", e);
@@ -1,7 +1,13 @@
 pub mod devices_id {
     #[allow(unused_imports)]
+    use crate::hash::hash::{HashAlgorithm, hmac};
     use regex::Regex;
+    use serde::{Deserialize, Serialize};
+    use std::collections::BTreeMap;
+    use std::ffi::{CString, c_char};
+    use std::path::{Path, PathBuf};
     use std::process::Command;
+    use std::sync::atomic::{AtomicBool, Ordering};
 
     #[warn(unused_imports)]
     use thiserror::Error;
@@ -25,6 +31,442 @@ pub mod devices_id {
 
         #[error("Invalid output format")]
         InvalidFormat,
+
+        #[error("Device ID salt must not be empty")]
+        InvalidSalt,
+
+        #[error("Running in a virtualized environment: {0}")]
+        VirtualizedEnvironment(String),
+
+        #[error("Failed to derive identifier: {0}")]
+        DerivationFailed(String),
+    }
+
+    /// v2 设备ID前缀
+    pub const DEVICE_ID_V2_PREFIX: &str = "v2:";
+
+    // v2 设备ID的 HMAC 上下文，修改会改变所有已发放的ID
+    const DEVICE_ID_V2_CONTEXT: &[u8] = b"firm_netter/device-id/v2\0";
+
+    /// procfs 中 CPU 信息的位置
+    pub const PROC_CPUINFO_PATH: &str = "/proc/cpuinfo";
+
+    // 没有DMI的ARM设备通过设备树提供序列号，所有用户可读，内容以NUL结尾
+    const DEVICETREE_SERIAL_PATH: &str = "/sys/firmware/devicetree/base/serial-number";
+
+    // Linux 上原生读取失败后是否允许调用外部命令
+    static COMMAND_FALLBACK: AtomicBool = AtomicBool::new(true);
+
+    /// 硬件信息的数据来源
+    #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+    #[serde(tag = "kind", content = "detail", rename_all = "snake_case")]
+    pub enum ValueSource {
+        /// 直接读取的文件（sysfs、procfs 等），记录路径
+        File(String),
+        /// rtnetlink 查询
+        Netlink,
+        /// Windows 注册表，记录键路径和值名
+        Registry(String),
+        /// 外部命令，记录命令行
+        Command(String),
+    }
+
+    impl std::fmt::Display for ValueSource {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            match self {
+                ValueSource::File(path) => write!(f, "file:{}", path),
+                ValueSource::Netlink => write!(f, "netlink"),
+                ValueSource::Registry(key) => write!(f, "registry:{}", key),
+                ValueSource::Command(cmd) => write!(f, "command:{}", cmd),
+            }
+        }
+    }
+
+    /// 带数据来源的硬件信息
+    #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+    pub struct SourcedValue {
+        pub value: String,
+        pub source: ValueSource,
+    }
+
+    impl SourcedValue {
+        pub fn new(value: impl Into<String>, source: ValueSource) -> Self {
+            SourcedValue {
+                value: value.into(),
+                source,
+            }
+        }
+    }
+
+    /// 从 /proc/cpuinfo 内容中提取CPU型号
+    ///
+    /// x86 使用 `model name`，ARM 使用 `Processor` 或 `Hardware`，
+    /// MIPS 使用 `cpu model`，PowerPC 使用 `cpu`。
+    pub fn parse_cpuinfo_model(content: &str) -> Option<String> {
+        const KEYS: &[&str] = &["model name", "Processor", "cpu model", "Hardware", "cpu"];
+
+        KEYS.iter().find_map(|key| {
+            content.lines().find_map(|line| {
+                let (name, value) = line.split_once(':')?;
+                let value = value.trim();
+                (name.trim() == *key && !value.is_empty()).then(|| value.to_string())
+            })
+        })
+    }
+
+    // 取 `键: 值` 行中指定键的值，`contains` 匹配保持与命令输出的缩进无关
+    fn find_colon_value<'a>(output: &'a str, key: &str) -> Option<&'a str> {
+        output
+            .lines()
+            .find(|l| l.contains(key))
+            .and_then(|line| line.split_once(':'))
+            .map(|(_, value)| value.trim())
+    }
+
+    /// 解析 WMIC 单列输出：第一行为列名，返回第一个非空值
+    pub fn parse_wmic_value(output: &str) -> Option<String> {
+        output
+            .lines()
+            .map(|l| l.trim())
+            .filter(|l| !l.is_empty())
+            .nth(1)
+            .map(|l| l.to_string())
+    }
+
+    /// 解析 `getmac /NH /FO CSV` 输出，返回第一块网卡去掉分隔符的MAC
+    pub fn parse_getmac_csv(output: &str) -> Option<String> {
+        // 提取MAC地址（CSV格式的第一个字段）
+        let first_line = output.lines().find(|l| !l.trim().is_empty())?;
+        let mac = first_line.split(',').next()?.trim().trim_matches('"');
+        (!mac.is_empty()).then(|| mac.replace("-", ""))
+    }
+
+    /// 解析 `dmidecode -t 1` 输出中的序列号
+    pub fn parse_dmidecode_serial(output: &str) -> Option<String> {
+        find_colon_value(output, "Serial Number:")
+            .filter(|serial| !serial.is_empty() && *serial != "None")
+            .map(|serial| serial.to_string())
+    }
+
+    /// 解析 `dmidecode -t 1` 输出：优先序列号，其次UUID
+    pub fn parse_dmidecode_system(output: &str) -> Option<String> {
+        parse_dmidecode_serial(output).or_else(|| {
+            find_colon_value(output, "UUID:")
+                .filter(|uuid| !uuid.is_empty() && *uuid != "00000000-0000-0000-0000-000000000000")
+                .map(|uuid| uuid.to_string())
+        })
+    }
+
+    /// 解析 `lshw -C system` 输出中的序列号
+    pub fn parse_lshw_serial(output: &str) -> Option<String> {
+        find_colon_value(output, "serial:")
+            .filter(|serial| !serial.is_empty() && *serial != "None")
+            .map(|serial| serial.to_string())
+    }
+
+    /// 解析 `ip link show` 输出中第一个以太网地址
+    pub fn parse_ip_link_mac(output: &str) -> Option<String> {
+        let re = Regex::new(r"link/ether\s+([0-9a-fA-F:]+)").unwrap();
+        re.captures(output)
+            .and_then(|caps| caps.get(1))
+            .map(|mac| mac.as_str().replace(":", ""))
+    }
+
+    /// 解析 `ifconfig` 输出中的MAC（macOS 为 `ether`，FreeBSD 可能为 `lladdr`）
+    pub fn parse_ifconfig_mac(output: &str) -> Option<String> {
+        let re = Regex::new(r"(?:ether|lladdr)\s+([0-9a-fA-F:]+)").unwrap();
+        re.captures(output)
+            .and_then(|caps| caps.get(1))
+            .map(|mac| mac.as_str().replace(":", ""))
+    }
+
+    /// 解析 `system_profiler SPHardwareDataType` 输出：
+    /// 依次尝试逻辑板序列号、硬件UUID、型号标识
+    pub fn parse_system_profiler(output: &str) -> Option<String> {
+        [
+            "Logic Board Serial Number:",
+            "Hardware UUID:",
+            "Model Identifier:",
+        ]
+        .iter()
+        .find_map(|key| find_colon_value(output, key).filter(|v| !v.is_empty()))
+        .map(|v| v.to_string())
+    }
+
+    /// 解析 `pciconf -lv` 输出中主桥（class=0x060000）的厂商和设备名
+    pub fn parse_pciconf_host_bridge(output: &str) -> Option<String> {
+        let chunk = output
+            .split("\n\n")
+            .find(|c| c.contains("class=0x060000"))?;
+        // 详细信息行形如 `    vendor     = 'Intel Corporation'`
+        let field = |key: &str| {
+            chunk.lines().find_map(|line| {
+                let (name, value) = line.split_once('=')?;
+                (name.trim() == key).then(|| {
+                    value
+                        .trim()
+                        .trim_matches('\'')
+                        .trim_matches('"')
+                        .to_string()
+                })
+            })
+        };
+        Some(format!("{}_{}", field("vendor")?, field("device")?))
+    }
+
+    /// 硬件信息的读取方式：文件、目录、符号链接、外部命令和注册表
+    ///
+    /// 默认实现 `SystemSource` 直接访问本机；测试时使用 `FixtureSource`
+    /// 注入从不同机器采集的文件内容和命令输出，验证解析逻辑。
+    pub trait HardwareSource {
+        /// 读取文本文件
+        fn read_file(&self, path: &str) -> std::io::Result<String>;
+
+        /// 列出目录中的条目名
+        fn list_dir(&self, path: &str) -> std::io::Result<Vec<String>>;
+
+        /// 读取符号链接的目标
+        fn read_link(&self, path: &str) -> std::io::Result<PathBuf>;
+
+        /// 路径是否存在
+        fn exists(&self, path: &str) -> bool;
+
+        /// 执行命令并返回标准输出，命令不存在或退出码非零时返回 `CommandFailed`
+        fn run_command(&self, program: &str, args: &[&str]) -> Result<String, HardwareError>;
+
+        /// 读取 HKEY_LOCAL_MACHINE 下的字符串值，非 Windows 平台返回 None
+        fn read_registry(&self, _key: &str, _value: &str) -> Option<String> {
+            None
+        }
+
+        /// CPUID hypervisor 位置位时返回 0x40000000 的厂商签名，否则返回 None
+        fn cpuid_hypervisor(&self) -> Option<String> {
+            None
+        }
+
+        /// 通过 rtnetlink 选出主网卡MAC；查询本身失败时返回错误
+        fn netlink_primary_mac(&self) -> std::io::Result<Option<String>> {
+            Err(std::io::Error::from(std::io::ErrorKind::Unsupported))
+        }
+    }
+
+    /// 直接读取本机的数据源
+    #[derive(Debug, Clone, Copy, Default)]
+    pub struct SystemSource;
+
+    impl HardwareSource for SystemSource {
+        fn read_file(&self, path: &str) -> std::io::Result<String> {
+            std::fs::read_to_string(path)
+        }
+
+        fn list_dir(&self, path: &str) -> std::io::Result<Vec<String>> {
+            Ok(std::fs::read_dir(path)?
+                .flatten()
+                .map(|entry| entry.file_name().to_string_lossy().to_string())
+                .collect())
+        }
+
+        fn read_link(&self, path: &str) -> std::io::Result<PathBuf> {
+            std::fs::read_link(path)
+        }
+
+        fn exists(&self, path: &str) -> bool {
+            Path::new(path).exists()
+        }
+
+        fn run_command(&self, program: &str, args: &[&str]) -> Result<String, HardwareError> {
+            let output = Command::new(program)
+                .args(args)
+                .output()
+                .map_err(|e| HardwareError::CommandFailed(e.to_string()))?;
+
+            if !output.status.success() {
+                return Err(HardwareError::CommandFailed(format!(
+                    "{} command failed",
+                    program
+                )));
+            }
+            Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+        }
+
+        #[cfg(target_os = "windows")]
+        fn read_registry(&self, key: &str, value: &str) -> Option<String> {
+            use winreg::RegKey;
+            use winreg::enums::*;
+
+            RegKey::predef(HKEY_LOCAL_MACHINE)
+                .open_subkey(key)
+                .and_then(|k| k.get_value::<String, _>(value))
+                .ok()
+        }
+
+        #[cfg(target_os = "linux")]
+        fn netlink_primary_mac(&self) -> std::io::Result<Option<String>> {
+            netlink_links().map(|links| select_netlink_mac(&links))
+        }
+
+        fn cpuid_hypervisor(&self) -> Option<String> {
+            native_cpuid_hypervisor()
+        }
+    }
+
+    /// 基于预置数据的数据源，用于测试解析逻辑
+    ///
+    /// 命令按完整命令行匹配，例如 `"dmidecode -t 1"`；
+    /// 目录由已登记的文件和链接路径推导。
+    #[derive(Debug, Clone, Default)]
+    pub struct FixtureSource {
+        files: BTreeMap<String, String>,
+        links: BTreeMap<String, String>,
+        commands: BTreeMap<String, String>,
+        registry: BTreeMap<String, String>,
+        netlink_mac: Option<Option<String>>,
+        cpuid_hypervisor: Option<String>,
+    }
+
+    impl FixtureSource {
+        pub fn new() -> Self {
+            Self::default()
+        }
+
+        /// 登记文件内容
+        pub fn with_file(mut self, path: &str, content: &str) -> Self {
+            self.files.insert(path.to_string(), content.to_string());
+            self
+        }
+
+        /// 登记符号链接
+        pub fn with_link(mut self, path: &str, target: &str) -> Self {
+            self.links.insert(path.to_string(), target.to_string());
+            self
+        }
+
+        /// 登记命令的标准输出
+        pub fn with_command(mut self, command_line: &str, stdout: &str) -> Self {
+            self.commands
+                .insert(command_line.to_string(), stdout.to_string());
+            self
+        }
+
+        /// 登记注册表值
+        pub fn with_registry(mut self, key: &str, value: &str, data: &str) -> Self {
+            self.registry
+                .insert(format!("{}\\{}", key, value), data.to_string());
+            self
+        }
+
+        /// 登记 netlink 查询结果，None 表示查询成功但没有合适的网卡
+        pub fn with_netlink_mac(mut self, mac: Option<&str>) -> Self {
+            self.netlink_mac = Some(mac.map(|m| m.to_string()));
+            self
+        }
+
+        /// 登记 CPUID 厂商签名，表示 hypervisor 位已置位
+        pub fn with_cpuid_hypervisor(mut self, signature: &str) -> Self {
+            self.cpuid_hypervisor = Some(signature.to_string());
+            self
+        }
+
+        fn not_found(path: &str) -> std::io::Error {
+            std::io::Error::new(std::io::ErrorKind::NotFound, path.to_string())
+        }
+
+        fn paths(&self) -> impl Iterator<Item = &String> {
+            self.files.keys().chain(self.links.keys())
+        }
+    }
+
+    impl HardwareSource for FixtureSource {
+        fn read_file(&self, path: &str) -> std::io::Result<String> {
+            self.files
+                .get(path)
+                .cloned()
+                .ok_or_else(|| Self::not_found(path))
+        }
+
+        fn list_dir(&self, path: &str) -> std::io::Result<Vec<String>> {
+            let prefix = format!("{}/", path.trim_end_matches('/'));
+            let mut names: Vec<String> = self
+                .paths()
+                .filter_map(|p| p.strip_prefix(&prefix))
+                .filter_map(|rest| rest.split('/').next())
+                .map(|name| name.to_string())
+                .collect();
+            if names.is_empty() {
+                return Err(Self::not_found(path));
+            }
+            names.sort();
+            names.dedup();
+            Ok(names)
+        }
+
+        fn read_link(&self, path: &str) -> std::io::Result<PathBuf> {
+            self.links
+                .get(path)
+                .map(PathBuf::from)
+                .ok_or_else(|| Self::not_found(path))
+        }
+
+        fn exists(&self, path: &str) -> bool {
+            let prefix = format!("{}/", path.trim_end_matches('/'));
+            self.paths().any(|p| p == path || p.starts_with(&prefix))
+        }
+
+        fn run_command(&self, program: &str, args: &[&str]) -> Result<String, HardwareError> {
+            let command_line = std::iter::once(program)
+                .chain(args.iter().copied())
+                .collect::<Vec<_>>()
+                .join(" ");
+            self.commands
+                .get(&command_line)
+                .cloned()
+                .ok_or_else(|| HardwareError::CommandFailed(format!("{}: not found", command_line)))
+        }
+
+        fn read_registry(&self, key: &str, value: &str) -> Option<String> {
+            self.registry.get(&format!("{}\\{}", key, value)).cloned()
+        }
+
+        fn cpuid_hypervisor(&self) -> Option<String> {
+            self.cpuid_hypervisor.clone()
+        }
+
+        fn netlink_primary_mac(&self) -> std::io::Result<Option<String>> {
+            self.netlink_mac
+                .clone()
+                .ok_or_else(|| std::io::Error::from(std::io::ErrorKind::Unsupported))
+        }
+    }
+
+    // 主板型号、厂商、BIOS 版本和 pciconf 的芯片组名称在同型号机器上完全相同，不能用作设备ID
+    fn is_unique_board_identifier(value: &SourcedValue) -> bool {
+        if is_placeholder(&value.value) {
+            return false;
+        }
+        match &value.source {
+            ValueSource::File(path) => {
+                !path.ends_with("/board_name") && !path.ends_with("/board_vendor")
+            }
+            ValueSource::Registry(path) => {
+                !["BaseBoardProduct", "SystemProductName", "BIOSVersion"]
+                    .iter()
+                    .any(|name| path.ends_with(&format!("\\{}", name)))
+            }
+            ValueSource::Command(command) => !command.starts_with("pciconf"),
+            _ => true,
+        }
+    }
+
+    // v2 设备ID只使用所有用户都能读取的来源，否则 root 服务和普通用户进程会得到不同的ID：
+    // DMI 的 UUID 和序列号文件只有 root 可读，dmidecode 和 lshw 也需要 root 权限
+    fn readable_by_all_users(value: &SourcedValue) -> bool {
+        match &value.source {
+            ValueSource::File(path) => !path.starts_with("/sys/class/dmi/id/"),
+            ValueSource::Command(command) => {
+                !command.starts_with("dmidecode") && !command.starts_with("lshw")
+            }
+            _ => true,
+        }
     }
 
     pub struct HardwareInfo;
@@ -48,26 +490,175 @@ pub mod devices_id {
             Ok(hashed_id)
         }
 
+        /// 使用应用专属的盐（命名空间）派生设备ID，格式为 `v2:<64位十六进制>`
+        ///
+        /// 以盐为密钥对硬件信息做 HMAC-SHA256，不同产品使用不同的盐时，
+        /// 同一台机器得到的ID互不相关。输出格式固定，升级库版本不会改变结果。
+        pub fn get_device_id_with_salt(salt: &[u8]) -> Result<String, HardwareError> {
+            Self::get_device_id_with_salt_from(&SystemSource, salt)
+        }
+
+        /// 从指定数据源派生 v2 设备ID
+        pub fn get_device_id_with_salt_from(
+            source: &dyn HardwareSource,
+            salt: &[u8],
+        ) -> Result<String, HardwareError> {
+            let material = Self::device_id_material_from(source)?;
+            Self::derive_device_id(salt, &material)
+        }
+
+        /// 由硬件信息派生 v2 设备ID，`get_device_id_with_salt` 的纯计算部分
+        pub fn derive_device_id(salt: &[u8], material: &str) -> Result<String, HardwareError> {
+            if salt.is_empty() {
+                return Err(HardwareError::InvalidSalt);
+            }
+
+            let mut message = Vec::with_capacity(DEVICE_ID_V2_CONTEXT.len() + material.len());
+            message.extend_from_slice(DEVICE_ID_V2_CONTEXT);
+            message.extend_from_slice(material.as_bytes());
+            let digest = hmac(HashAlgorithm::Sha256, salt, &message)
+                .map_err(|e| HardwareError::DerivationFailed(e.to_string()))?;
+
+            Ok(format!("{}{}", DEVICE_ID_V2_PREFIX, digest.to_hex()))
+        }
+
+        /// v2 设备ID的输入：优先系统UUID或序列号，否则为CPU信息+MAC地址，带类型前缀避免混淆
+        ///
+        /// 主板型号、厂商等同型号机器完全相同的值不会被使用。只使用所有用户都能读取的来源，
+        /// 同一台机器上 root 服务和普通用户进程得到相同的ID：Linux 的 product_uuid
+        /// 和序列号文件只有 root 可读，因此 Linux 上只有设备树序列号会被使用，
+        /// 其余情况使用CPU信息+MAC地址。
+        pub fn device_id_material_from(
+            source: &dyn HardwareSource,
+        ) -> Result<String, HardwareError> {
+            // query_motherboard_id_from 在 root 下会先返回 DMI 值，这里直接读取设备树序列号
+            #[cfg(target_os = "linux")]
+            let board = source
+                .read_file(DEVICETREE_SERIAL_PATH)
+                .ok()
+                .map(|content| {
+                    SourcedValue::new(
+                        content.trim().trim_matches('\0'),
+                        ValueSource::File(DEVICETREE_SERIAL_PATH.into()),
+                    )
+                });
+            #[cfg(not(target_os = "linux"))]
+            let board = Self::query_motherboard_id_from(source).ok();
+
+            if let Some(board) = board
+                && is_unique_board_identifier(&board)
+                && readable_by_all_users(&board)
+            {
+                return Ok(format!("board:{}", board.value.trim()));
+            }
+
+            // Windows 注册表中只有型号，系统UUID要通过 WMI 获取
+            #[cfg(target_os = "windows")]
+            if let Ok(stdout) = source.run_command("wmic", &["csproduct", "get", "uuid"])
+                && let Some(uuid) = parse_wmic_value(&stdout)
+                && !is_placeholder(&uuid)
+            {
+                return Ok(format!("board:{}", uuid.trim()));
+            }
+
+            let cpu_info = Self::query_cpu_info_from(source)?.value;
+            let mac_address = Self::query_primary_mac_from(source)?.value;
+            Ok(format!(
+                "cpu:{}\nmac:{}",
+                cpu_info.trim(),
+                mac_address.to_ascii_lowercase()
+            ))
+        }
+
+        /// 检测当前是否运行在虚拟机或容器中
+        pub fn detect_virtualization() -> VirtualizationInfo {
+            detect_virtualization_from(&SystemSource)
+        }
+
+        /// 按虚拟化策略派生 v2 设备ID
+        ///
+        /// `MixInstance` 在虚拟化环境中混入实例相关的信息（machine-id 或 MachineGuid，
+        /// 设备ID输入中还没有MAC时再加入主网卡MAC），克隆出的虚拟机重新生成这些值后得到不同的ID；
+        /// `Refuse` 在可信度达到 `Medium` 时返回 `VirtualizedEnvironment`。
+        pub fn get_device_id_with_policy(
+            salt: &[u8],
+            policy: VirtualizationPolicy,
+        ) -> Result<String, HardwareError> {
+            Self::get_device_id_with_policy_from(&SystemSource, salt, policy)
+        }
+
+        /// 从指定数据源按虚拟化策略派生 v2 设备ID
+        pub fn get_device_id_with_policy_from(
+            source: &dyn HardwareSource,
+            salt: &[u8],
+            policy: VirtualizationPolicy,
+        ) -> Result<String, HardwareError> {
+            let virtualization = detect_virtualization_from(source);
+            let mut material = Self::device_id_material_from(source)?;
+
+            match policy {
+                VirtualizationPolicy::Allow => {}
+                VirtualizationPolicy::Refuse => {
+                    if virtualization.is_virtualized(Confidence::Medium) {
+                        return Err(HardwareError::VirtualizedEnvironment(
+                            virtualization.evidence.join("; "),
+                        ));
+                    }
+                }
+                VirtualizationPolicy::MixInstance => {
+                    if virtualization.is_virtualized(Confidence::Medium) {
+                        // CPU信息+MAC地址的输入中已经有MAC，不重复加入
+                        if material.starts_with("board:")
+                            && let Ok(mac) = Self::query_primary_mac_from(source)
+                        {
+                            material.push_str(&format!(
+                                "\ninstance-mac:{}",
+                                mac.value.to_ascii_lowercase()
+                            ));
+                        }
+                        if let Some(instance_id) = instance_id_from(source) {
+                            material.push_str(&format!("\ninstance-id:{}", instance_id));
+                        }
+                    }
+                }
+            }
+
+            Self::derive_device_id(salt, &material)
+        }
+
         /// 获取主板ID
         pub fn get_motherboard_id() -> Result<String, HardwareError> {
+            Self::query_motherboard_id().map(|v| v.value)
+        }
+
+        /// 获取主板ID，并记录数据来源
+        pub fn query_motherboard_id() -> Result<SourcedValue, HardwareError> {
+            Self::query_motherboard_id_from(&SystemSource)
+        }
+
+        /// 从指定数据源获取主板ID
+        #[allow(unused_variables)]
+        pub fn query_motherboard_id_from(
+            source: &dyn HardwareSource,
+        ) -> Result<SourcedValue, HardwareError> {
             #[cfg(target_os = "windows")]
             {
-                return Self::get_windows_motherboard_id();
+                return Self::get_windows_motherboard_id(source);
             }
 
             #[cfg(target_os = "linux")]
             {
-                return Self::get_linux_motherboard_id();
+                return Self::get_linux_motherboard_id(source);
             }
 
             #[cfg(target_os = "macos")]
             {
-                return Self::get_macos_motherboard_id();
+                return Self::get_macos_motherboard_id(source);
             }
 
             #[cfg(target_os = "freebsd")]
             {
-                return Self::get_freebsd_motherboard_id();
+                return Self::get_freebsd_motherboard_id(source);
             }
 
             #[cfg(not(any(
@@ -81,76 +672,61 @@ pub mod devices_id {
             }
         }
 
-        /// 获取CPU信息
+        /// 获取CPU信息（型号名称）
+        ///
+        /// 结果参与设备ID的计算，保持为型号字符串；完整信息见 `get_cpu_details`。
         pub fn get_cpu_info() -> Result<String, HardwareError> {
-            #[cfg(target_os = "windows")]
-            {
-                let output = Command::new("wmic")
-                    .args(&["cpu", "get", "Name"])
-                    .output()
-                    .map_err(|e| HardwareError::CommandFailed(e.to_string()))?;
-
-                if !output.status.success() {
-                    return Err(HardwareError::CommandFailed(
-                        "WMIC command failed".parse().unwrap(),
-                    ));
-                }
+            Self::query_cpu_info().map(|v| v.value)
+        }
 
-                let stdout = String::from_utf8_lossy(&output.stdout);
-                let lines: Vec<&str> = stdout.lines().map(|l| l.trim()).collect();
+        /// 获取CPU信息，并记录数据来源
+        pub fn query_cpu_info() -> Result<SourcedValue, HardwareError> {
+            Self::query_cpu_info_from(&SystemSource)
+        }
 
-                if lines.len() >= 2 {
-                    return Ok(lines[1].to_string());
-                }
+        /// 从指定数据源获取CPU信息
+        #[allow(unused_variables)]
+        pub fn query_cpu_info_from(
+            source: &dyn HardwareSource,
+        ) -> Result<SourcedValue, HardwareError> {
+            #[cfg(target_os = "windows")]
+            {
+                let stdout = source.run_command("wmic", &["cpu", "get", "Name"])?;
+                let name = parse_wmic_value(&stdout).ok_or(HardwareError::IdNotFound)?;
 
-                Err(HardwareError::IdNotFound)
+                Ok(SourcedValue::new(
+                    name,
+                    ValueSource::Command("wmic cpu get Name".into()),
+                ))
             }
 
             #[cfg(target_os = "linux")]
             {
-                let content = std::fs::read_to_string("/proc/cpuinfo")?;
-                let model_line = content
-                    .lines()
-                    .find(|l| l.contains("model name"))
-                    .ok_or(HardwareError::IdNotFound)?;
+                let content = source.read_file(PROC_CPUINFO_PATH)?;
+                let model = parse_cpuinfo_model(&content).ok_or(HardwareError::IdNotFound)?;
 
-                let model = model_line
-                    .split(':')
-                    .nth(1)
-                    .map(|s| s.trim())
-                    .ok_or(HardwareError::InvalidFormat)?;
-
-                Ok(model.to_string())
+                Ok(SourcedValue::new(
+                    model,
+                    ValueSource::File(PROC_CPUINFO_PATH.into()),
+                ))
             }
 
             #[cfg(target_os = "macos")]
             {
-                let output = Command::new("sysctl")
-                    .args(&["-n", "machdep.cpu.brand_string"])
-                    .output()
-                    .map_err(|e| HardwareError::CommandFailed(e.to_string()))?;
-
-                if !output.status.success() {
-                    return Err(HardwareError::CommandFailed("sysctl command failed"));
-                }
-
-                let stdout = String::from_utf8_lossy(&output.stdout);
-                Ok(stdout.trim().to_string())
+                let stdout = source.run_command("sysctl", &["-n", "machdep.cpu.brand_string"])?;
+                Ok(SourcedValue::new(
+                    stdout.trim(),
+                    ValueSource::Command("sysctl -n machdep.cpu.brand_string".into()),
+                ))
             }
 
             #[cfg(target_os = "freebsd")]
             {
-                let output = Command::new("sysctl")
-                    .args(&["-n", "hw.model"])
-                    .output()
-                    .map_err(|e| HardwareError::CommandFailed(e.to_string()))?;
-
-                if !output.status.success() {
-                    return Err(HardwareError::CommandFailed("sysctl command failed"));
-                }
-
-                let stdout = String::from_utf8_lossy(&output.stdout);
-                Ok(stdout.trim().to_string())
+                let stdout = source.run_command("sysctl", &["-n", "hw.model"])?;
+                Ok(SourcedValue::new(
+                    stdout.trim(),
+                    ValueSource::Command("sysctl -n hw.model".into()),
+                ))
             }
 
             #[cfg(not(any(
@@ -164,101 +740,163 @@ pub mod devices_id {
             }
         }
 
-        /// 获取主网络接口的MAC地址
-        pub fn get_primary_mac() -> Result<String, HardwareError> {
-            #[cfg(target_os = "windows")]
+        /// 获取结构化的CPU信息
+        pub fn get_cpu_details() -> Result<CpuInfo, HardwareError> {
+            Self::get_cpu_details_from(&SystemSource)
+        }
+
+        /// 从指定数据源获取结构化的CPU信息
+        ///
+        /// Linux 上解析 /proc/cpuinfo 并用 /sys/devices/system/cpu 补充拓扑、频率和缓存；
+        /// 其他平台目前只有型号和逻辑处理器数量。
+        pub fn get_cpu_details_from(source: &dyn HardwareSource) -> Result<CpuInfo, HardwareError> {
+            #[cfg(target_os = "linux")]
             {
-                let output = Command::new("getmac")
-                    .args(&["/NH", "/FO", "CSV"]) // 无标题，CSV格式
-                    .output()
-                    .map_err(|e| HardwareError::CommandFailed(e.to_string()))?;
+                let content = source.read_file(PROC_CPUINFO_PATH)?;
+                let mut info = CpuInfo::parse_proc_cpuinfo(&content);
+                info.apply_sysfs(source, SYSFS_CPU_PATH);
+                Ok(info)
+            }
 
-                if !output.status.success() {
-                    return Err(HardwareError::CommandFailed(
-                        "getmac command failed".parse().unwrap(),
-                    ));
-                }
+            #[cfg(not(target_os = "linux"))]
+            {
+                let model_name = Self::query_cpu_info_from(source)?.value;
+                Ok(CpuInfo {
+                    model_name: Some(model_name),
+                    logical_processors: std::thread::available_parallelism()
+                        .map(|n| n.get() as u32)
+                        .unwrap_or(0),
+                    ..Default::default()
+                })
+            }
+        }
+
+        /// 是否允许在原生读取失败后调用外部命令（dmidecode、lshw、ip 等）
+        pub fn command_fallback_enabled() -> bool {
+            COMMAND_FALLBACK.load(Ordering::Relaxed)
+        }
+
+        /// 设置是否允许外部命令回退，默认允许；
+        /// 在没有这些工具的精简容器中可关闭，避免无意义的进程创建
+        pub fn set_command_fallback(enabled: bool) {
+            COMMAND_FALLBACK.store(enabled, Ordering::Relaxed);
+        }
 
-                let stdout = String::from_utf8_lossy(&output.stdout);
-                let first_line = stdout.lines().next().ok_or(HardwareError::IdNotFound)?;
+        /// 列出所有网络接口及其元数据（目前仅支持 Linux）
+        pub fn list_network_interfaces() -> Result<Vec<NetworkInterface>, HardwareError> {
+            Self::list_network_interfaces_from(&SystemSource)
+        }
 
-                // 提取MAC地址（CSV格式的第一个字段）
-                let mac = first_line
-                    .split(',')
-                    .next()
-                    .map(|s| s.trim_matches('"').replace("-", ""))
-                    .ok_or(HardwareError::InvalidFormat)?;
+        /// 从指定数据源列出所有网络接口
+        #[allow(unused_variables)]
+        pub fn list_network_interfaces_from(
+            source: &dyn HardwareSource,
+        ) -> Result<Vec<NetworkInterface>, HardwareError> {
+            #[cfg(target_os = "linux")]
+            {
+                read_network_interfaces_from(source, SYSFS_NET_PATH)
+            }
 
-                Ok(mac)
+            #[cfg(not(target_os = "linux"))]
+            {
+                Err(HardwareError::UnsupportedPlatform)
             }
+        }
 
+        /// 列出物理块设备（目前仅支持 Linux）
+        pub fn list_block_devices() -> Result<Vec<BlockDevice>, HardwareError> {
+            Self::list_block_devices_from(&SystemSource)
+        }
+
+        /// 从指定数据源列出物理块设备
+        #[allow(unused_variables)]
+        pub fn list_block_devices_from(
+            source: &dyn HardwareSource,
+        ) -> Result<Vec<BlockDevice>, HardwareError> {
             #[cfg(target_os = "linux")]
             {
-                // 尝试多个网络接口
-                let interfaces = vec!["eth0", "wlan0", "enp0s3", "en0"];
+                read_block_devices_from(source, SYSFS_BLOCK_PATH, UDEV_DATA_PATH)
+            }
 
-                for iface in interfaces {
-                    let path = format!("/sys/class/net/{}/address", iface);
-                    if let Ok(mac) = std::fs::read_to_string(&path) {
-                        let cleaned = mac.trim().replace(":", "");
-                        if !cleaned.is_empty() {
-                            return Ok(cleaned);
-                        }
-                    }
-                }
+            #[cfg(not(target_os = "linux"))]
+            {
+                Err(HardwareError::UnsupportedPlatform)
+            }
+        }
 
-                // 如果文件方法失败，尝试使用ip命令
-                let output = Command::new("ip")
-                    .args(&["link", "show"])
-                    .output()
-                    .map_err(|e| HardwareError::CommandFailed(e.to_string()))?;
+        /// 获取主网络接口的MAC地址
+        pub fn get_primary_mac() -> Result<String, HardwareError> {
+            Self::query_primary_mac().map(|v| v.value)
+        }
 
-                if output.status.success() {
-                    let stdout = String::from_utf8_lossy(&output.stdout);
-                    let re = Regex::new(r"link/ether\s+([0-9a-fA-F:]+)").unwrap();
+        /// 获取主网络接口的MAC地址，并记录数据来源
+        pub fn query_primary_mac() -> Result<SourcedValue, HardwareError> {
+            Self::query_primary_mac_from(&SystemSource)
+        }
 
-                    if let Some(caps) = re.captures(&*stdout) {
-                        if let Some(mac) = caps.get(1) {
-                            return Ok(mac.as_str().replace(":", ""));
-                        }
-                    }
-                }
+        /// 从指定数据源获取主网络接口的MAC地址
+        #[allow(unused_variables)]
+        pub fn query_primary_mac_from(
+            source: &dyn HardwareSource,
+        ) -> Result<SourcedValue, HardwareError> {
+            #[cfg(target_os = "windows")]
+            {
+                // 无标题，CSV格式
+                let stdout = source.run_command("getmac", &["/NH", "/FO", "CSV"])?;
+                let mac = parse_getmac_csv(&stdout).ok_or(HardwareError::IdNotFound)?;
 
-                Err(HardwareError::IdNotFound)
+                Ok(SourcedValue::new(
+                    mac,
+                    ValueSource::Command("getmac /NH /FO CSV".into()),
+                ))
             }
 
-            #[cfg(target_os = "macos")]
+            #[cfg(target_os = "linux")]
             {
-                let output = Command::new("ifconfig")
-                    .arg("en0")
-                    .output()
-                    .map_err(|e| HardwareError::CommandFailed(e.to_string()))?;
+                // 枚举 sysfs 中的所有网卡，按策略选出主网卡，排除虚拟网卡和随机地址
+                if let Ok(interfaces) = Self::list_network_interfaces_from(source)
+                    && let Some(iface) = select_primary_interface(&interfaces)
+                    && let Some(mac) = &iface.mac
+                {
+                    return Ok(SourcedValue::new(
+                        mac.replace(":", ""),
+                        ValueSource::File(format!("{}/{}/address", SYSFS_NET_PATH, iface.name)),
+                    ));
+                }
 
-                if output.status.success() {
-                    let stdout = String::from_utf8_lossy(&output.stdout);
-                    let re = Regex::new(r"ether\s+([0-9a-fA-F:]+)").unwrap();
+                // sysfs 不可用（部分容器未挂载）或没有合格网卡时通过 rtnetlink 查询
+                if let Ok(Some(mac)) = source.netlink_primary_mac() {
+                    return Ok(SourcedValue::new(mac, ValueSource::Netlink));
+                }
 
-                    if let Some(caps) = re.captures(stdout) {
-                        if let Some(mac) = caps.get(1) {
-                            return Ok(mac.as_str().replace(":", ""));
-                        }
-                    }
+                // 容器中通常只有随机地址的 veth 网卡，上面两种方式都会排除它；
+                // 与旧版本一致，最后由 ip 命令取第一块以太网卡的地址
+
+                if !Self::command_fallback_enabled() {
+                    return Err(HardwareError::IdNotFound);
                 }
 
-                // 尝试en1接口
-                let output = Command::new("ifconfig")
-                    .arg("en1")
-                    .output()
-                    .map_err(|e| HardwareError::CommandFailed(e.to_string()))?;
+                // 最后尝试使用ip命令
+                let stdout = source.run_command("ip", &["link", "show"])?;
+                let mac = parse_ip_link_mac(&stdout).ok_or(HardwareError::IdNotFound)?;
 
-                if output.status.success() {
-                    let stdout = String::from_utf8_lossy(&output.stdout);
-                    let re = Regex::new(r"ether\s+([0-9a-fA-F:]+)").unwrap();
+                Ok(SourcedValue::new(
+                    mac,
+                    ValueSource::Command("ip link show".into()),
+                ))
+            }
 
-                    if let Some(caps) = re.captures(stdout) {
-                        if let Some(mac) = caps.get(1) {
-                            return Ok(mac.as_str().replace(":", ""));
-                        }
+            #[cfg(target_os = "macos")]
+            {
+                // 依次尝试en0、en1接口
+                for iface in ["en0", "en1"] {
+                    if let Ok(stdout) = source.run_command("ifconfig", &[iface])
+                        && let Some(mac) = parse_ifconfig_mac(&stdout)
+                    {
+                        return Ok(SourcedValue::new(
+                            mac,
+                            ValueSource::Command(format!("ifconfig {}", iface)),
+                        ));
                     }
                 }
 
@@ -267,23 +905,13 @@ pub mod devices_id {
 
             #[cfg(target_os = "freebsd")]
             {
-                let output = Command::new("ifconfig")
-                    .arg("em0")
-                    .output()
-                    .map_err(|e| HardwareError::CommandFailed(e.to_string()))?;
-
-                if output.status.success() {
-                    let stdout = String::from_utf8_lossy(&output.stdout);
-                    let re = Regex::new(r"lladdr\s+([0-9a-fA-F:]+)").unwrap();
-
-                    if let Some(caps) = re.captures(stdout) {
-                        if let Some(mac) = caps.get(1) {
-                            return Ok(mac.as_str().replace(":", ""));
-                        }
-                    }
-                }
+                let stdout = source.run_command("ifconfig", &["em0"])?;
+                let mac = parse_ifconfig_mac(&stdout).ok_or(HardwareError::IdNotFound)?;
 
-                Err(HardwareError::IdNotFound)
+                Ok(SourcedValue::new(
+                    mac,
+                    ValueSource::Command("ifconfig em0".into()),
+                ))
             }
 
             #[cfg(not(any(
@@ -299,13 +927,9 @@ pub mod devices_id {
 
         // Windows平台获取主板ID的具体实现，用cfg标记
         #[cfg(target_os = "windows")]
-        fn get_windows_motherboard_id() -> Result<String, HardwareError> {
-            use winreg::RegKey;
-            use winreg::enums::*;
-
-            // 尝试从注册表获取
-            let hklm = RegKey::predef(HKEY_LOCAL_MACHINE);
-
+        fn get_windows_motherboard_id(
+            source: &dyn HardwareSource,
+        ) -> Result<SourcedValue, HardwareError> {
             // 尝试不同的注册表路径
             let possible_paths = vec![
                 "HARDWARE\\DESCRIPTION\\System\\BIOS",
@@ -320,43 +944,36 @@ pub mod devices_id {
                 "BIOSVersion",
             ];
 
+            // 尝试从注册表获取
             for path in possible_paths {
-                if let Ok(key) = hklm.open_subkey(path) {
-                    for value_name in &possible_values {
-                        if let Ok(id) = key.get_value::<String, _>(value_name) {
-                            let cleaned = id.trim().to_string();
-                            if !cleaned.is_empty() && cleaned != "To be filled by O.E.M." {
-                                return Ok(cleaned);
-                            }
+                for value_name in &possible_values {
+                    if let Some(id) = source.read_registry(path, value_name) {
+                        let cleaned = id.trim().to_string();
+                        if !cleaned.is_empty() && cleaned != "To be filled by O.E.M." {
+                            return Ok(SourcedValue::new(
+                                cleaned,
+                                ValueSource::Registry(format!("HKLM\\{}\\{}", path, value_name)),
+                            ));
                         }
                     }
                 }
             }
 
             // 如果注册表方法失败，尝试使用WMIC命令
-            let output = Command::new("wmic")
-                .args(&["csproduct", "get", "uuid"])
-                .output()
-                .map_err(|e| HardwareError::CommandFailed(e.to_string()))?;
-
-            if output.status.success() {
-                let stdout = String::from_utf8_lossy(&output.stdout);
-                let lines: Vec<&str> = stdout.lines().map(|l| l.trim()).collect();
-
-                if lines.len() >= 2 {
-                    let uuid = lines[1].to_string();
-                    if !uuid.is_empty() && uuid != "UUID" {
-                        return Ok(uuid);
-                    }
-                }
-            }
+            let stdout = source.run_command("wmic", &["csproduct", "get", "uuid"])?;
+            let uuid = parse_wmic_value(&stdout).ok_or(HardwareError::IdNotFound)?;
 
-            Err(HardwareError::IdNotFound)
+            Ok(SourcedValue::new(
+                uuid,
+                ValueSource::Command("wmic csproduct get uuid".into()),
+            ))
         }
 
         // Linux平台获取主板ID的具体实现
         #[cfg(target_os = "linux")]
-        fn get_linux_motherboard_id() -> Result<String, HardwareError> {
+        fn get_linux_motherboard_id(
+            source: &dyn HardwareSource,
+        ) -> Result<SourcedValue, HardwareError> {
             // 尝试读取多个可能的DMI信息文件
             let possible_paths = vec![
                 "/sys/class/dmi/id/product_uuid",
@@ -364,68 +981,40 @@ pub mod devices_id {
                 "/sys/class/dmi/id/product_serial",
                 "/sys/class/dmi/id/board_name",
                 "/sys/class/dmi/id/board_vendor",
+                DEVICETREE_SERIAL_PATH,
             ];
 
             for path in possible_paths {
-                if let Ok(content) = std::fs::read_to_string(path) {
-                    let id = content.trim().to_string();
+                if let Ok(content) = source.read_file(path) {
+                    let id = content.trim().trim_matches('\0').to_string();
                     if !id.is_empty() && id != "0" && id != "None" && id != "Default string" {
-                        return Ok(id);
+                        return Ok(SourcedValue::new(id, ValueSource::File(path.into())));
                     }
                 }
             }
 
-            // 如果文件读取失败，尝试使用dmidecode命令
-            let output = Command::new("dmidecode")
-                .args(&["-t", "1"]) // 1表示系统信息
-                .output();
-
-            if let Ok(output) = output {
-                if output.status.success() {
-                    let stdout = String::from_utf8_lossy(&output.stdout);
-
-                    // 尝试提取序列号
-                    if let Some(line) = stdout.lines().find(|l| l.contains("Serial Number:")) {
-                        let parts: Vec<&str> = line.splitn(2, ':').collect();
-                        if parts.len() > 1 {
-                            let serial = parts[1].trim().to_string();
-                            if !serial.is_empty() && serial != "None" {
-                                return Ok(serial);
-                            }
-                        }
-                    }
+            if !Self::command_fallback_enabled() {
+                return Err(HardwareError::IdNotFound);
+            }
 
-                    // 尝试提取UUID
-                    if let Some(line) = stdout.lines().find(|l| l.contains("UUID:")) {
-                        let parts: Vec<&str> = line.splitn(2, ':').collect();
-                        if parts.len() > 1 {
-                            let uuid = parts[1].trim().to_string();
-                            if !uuid.is_empty() && uuid != "00000000-0000-0000-0000-000000000000" {
-                                return Ok(uuid);
-                            }
-                        }
-                    }
-                }
+            // 如果文件读取失败，尝试使用dmidecode命令（1表示系统信息）
+            if let Ok(stdout) = source.run_command("dmidecode", &["-t", "1"])
+                && let Some(id) = parse_dmidecode_system(&stdout)
+            {
+                return Ok(SourcedValue::new(
+                    id,
+                    ValueSource::Command("dmidecode -t 1".into()),
+                ));
             }
 
             // 最后尝试使用lshw命令
-            let output = Command::new("lshw").args(&["-C", "system"]).output();
-
-            if let Ok(output) = output {
-                if output.status.success() {
-                    let stdout = String::from_utf8_lossy(&output.stdout);
-
-                    // 尝试提取序列号
-                    if let Some(line) = stdout.lines().find(|l| l.contains("serial:")) {
-                        let parts: Vec<&str> = line.splitn(2, ':').collect();
-                        if parts.len() > 1 {
-                            let serial = parts[1].trim().to_string();
-                            if !serial.is_empty() && serial != "None" {
-                                return Ok(serial);
-                            }
-                        }
-                    }
-                }
+            if let Ok(stdout) = source.run_command("lshw", &["-C", "system"])
+                && let Some(serial) = parse_lshw_serial(&stdout)
+            {
+                return Ok(SourcedValue::new(
+                    serial,
+                    ValueSource::Command("lshw -C system".into()),
+                ));
             }
 
             Err(HardwareError::IdNotFound)
@@ -433,144 +1022,3170 @@ pub mod devices_id {
 
         // macOS平台获取主板ID的具体实现
         #[cfg(target_os = "macos")]
-        fn get_macos_motherboard_id() -> Result<String, HardwareError> {
+        fn get_macos_motherboard_id(
+            source: &dyn HardwareSource,
+        ) -> Result<SourcedValue, HardwareError> {
             // 使用system_profiler命令获取硬件信息
-            let output = Command::new("system_profiler")
-                .args(&["SPHardwareDataType", "-detailLevel", "full"])
-                .output()
-                .map_err(|e| HardwareError::CommandFailed(e.to_string()))?;
+            let stdout = source.run_command(
+                "system_profiler",
+                &["SPHardwareDataType", "-detailLevel", "full"],
+            )?;
+            let id = parse_system_profiler(&stdout).ok_or(HardwareError::IdNotFound)?;
 
-            if !output.status.success() {
-                return Err(HardwareError::CommandFailed(
-                    "system_profiler command failed",
+            Ok(SourcedValue::new(
+                id,
+                ValueSource::Command("system_profiler SPHardwareDataType".into()),
+            ))
+        }
+
+        // FreeBSD平台获取主板ID的具体实现
+        #[cfg(target_os = "freebsd")]
+        fn get_freebsd_motherboard_id(
+            source: &dyn HardwareSource,
+        ) -> Result<SourcedValue, HardwareError> {
+            // 使用dmidecode命令（如果安装）
+            if let Ok(stdout) = source.run_command("dmidecode", &["-t", "1"])
+                && let Some(serial) = parse_dmidecode_serial(&stdout)
+            {
+                return Ok(SourcedValue::new(
+                    serial,
+                    ValueSource::Command("dmidecode -t 1".into()),
                 ));
             }
 
-            let stdout = String::from_utf8_lossy(&output.stdout);
+            // 尝试使用pciconf命令
+            let stdout = source.run_command("pciconf", &["-lv"])?;
+            let board = parse_pciconf_host_bridge(&stdout).ok_or(HardwareError::IdNotFound)?;
 
-            // 尝试提取逻辑板序列号
-            if let Some(line) = stdout
-                .lines()
-                .find(|l| l.contains("Logic Board Serial Number:"))
-            {
-                let parts: Vec<&str> = line.splitn(2, ':').collect();
-                if parts.len() > 1 {
-                    let serial = parts[1].trim().to_string();
-                    if !serial.is_empty() {
-                        return Ok(serial);
+            Ok(SourcedValue::new(
+                board,
+                ValueSource::Command("pciconf -lv".into()),
+            ))
+        }
+
+        /// 哈希字符串生成固定长度的标识符
+        fn hash_string(s: &str) -> String {
+            use sha2::{Digest, Sha256};
+
+            let mut hasher = Sha256::new();
+            hasher.update(s.as_bytes());
+            let result = hasher.finalize();
+
+            hex::encode(result)
+        }
+    }
+
+    /// Linux sysfs 中CPU信息的默认位置
+    pub const SYSFS_CPU_PATH: &str = "/sys/devices/system/cpu";
+
+    /// 一级CPU缓存的描述（对应 sysfs `cache/indexN`）
+    #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+    pub struct CpuCache {
+        pub level: u32,
+        /// `Data`、`Instruction` 或 `Unified`
+        pub cache_type: String,
+        pub size_kib: u64,
+    }
+
+    /// 结构化的CPU信息，字段取不到时为空
+    #[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+    pub struct CpuInfo {
+        /// 例如 `GenuineIntel`、`AuthenticAMD`，ARM 为实现者代码
+        pub vendor: Option<String>,
+        pub model_name: Option<String>,
+        pub family: Option<u32>,
+        pub model: Option<u32>,
+        pub stepping: Option<u32>,
+        pub microcode: Option<String>,
+        pub sockets: Option<u32>,
+        pub physical_cores: Option<u32>,
+        /// 逻辑处理器（线程）数量
+        pub logical_processors: u32,
+        pub current_mhz: Option<f64>,
+        pub min_mhz: Option<f64>,
+        pub max_mhz: Option<f64>,
+        pub caches: Vec<CpuCache>,
+        pub flags: Vec<String>,
+    }
+
+    // 解析 `0x2f`、`47` 两种写法
+    fn parse_cpu_number(value: &str) -> Option<u32> {
+        match value.strip_prefix("0x") {
+            Some(hex) => u32::from_str_radix(hex, 16).ok(),
+            None => value.parse().ok(),
+        }
+    }
+
+    // 解析 sysfs 缓存大小，例如 `48K`、`2048K`、`16M`
+    fn parse_cache_size_kib(value: &str) -> Option<u64> {
+        let value = value.trim();
+        if let Some(k) = value.strip_suffix('K') {
+            k.parse().ok()
+        } else if let Some(m) = value.strip_suffix('M') {
+            m.parse::<u64>().ok().map(|m| m * 1024)
+        } else {
+            value.parse::<u64>().ok().map(|b| b / 1024)
+        }
+    }
+
+    // 解析 `0-3,8-11` 形式的CPU列表，返回CPU编号
+    fn parse_cpu_list(value: &str) -> Vec<u32> {
+        value
+            .trim()
+            .split(',')
+            .filter(|part| !part.is_empty())
+            .flat_map(|part| match part.split_once('-') {
+                Some((start, end)) => match (start.parse::<u32>(), end.parse::<u32>()) {
+                    (Ok(start), Ok(end)) if start <= end => (start..=end).collect(),
+                    _ => Vec::new(),
+                },
+                None => part.parse().map(|n| vec![n]).unwrap_or_default(),
+            })
+            .collect()
+    }
+
+    impl CpuInfo {
+        /// 解析 /proc/cpuinfo 内容，字段名兼容 x86 和 ARM
+        pub fn parse_proc_cpuinfo(content: &str) -> Self {
+            let mut info = CpuInfo {
+                model_name: parse_cpuinfo_model(content),
+                ..Default::default()
+            };
+
+            let mut packages = Vec::new();
+            let mut cores = Vec::new();
+            let mut current_package = None;
+            for line in content.lines() {
+                let Some((key, value)) = line.split_once(':') else {
+                    continue;
+                };
+                let value = value.trim();
+                match key.trim() {
+                    "processor" if value.parse::<u32>().is_ok() => {
+                        info.logical_processors += 1;
+                        current_package = None;
+                    }
+                    "vendor_id" | "CPU implementer" if info.vendor.is_none() => {
+                        info.vendor = Some(value.to_string());
+                    }
+                    "cpu family" | "CPU architecture" if info.family.is_none() => {
+                        info.family = parse_cpu_number(value);
                     }
+                    "model" | "CPU part" if info.model.is_none() => {
+                        info.model = parse_cpu_number(value);
+                    }
+                    "stepping" | "CPU revision" if info.stepping.is_none() => {
+                        info.stepping = parse_cpu_number(value);
+                    }
+                    "microcode" if info.microcode.is_none() => {
+                        info.microcode = Some(value.to_string());
+                    }
+                    "cpu MHz" if info.current_mhz.is_none() => {
+                        info.current_mhz = value.parse().ok();
+                    }
+                    "flags" | "Features" if info.flags.is_empty() => {
+                        info.flags = value.split_whitespace().map(|f| f.to_string()).collect();
+                    }
+                    "physical id" => {
+                        current_package = Some(value.to_string());
+                        if !packages.contains(&current_package) {
+                            packages.push(current_package.clone());
+                        }
+                    }
+                    "core id" => {
+                        let core = (current_package.clone(), value.to_string());
+                        if !cores.contains(&core) {
+                            cores.push(core);
+                        }
+                    }
+                    _ => {}
                 }
             }
 
-            // 尝试提取硬件UUID
-            if let Some(line) = stdout.lines().find(|l| l.contains("Hardware UUID:")) {
-                let parts: Vec<&str> = line.splitn(2, ':').collect();
-                if parts.len() > 1 {
-                    let uuid = parts[1].trim().to_string();
-                    if !uuid.is_empty() {
-                        return Ok(uuid);
-                    }
+            if !packages.is_empty() {
+                info.sockets = Some(packages.len() as u32);
+            }
+            if !cores.is_empty() {
+                info.physical_cores = Some(cores.len() as u32);
+            }
+            info
+        }
+
+        /// 用 sysfs 的拓扑、频率和缓存信息补充（覆盖）/proc/cpuinfo 的结果
+        pub fn apply_sysfs(&mut self, source: &dyn HardwareSource, root: &str) {
+            let read = |path: String| {
+                source
+                    .read_file(&path)
+                    .ok()
+                    .map(|s| s.trim().to_string())
+                    .filter(|s| !s.is_empty())
+            };
+            let khz_to_mhz = |path: String| read(path)?.parse::<f64>().ok().map(|khz| khz / 1000.0);
+
+            let online = read(format!("{}/online", root))
+                .map(|list| parse_cpu_list(&list))
+                .unwrap_or_default();
+            if !online.is_empty() {
+                self.logical_processors = online.len() as u32;
+            }
+
+            // 按 (封装, 核心) 去重统计物理核心
+            let mut packages = Vec::new();
+            let mut cores = Vec::new();
+            for cpu in &online {
+                let topology = format!("{}/cpu{}/topology", root, cpu);
+                let (Some(package), Some(core)) = (
+                    read(format!("{}/physical_package_id", topology)),
+                    read(format!("{}/core_id", topology)),
+                ) else {
+                    continue;
+                };
+                if !packages.contains(&package) {
+                    packages.push(package.clone());
+                }
+                if !cores.contains(&(package.clone(), core.clone())) {
+                    cores.push((package, core));
                 }
             }
+            if !packages.is_empty() {
+                self.sockets = Some(packages.len() as u32);
+                self.physical_cores = Some(cores.len() as u32);
+            }
 
-            // 尝试提取主板型号
-            if let Some(line) = stdout.lines().find(|l| l.contains("Model Identifier:")) {
-                let parts: Vec<&str> = line.splitn(2, ':').collect();
-                if parts.len() > 1 {
-                    let model = parts[1].trim().to_string();
-                    if !model.is_empty() {
-                        return Ok(model);
-                    }
+            let cpufreq = format!("{}/cpu0/cpufreq", root);
+            self.min_mhz = khz_to_mhz(format!("{}/cpuinfo_min_freq", cpufreq)).or(self.min_mhz);
+            self.max_mhz = khz_to_mhz(format!("{}/cpuinfo_max_freq", cpufreq)).or(self.max_mhz);
+            self.current_mhz =
+                khz_to_mhz(format!("{}/scaling_cur_freq", cpufreq)).or(self.current_mhz);
+            if let Some(microcode) = read(format!("{}/cpu0/microcode/version", root)) {
+                self.microcode = Some(microcode);
+            }
+
+            let cache_dir = format!("{}/cpu0/cache", root);
+            if let Ok(mut indexes) = source.list_dir(&cache_dir) {
+                indexes.retain(|name| name.starts_with("index"));
+                indexes.sort();
+                let caches: Vec<CpuCache> = indexes
+                    .iter()
+                    .filter_map(|index| {
+                        let dir = format!("{}/{}", cache_dir, index);
+                        Some(CpuCache {
+                            level: read(format!("{}/level", dir))?.parse().ok()?,
+                            cache_type: read(format!("{}/type", dir))?,
+                            size_kib: parse_cache_size_kib(&read(format!("{}/size", dir))?)?,
+                        })
+                    })
+                    .collect();
+                if !caches.is_empty() {
+                    self.caches = caches;
                 }
             }
+        }
 
-            Err(HardwareError::IdNotFound)
+        /// 是否支持某个指令集标志，例如 `avx2`、`aes`
+        pub fn has_flag(&self, flag: &str) -> bool {
+            self.flags.iter().any(|f| f == flag)
         }
+    }
 
-        // FreeBSD平台获取主板ID的具体实现
-        #[cfg(target_os = "freebsd")]
-        fn get_freebsd_motherboard_id() -> Result<String, HardwareError> {
-            // 使用dmidecode命令（如果安装）
-            let output = Command::new("dmidecode").args(&["-t", "1"]).output();
-
-            if let Ok(output) = output {
-                if output.status.success() {
-                    let stdout = String::from_utf8_lossy(&output.stdout);
-
-                    // 尝试提取序列号
-                    if let Some(line) = stdout.lines().find(|l| l.contains("Serial Number:")) {
-                        let parts: Vec<&str> = line.splitn(2, ':').collect();
-                        if parts.len() > 1 {
-                            let serial = parts[1].trim().to_string();
-                            if !serial.is_empty() && serial != "None" {
-                                return Ok(serial);
-                            }
-                        }
-                    }
+    impl std::fmt::Display for CpuInfo {
+        /// 单行摘要，例如 `Intel(R) Xeon(R) CPU (GenuineIntel, family 6 model 85 stepping 7, 8C/16T, 3800 MHz)`
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            write!(f, "{}", self.model_name.as_deref().unwrap_or("Unknown CPU"))?;
+
+            let mut details = Vec::new();
+            if let Some(vendor) = &self.vendor {
+                details.push(vendor.clone());
+            }
+            if let (Some(family), Some(model)) = (self.family, self.model) {
+                let mut id = format!("family {} model {}", family, model);
+                if let Some(stepping) = self.stepping {
+                    id.push_str(&format!(" stepping {}", stepping));
                 }
+                details.push(id);
+            }
+            match self.physical_cores {
+                Some(cores) => details.push(format!("{}C/{}T", cores, self.logical_processors)),
+                None if self.logical_processors > 0 => {
+                    details.push(format!("{}T", self.logical_processors))
+                }
+                None => {}
+            }
+            if let Some(mhz) = self.max_mhz.or(self.current_mhz) {
+                details.push(format!("{:.0} MHz", mhz));
             }
 
-            // 尝试使用pciconf命令
-            let output = Command::new("pciconf")
-                .args(&["-lv"])
-                .output()
-                .map_err(|e| HardwareError::CommandFailed(e.to_string()))?;
+            if !details.is_empty() {
+                write!(f, " ({})", details.join(", "))?;
+            }
+            Ok(())
+        }
+    }
 
-            if output.status.success() {
-                let stdout = String::from_utf8_lossy(&output.stdout);
+    /// Linux sysfs 中块设备的默认位置
+    pub const SYSFS_BLOCK_PATH: &str = "/sys/block";
 
-                // 尝试提取主板信息
-                if let Some(chunk) = stdout.split("\n\n").find(|c| c.contains("class=0x060000")) {
-                    if let Some(line) = chunk.lines().find(|l| l.contains("vendor=")) {
-                        if let Some(line2) = chunk.lines().find(|l| l.contains("device=")) {
-                            let vendor = line.split('=').nth(1).unwrap_or("").trim_matches('"');
-                            let device = line2.split('=').nth(1).unwrap_or("").trim_matches('"');
-                            let combined = format!("{}_{}", vendor, device);
+    /// udev 设备数据库的默认位置
+    pub const UDEV_DATA_PATH: &str = "/run/udev/data";
 
-                            if !combined.is_empty() {
-                                return Ok(combined);
-                            }
-                        }
-                    }
-                }
+    /// 一个物理块设备（磁盘）
+    #[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+    pub struct BlockDevice {
+        /// 内核设备名，例如 `sda`、`nvme0n1`
+        pub name: String,
+        pub model: Option<String>,
+        pub vendor: Option<String>,
+        pub serial: Option<String>,
+        /// World Wide Name，例如 `0x5002538e40a1b2c3` 或 `eui.0025388b71b2c3d4`
+        pub wwn: Option<String>,
+        pub size_bytes: u64,
+        /// 机械硬盘为 true
+        pub rotational: bool,
+        /// U盘、读卡器等可移动设备为 true
+        pub removable: bool,
+    }
+
+    impl BlockDevice {
+        /// 适合作为硬件标识的值：优先序列号，其次 WWN
+        pub fn identity(&self) -> Option<&str> {
+            self.serial.as_deref().or(self.wwn.as_deref())
+        }
+    }
+
+    /// 解析 udev 数据库条目中的 `E:KEY=VALUE` 属性
+    pub fn parse_udev_properties(content: &str) -> BTreeMap<String, String> {
+        content
+            .lines()
+            .filter_map(|line| line.strip_prefix("E:"))
+            .filter_map(|prop| prop.split_once('='))
+            .map(|(key, value)| (key.to_string(), value.trim().to_string()))
+            .collect()
+    }
+
+    /// 从指定数据源读取块设备
+    ///
+    /// 只返回有 `device` 链接的设备，loop、dm、zram、md 等虚拟设备被跳过；
+    /// udev 数据存在时优先使用其中的序列号、WWN 和型号。
+    pub fn read_block_devices_from(
+        source: &dyn HardwareSource,
+        root: &str,
+        udev_root: &str,
+    ) -> Result<Vec<BlockDevice>, HardwareError> {
+        let read = |path: String| {
+            source
+                .read_file(&path)
+                .ok()
+                .map(|s| s.trim().trim_matches('\0').to_string())
+                .filter(|s| !s.is_empty())
+        };
+
+        let mut devices = Vec::new();
+        for name in source.list_dir(root)? {
+            let dir = format!("{}/{}", root.trim_end_matches('/'), name);
+            if !source.exists(&format!("{}/device", dir)) {
+                continue;
             }
 
-            Err(HardwareError::IdNotFound)
+            let udev = read(format!("{}/dev", dir))
+                .and_then(|dev| read(format!("{}/b{}", udev_root, dev)))
+                .map(|content| parse_udev_properties(&content))
+                .unwrap_or_default();
+            let udev_value = |key: &str| udev.get(key).filter(|v| !v.is_empty()).cloned();
+
+            devices.push(BlockDevice {
+                model: udev_value("ID_MODEL")
+                    .map(|m| m.replace('_', " "))
+                    .or_else(|| read(format!("{}/device/model", dir))),
+                vendor: read(format!("{}/device/vendor", dir)).or_else(|| udev_value("ID_VENDOR")),
+                // SCSI/SATA 在 device/serial，virtio 直接在设备目录下
+                serial: udev_value("ID_SERIAL_SHORT")
+                    .or_else(|| read(format!("{}/device/serial", dir)))
+                    .or_else(|| read(format!("{}/serial", dir))),
+                wwn: udev_value("ID_WWN_WITH_EXTENSION")
+                    .or_else(|| udev_value("ID_WWN"))
+                    .or_else(|| read(format!("{}/device/wwid", dir)))
+                    .or_else(|| read(format!("{}/wwid", dir))),
+                // size 以 512 字节扇区为单位，与实际扇区大小无关
+                size_bytes: read(format!("{}/size", dir))
+                    .and_then(|s| s.parse::<u64>().ok())
+                    .map(|sectors| sectors * 512)
+                    .unwrap_or(0),
+                rotational: read(format!("{}/queue/rotational", dir)).as_deref() == Some("1"),
+                removable: read(format!("{}/removable", dir)).as_deref() == Some("1"),
+                name,
+            });
         }
 
-        /// 哈希字符串生成固定长度的标识符
-        fn hash_string(s: &str) -> String {
-            use sha2::{Digest, Sha256};
+        devices.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(devices)
+    }
 
-            let mut hasher = Sha256::new();
-            hasher.update(s.as_bytes());
-            let result = hasher.finalize();
+    /// 虚拟机类型
+    #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+    #[serde(rename_all = "snake_case")]
+    pub enum Hypervisor {
+        Kvm,
+        Qemu,
+        VMware,
+        VirtualBox,
+        HyperV,
+        Xen,
+        Parallels,
+        Bhyve,
+        /// 能确认是虚拟机但无法识别类型，或云厂商的自定义名称
+        Other(String),
+    }
 
-            hex::encode(result)
+    /// 容器类型
+    #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+    #[serde(rename_all = "snake_case")]
+    pub enum ContainerRuntime {
+        Docker,
+        Podman,
+        Kubernetes,
+        Lxc,
+        SystemdNspawn,
+        Other(String),
+    }
+
+    /// 检测结论的可信度，按从低到高排序
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+    #[serde(rename_all = "snake_case")]
+    pub enum Confidence {
+        /// 没有发现任何虚拟化迹象
+        None,
+        Low,
+        Medium,
+        High,
+    }
+
+    /// 运行环境检测结果
+    #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+    pub struct VirtualizationInfo {
+        pub hypervisor: Option<Hypervisor>,
+        pub container: Option<ContainerRuntime>,
+        /// 运行在虚拟化环境（虚拟机或容器）中的可信度
+        pub confidence: Confidence,
+        /// 每条命中的依据，便于排查误判
+        pub evidence: Vec<String>,
+    }
+
+    impl VirtualizationInfo {
+        pub fn is_virtual_machine(&self) -> bool {
+            self.hypervisor.is_some()
+        }
+
+        pub fn is_container(&self) -> bool {
+            self.container.is_some()
+        }
+
+        /// 可信度不低于 `min` 时视为虚拟化环境
+        pub fn is_virtualized(&self, min: Confidence) -> bool {
+            self.confidence != Confidence::None && self.confidence >= min
         }
     }
 
-    // 测试代码
-    #[test]
-    fn main() {
-        match HardwareInfo::get_device_id() {
-            Ok(id) => println!("Device ID: {}", id),
-            Err(e) => eprintln!("Error getting device ID: {}", e),
+    /// 设备ID在虚拟化环境中的处理方式
+    #[repr(C)]
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum VirtualizationPolicy {
+        /// 不做处理，与物理机相同
+        Allow = 0,
+        /// 混入实例相关的信息（machine-id、MachineGuid、MAC），避免克隆的镜像得到相同ID
+        MixInstance = 1,
+        /// 在虚拟化环境中拒绝生成ID
+        Refuse = 2,
+    }
+
+    // Windows 注册表中 SMBIOS 系统信息的位置（HKEY_LOCAL_MACHINE 下）
+    const WINDOWS_BIOS_KEY: &str = "HARDWARE\\DESCRIPTION\\System\\BIOS";
+
+    // 系统安装时生成的实例标识，克隆镜像后重新生成即可区分实例
+    const MACHINE_ID_PATHS: &[&str] = &["/etc/machine-id", "/var/lib/dbus/machine-id"];
+    const WINDOWS_CRYPTOGRAPHY_KEY: &str = "SOFTWARE\\Microsoft\\Cryptography";
+
+    // 读取实例标识：Linux 的 machine-id，Windows 的 MachineGuid
+    fn instance_id_from(source: &dyn HardwareSource) -> Option<String> {
+        MACHINE_ID_PATHS
+            .iter()
+            .filter_map(|path| source.read_file(path).ok())
+            .chain(source.read_registry(WINDOWS_CRYPTOGRAPHY_KEY, "MachineGuid"))
+            .map(|id| id.trim().to_ascii_lowercase())
+            .find(|id| !is_placeholder(id))
+    }
+
+    /// 按 DMI 厂商/产品字符串识别虚拟机
+    pub fn hypervisor_from_dmi(value: &str) -> Option<Hypervisor> {
+        let lower = value.to_ascii_lowercase();
+        let hypervisor = if lower.contains("kvm") {
+            Hypervisor::Kvm
+        } else if lower.contains("qemu") {
+            Hypervisor::Qemu
+        } else if lower.contains("vmware") {
+            Hypervisor::VMware
+        } else if lower.contains("virtualbox") || lower.contains("innotek") {
+            Hypervisor::VirtualBox
+        } else if lower.contains("xen") {
+            Hypervisor::Xen
+        } else if lower.contains("parallels") {
+            Hypervisor::Parallels
+        } else if lower.contains("bhyve") {
+            Hypervisor::Bhyve
+        } else if lower == "virtual machine" || lower.contains("hyper-v") {
+            Hypervisor::HyperV
+        } else if lower.contains("amazon ec2") || lower.contains("google compute engine") {
+            Hypervisor::Other(value.trim().to_string())
+        } else {
+            return None;
+        };
+        Some(hypervisor)
+    }
+
+    /// 按 CPUID 0x40000000 的厂商签名识别虚拟机
+    pub fn hypervisor_from_cpuid(signature: &str) -> Hypervisor {
+        match signature.trim_end_matches('\0') {
+            "KVMKVMKVM" => Hypervisor::Kvm,
+            "TCGTCGTCGTCG" => Hypervisor::Qemu,
+            "VMwareVMware" => Hypervisor::VMware,
+            "VBoxVBoxVBox" => Hypervisor::VirtualBox,
+            "Microsoft Hv" => Hypervisor::HyperV,
+            "XenVMMXenVMM" => Hypervisor::Xen,
+            " lrpepyh  vr" | "prl hyperv  " => Hypervisor::Parallels,
+            "bhyve bhyve " => Hypervisor::Bhyve,
+            other => Hypervisor::Other(other.trim().to_string()),
         }
+    }
 
-        match HardwareInfo::get_motherboard_id() {
-            Ok(id) => println!("Motherboard ID: {}", id),
-            Err(e) => eprintln!("Error getting motherboard ID: {}", e),
+    /// 按 /proc/1/cgroup 或 /run/systemd/container 的内容识别容器
+    pub fn container_from_text(text: &str) -> Option<ContainerRuntime> {
+        let lower = text.to_ascii_lowercase();
+        let runtime = if lower.contains("kubepods") {
+            ContainerRuntime::Kubernetes
+        } else if lower.contains("libpod") || lower.contains("podman") {
+            ContainerRuntime::Podman
+        } else if lower.contains("docker") {
+            ContainerRuntime::Docker
+        } else if lower.contains("lxc") {
+            ContainerRuntime::Lxc
+        } else if lower.contains("systemd-nspawn") || lower.contains("machine.slice") {
+            ContainerRuntime::SystemdNspawn
+        } else {
+            return None;
+        };
+        Some(runtime)
+    }
+
+    // 读取本机 CPUID 的 hypervisor 位和厂商签名，非 x86 平台返回 None
+    fn native_cpuid_hypervisor() -> Option<String> {
+        #[cfg(target_arch = "x86_64")]
+        {
+            use std::arch::x86_64::__cpuid;
+
+            // CPUID.1:ECX 第 31 位为 hypervisor 位，物理机上为 0
+            #[allow(unused_unsafe)]
+            let leaf1 = unsafe { __cpuid(1) };
+            if leaf1.ecx & (1 << 31) == 0 {
+                return None;
+            }
+            #[allow(unused_unsafe)]
+            let leaf = unsafe { __cpuid(0x4000_0000) };
+            let mut signature = Vec::with_capacity(12);
+            for reg in [leaf.ebx, leaf.ecx, leaf.edx] {
+                signature.extend_from_slice(&reg.to_le_bytes());
+            }
+            Some(String::from_utf8_lossy(&signature).into_owned())
         }
 
-        match HardwareInfo::get_cpu_info() {
-            Ok(info) => println!("CPU Info: {}", info),
-            Err(e) => eprintln!("Error getting CPU info: {}", e),
+        #[cfg(not(target_arch = "x86_64"))]
+        {
+            None
         }
+    }
 
-        match HardwareInfo::get_primary_mac() {
-            Ok(mac) => println!("Primary MAC: {}", mac),
-            Err(e) => eprintln!("Error getting MAC address: {}", e),
+    /// 从指定数据源检测虚拟机和容器
+    pub fn detect_virtualization_from(source: &dyn HardwareSource) -> VirtualizationInfo {
+        let read = |path: &str| {
+            source
+                .read_file(path)
+                .ok()
+                .map(|s| s.trim().to_string())
+                .filter(|s| !s.is_empty())
+        };
+
+        let mut info = VirtualizationInfo {
+            hypervisor: None,
+            container: None,
+            confidence: Confidence::None,
+            evidence: Vec::new(),
+        };
+        let raise = |info: &mut VirtualizationInfo, level: Confidence, evidence: String| {
+            info.confidence = info.confidence.max(level);
+            info.evidence.push(evidence);
+        };
+
+        // DMI 厂商字符串：克隆的虚拟机也会保留，可信度高
+        for path in [
+            "/sys/class/dmi/id/sys_vendor",
+            "/sys/class/dmi/id/product_name",
+            "/sys/class/dmi/id/board_vendor",
+            "/sys/class/dmi/id/bios_vendor",
+        ] {
+            if let Some(value) = read(path)
+                && let Some(hypervisor) = hypervisor_from_dmi(&value)
+            {
+                raise(&mut info, Confidence::High, format!("{}: {}", path, value));
+                info.hypervisor.get_or_insert(hypervisor);
+            }
+        }
+
+        // Windows 上 SMBIOS 信息在注册表中
+        for value_name in ["SystemManufacturer", "SystemProductName", "BIOSVendor"] {
+            if let Some(value) = source.read_registry(WINDOWS_BIOS_KEY, value_name)
+                && let Some(hypervisor) = hypervisor_from_dmi(&value)
+            {
+                raise(
+                    &mut info,
+                    Confidence::High,
+                    format!("registry {}: {}", value_name, value),
+                );
+                info.hypervisor.get_or_insert(hypervisor);
+            }
+        }
+        let confirmed = info.hypervisor.is_some();
+
+        // 开启 VBS/Hyper-V 的物理 Windows 主机运行在根分区中，同样会置位 hypervisor 位
+        // 并返回 "Microsoft Hv"；没有 DMI/注册表佐证时只作为低可信度依据，不认定为虚拟机
+        let mut root_partition = false;
+        if let Some(signature) = source.cpuid_hypervisor() {
+            let hypervisor = hypervisor_from_cpuid(&signature);
+            let evidence = format!(
+                "cpuid hypervisor signature: {:?}",
+                signature.trim_end_matches('\0')
+            );
+            if hypervisor == Hypervisor::HyperV && !confirmed {
+                root_partition = true;
+                raise(
+                    &mut info,
+                    Confidence::Low,
+                    format!("{} (possibly Hyper-V root partition)", evidence),
+                );
+            } else {
+                raise(&mut info, Confidence::High, evidence);
+                info.hypervisor.get_or_insert(hypervisor);
+            }
+        }
+
+        // /proc/cpuinfo 的 hypervisor 标志只能说明是虚拟机，无法判断类型；
+        // 它与 CPUID 的 hypervisor 位同源，根分区的情况下同样只算低可信度
+        if let Some(cpuinfo) = read(PROC_CPUINFO_PATH)
+            && CpuInfo::parse_proc_cpuinfo(&cpuinfo).has_flag("hypervisor")
+        {
+            if root_partition {
+                raise(
+                    &mut info,
+                    Confidence::Low,
+                    "/proc/cpuinfo: hypervisor flag".into(),
+                );
+            } else {
+                raise(
+                    &mut info,
+                    Confidence::Medium,
+                    "/proc/cpuinfo: hypervisor flag".into(),
+                );
+                info.hypervisor
+                    .get_or_insert(Hypervisor::Other("unknown".into()));
+            }
+        }
+
+        // 容器运行时留下的标记文件
+        if source.exists("/.dockerenv") {
+            raise(&mut info, Confidence::High, "/.dockerenv exists".into());
+            info.container.get_or_insert(ContainerRuntime::Docker);
+        }
+        if source.exists("/run/.containerenv") {
+            raise(
+                &mut info,
+                Confidence::High,
+                "/run/.containerenv exists".into(),
+            );
+            info.container.get_or_insert(ContainerRuntime::Podman);
+        }
+        if let Some(value) = read("/run/systemd/container") {
+            let runtime = container_from_text(&value)
+                .unwrap_or_else(|| ContainerRuntime::Other(value.clone()));
+            raise(
+                &mut info,
+                Confidence::High,
+                format!("/run/systemd/container: {}", value),
+            );
+            info.container.get_or_insert(runtime);
+        }
+
+        // cgroup 路径（cgroup v2 的命名空间下常常只剩 "0::/"，所以只作为中等依据）
+        if let Some(cgroup) = read("/proc/1/cgroup")
+            && let Some(runtime) = container_from_text(&cgroup)
+        {
+            raise(
+                &mut info,
+                Confidence::Medium,
+                format!("/proc/1/cgroup mentions {:?}", runtime),
+            );
+            info.container.get_or_insert(runtime);
+        }
+
+        info
+    }
+
+    /// Linux sysfs 中网卡信息的默认位置
+    pub const SYSFS_NET_PATH: &str = "/sys/class/net";
+
+    // ARPHRD_ETHER，以太网和无线网卡都使用该类型
+    const ARPHRD_ETHER: u32 = 1;
+
+    /// MAC 地址的来源（对应 sysfs `addr_assign_type`）
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+    #[serde(rename_all = "snake_case")]
+    pub enum MacAssignType {
+        /// 出厂烧录的地址
+        Permanent,
+        /// 随机生成（例如无线网卡的隐私地址）
+        Random,
+        /// 借用其他设备的地址（例如 bond）
+        Stolen,
+        /// 由用户或驱动设置
+        Set,
+        Unknown,
+    }
+
+    /// 网卡连接状态（对应 sysfs `operstate`）
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+    #[serde(rename_all = "snake_case")]
+    pub enum LinkState {
+        Up,
+        Down,
+        Dormant,
+        LowerLayerDown,
+        NotPresent,
+        Testing,
+        Unknown,
+    }
+
+    /// 一个网络接口及其元数据
+    #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+    pub struct NetworkInterface {
+        pub name: String,
+        /// 小写冒号分隔，例如 `aa:bb:cc:dd:ee:ff`
+        pub mac: Option<String>,
+        pub assign_type: MacAssignType,
+        /// MAC 第一个字节的 0x02 位，表示本地管理（非厂商分配）的地址
+        pub locally_administered: bool,
+        /// 存在 `device` 链接，即背后有真实（或虚拟机模拟）的硬件设备
+        pub physical: bool,
+        pub wireless: bool,
+        pub link_state: LinkState,
+        /// 驱动名，例如 `e1000e`、`iwlwifi`
+        pub driver: Option<String>,
+        /// ARPHRD 类型，以太网为 1
+        pub if_type: Option<u32>,
+    }
+
+    impl NetworkInterface {
+        /// 是否适合作为稳定的硬件标识：物理设备、以太网类型、非随机的非空地址
+        ///
+        /// 本地管理的地址只在 `addr_assign_type` 为出厂时接受，
+        /// 部分虚拟机的网卡出厂即为本地管理地址。
+        pub fn is_stable_identity(&self) -> bool {
+            let Some(mac) = &self.mac else {
+                return false;
+            };
+            let assign_ok = match self.assign_type {
+                MacAssignType::Permanent => true,
+                MacAssignType::Random | MacAssignType::Stolen => false,
+                MacAssignType::Set | MacAssignType::Unknown => !self.locally_administered,
+            };
+            self.physical
+                && assign_ok
+                && self.if_type.unwrap_or(ARPHRD_ETHER) == ARPHRD_ETHER
+                && mac != "00:00:00:00:00:00"
+        }
+    }
+
+    fn parse_assign_type(value: &str) -> MacAssignType {
+        match value.trim() {
+            "0" => MacAssignType::Permanent,
+            "1" => MacAssignType::Random,
+            "2" => MacAssignType::Stolen,
+            "3" => MacAssignType::Set,
+            _ => MacAssignType::Unknown,
+        }
+    }
+
+    fn parse_link_state(value: &str) -> LinkState {
+        match value.trim() {
+            "up" => LinkState::Up,
+            "down" => LinkState::Down,
+            "dormant" => LinkState::Dormant,
+            "lowerlayerdown" => LinkState::LowerLayerDown,
+            "notpresent" => LinkState::NotPresent,
+            "testing" => LinkState::Testing,
+            _ => LinkState::Unknown,
+        }
+    }
+
+    /// 从 sysfs 格式的目录读取所有网络接口，按名称排序，跳过回环接口
+    pub fn read_network_interfaces(root: &Path) -> Result<Vec<NetworkInterface>, HardwareError> {
+        read_network_interfaces_from(&SystemSource, &root.to_string_lossy())
+    }
+
+    /// 从指定数据源的 sysfs 格式目录读取所有网络接口
+    pub fn read_network_interfaces_from(
+        source: &dyn HardwareSource,
+        root: &str,
+    ) -> Result<Vec<NetworkInterface>, HardwareError> {
+        let read = |path: String| {
+            source
+                .read_file(&path)
+                .ok()
+                .map(|s| s.trim().to_string())
+                .filter(|s| !s.is_empty())
+        };
+
+        let mut interfaces = Vec::new();
+        for name in source.list_dir(root)? {
+            let dir = format!("{}/{}", root.trim_end_matches('/'), name);
+            if name == "lo" {
+                continue;
+            }
+
+            let mac = read(format!("{}/address", dir))
+                .map(|m| m.to_ascii_lowercase())
+                .filter(|m| m.len() == 17);
+            let locally_administered = mac
+                .as_deref()
+                .and_then(|m| u8::from_str_radix(&m[..2], 16).ok())
+                .is_some_and(|first| first & 0x02 != 0);
+            let driver = source
+                .read_link(&format!("{}/device/driver", dir))
+                .ok()
+                .and_then(|p| p.file_name().map(|n| n.to_string_lossy().to_string()));
+
+            interfaces.push(NetworkInterface {
+                mac,
+                assign_type: read(format!("{}/addr_assign_type", dir))
+                    .map(|v| parse_assign_type(&v))
+                    .unwrap_or(MacAssignType::Unknown),
+                locally_administered,
+                physical: source.exists(&format!("{}/device", dir)),
+                wireless: source.exists(&format!("{}/wireless", dir))
+                    || source.exists(&format!("{}/phy80211", dir)),
+                link_state: read(format!("{}/operstate", dir))
+                    .map(|v| parse_link_state(&v))
+                    .unwrap_or(LinkState::Unknown),
+                driver,
+                if_type: read(format!("{}/type", dir)).and_then(|v| v.parse().ok()),
+                name,
+            });
+        }
+
+        interfaces.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(interfaces)
+    }
+
+    // rtnetlink 返回的链路信息
+    #[cfg(target_os = "linux")]
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    struct NetlinkLink {
+        index: i32,
+        if_type: u16,
+        flags: u32,
+        mac: Option<[u8; 6]>,
+        /// IFLA_PERM_ADDRESS（Linux 5.6+），只有真实网卡才有
+        perm_mac: Option<[u8; 6]>,
+    }
+
+    #[cfg(target_os = "linux")]
+    const NLMSG_HDR_LEN: usize = 16;
+    #[cfg(target_os = "linux")]
+    const IFINFO_LEN: usize = 16;
+
+    #[cfg(target_os = "linux")]
+    fn ne_u16(buf: &[u8], off: usize) -> u16 {
+        u16::from_ne_bytes([buf[off], buf[off + 1]])
+    }
+
+    #[cfg(target_os = "linux")]
+    fn ne_u32(buf: &[u8], off: usize) -> u32 {
+        u32::from_ne_bytes([buf[off], buf[off + 1], buf[off + 2], buf[off + 3]])
+    }
+
+    // netlink 消息和属性按 4 字节对齐
+    #[cfg(target_os = "linux")]
+    fn nl_align(len: usize) -> usize {
+        (len + 3) & !3
+    }
+
+    // 每次转储使用新的序号，只接受序号匹配的应答
+    #[cfg(target_os = "linux")]
+    static NETLINK_SEQ: std::sync::atomic::AtomicU32 = std::sync::atomic::AtomicU32::new(1);
+
+    // 单次 recv 的超时，内核没有应答时不会一直阻塞
+    #[cfg(target_os = "linux")]
+    const NETLINK_RECV_TIMEOUT_SECS: libc::time_t = 2;
+
+    // 通过 RTM_GETLINK 转储所有网络接口，不依赖 sysfs 和 ip 命令
+    #[cfg(target_os = "linux")]
+    fn netlink_links() -> std::io::Result<Vec<NetlinkLink>> {
+        use std::io::Error;
+        use std::os::fd::{AsRawFd, FromRawFd, OwnedFd};
+
+        let raw_fd = unsafe {
+            libc::socket(
+                libc::AF_NETLINK,
+                libc::SOCK_RAW | libc::SOCK_CLOEXEC,
+                libc::NETLINK_ROUTE,
+            )
+        };
+        if raw_fd < 0 {
+            return Err(Error::last_os_error());
+        }
+        let fd = unsafe { OwnedFd::from_raw_fd(raw_fd) };
+
+        let timeout = libc::timeval {
+            tv_sec: NETLINK_RECV_TIMEOUT_SECS,
+            tv_usec: 0,
+        };
+        let result = unsafe {
+            libc::setsockopt(
+                fd.as_raw_fd(),
+                libc::SOL_SOCKET,
+                libc::SO_RCVTIMEO,
+                &timeout as *const libc::timeval as *const libc::c_void,
+                std::mem::size_of::<libc::timeval>() as libc::socklen_t,
+            )
+        };
+        if result < 0 {
+            return Err(Error::last_os_error());
+        }
+
+        // nlmsghdr + ifinfomsg，按本机字节序
+        let seq = NETLINK_SEQ.fetch_add(1, Ordering::Relaxed);
+        let mut request = Vec::with_capacity(NLMSG_HDR_LEN + IFINFO_LEN);
+        request.extend_from_slice(&((NLMSG_HDR_LEN + IFINFO_LEN) as u32).to_ne_bytes());
+        request.extend_from_slice(&libc::RTM_GETLINK.to_ne_bytes());
+        request.extend_from_slice(&((libc::NLM_F_REQUEST | libc::NLM_F_DUMP) as u16).to_ne_bytes());
+        request.extend_from_slice(&seq.to_ne_bytes());
+        request.extend_from_slice(&0u32.to_ne_bytes());
+        request.push(libc::AF_UNSPEC as u8);
+        request.resize(NLMSG_HDR_LEN + IFINFO_LEN, 0);
+
+        let sent = unsafe {
+            libc::send(
+                fd.as_raw_fd(),
+                request.as_ptr() as *const libc::c_void,
+                request.len(),
+                0,
+            )
+        };
+        if sent < 0 {
+            return Err(Error::last_os_error());
+        }
+
+        receive_netlink_dump(seq, |buf| {
+            let received = unsafe {
+                libc::recv(
+                    fd.as_raw_fd(),
+                    buf.as_mut_ptr() as *mut libc::c_void,
+                    buf.len(),
+                    0,
+                )
+            };
+            if received < 0 {
+                let err = Error::last_os_error();
+                return Err(if err.kind() == std::io::ErrorKind::WouldBlock {
+                    Error::new(std::io::ErrorKind::TimedOut, "netlink dump timed out")
+                } else {
+                    err
+                });
+            }
+            Ok(received as usize)
+        })
+    }
+
+    // 反复接收直到 NLMSG_DONE；recv 返回 0 说明套接字已关闭，不能再等下去
+    #[cfg(target_os = "linux")]
+    fn receive_netlink_dump(
+        seq: u32,
+        mut recv: impl FnMut(&mut [u8]) -> std::io::Result<usize>,
+    ) -> std::io::Result<Vec<NetlinkLink>> {
+        let mut links = Vec::new();
+        let mut buf = vec![0u8; 32 * 1024];
+        loop {
+            let received = recv(&mut buf)?;
+            if received == 0 {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::UnexpectedEof,
+                    "netlink socket returned no data",
+                ));
+            }
+            if parse_netlink_dump(&buf[..received], seq, &mut links)? {
+                return Ok(links);
+            }
+        }
+    }
+
+    // 解析一次 recv 得到的若干条 netlink 消息，收到 NLMSG_DONE 时返回 true；
+    // 序号与请求不符的消息不属于本次转储，直接跳过
+    #[cfg(target_os = "linux")]
+    fn parse_netlink_dump(
+        data: &[u8],
+        seq: u32,
+        links: &mut Vec<NetlinkLink>,
+    ) -> std::io::Result<bool> {
+        let mut offset = 0;
+        while offset + NLMSG_HDR_LEN <= data.len() {
+            let msg_len = ne_u32(data, offset) as usize;
+            let msg_type = ne_u16(data, offset + 4);
+            if msg_len < NLMSG_HDR_LEN || offset + msg_len > data.len() {
+                return Err(std::io::Error::other("truncated netlink message"));
+            }
+            if ne_u32(data, offset + 8) != seq {
+                offset += nl_align(msg_len);
+                continue;
+            }
+            if msg_type == libc::NLMSG_DONE as u16 {
+                return Ok(true);
+            }
+            if msg_type == libc::NLMSG_ERROR as u16 {
+                return Err(std::io::Error::other("netlink request failed"));
+            }
+
+            if let Some(link) = parse_rtm_newlink(&data[offset..offset + msg_len]) {
+                links.push(link);
+            }
+            offset += nl_align(msg_len);
+        }
+        Ok(false)
+    }
+
+    // 解析单条 RTM_NEWLINK 消息（含 nlmsghdr），其他类型或长度不足时返回 None
+    #[cfg(target_os = "linux")]
+    fn parse_rtm_newlink(msg: &[u8]) -> Option<NetlinkLink> {
+        const RTA_HDR_LEN: usize = 4;
+        const IFLA_PERM_ADDRESS: u16 = 54;
+
+        if msg.len() < NLMSG_HDR_LEN + IFINFO_LEN || ne_u16(msg, 4) != libc::RTM_NEWLINK {
+            return None;
         }
+        let msg = &msg[..(ne_u32(msg, 0) as usize).min(msg.len())];
+
+        let info = NLMSG_HDR_LEN;
+        let mut link = NetlinkLink {
+            if_type: ne_u16(msg, info + 2),
+            index: ne_u32(msg, info + 4) as i32,
+            flags: ne_u32(msg, info + 8),
+            mac: None,
+            perm_mac: None,
+        };
+
+        let mut attr = NLMSG_HDR_LEN + IFINFO_LEN;
+        while attr + RTA_HDR_LEN <= msg.len() {
+            let attr_len = ne_u16(msg, attr) as usize;
+            let attr_type = ne_u16(msg, attr + 2);
+            if attr_len < RTA_HDR_LEN || attr + attr_len > msg.len() {
+                break;
+            }
+            let payload = &msg[attr + RTA_HDR_LEN..attr + attr_len];
+            if payload.len() == 6 {
+                match attr_type {
+                    libc::IFLA_ADDRESS => link.mac = payload.try_into().ok(),
+                    IFLA_PERM_ADDRESS => link.perm_mac = payload.try_into().ok(),
+                    _ => {}
+                }
+            }
+            attr += nl_align(attr_len);
+        }
+        Some(link)
+    }
+
+    // netlink 拿不到 device 链接：优先使用内核报告的永久地址（只有真实网卡才有），
+    // 否则退回厂商分配的当前地址，同一优先级内取接口序号最小的
+    #[cfg(target_os = "linux")]
+    fn select_netlink_mac(links: &[NetlinkLink]) -> Option<String> {
+        links
+            .iter()
+            .filter(|link| link.if_type as u32 == ARPHRD_ETHER)
+            .filter(|link| link.flags & libc::IFF_LOOPBACK as u32 == 0)
+            .filter_map(|link| match (link.perm_mac, link.mac) {
+                (Some(perm), _) if perm.iter().any(|b| *b != 0) => Some((0, link.index, perm)),
+                (_, Some(mac)) if mac[0] & 0x02 == 0 && mac.iter().any(|b| *b != 0) => {
+                    Some((1, link.index, mac))
+                }
+                _ => None,
+            })
+            .min_by_key(|(rank, index, _)| (*rank, *index))
+            .map(|(_, _, mac)| hex::encode(mac))
+    }
+
+    /// 主网卡选择策略
+    ///
+    /// 只考虑 `is_stable_identity` 的接口；优先厂商分配的地址、出厂地址、有线网卡，
+    /// 最后按名称排序。
+    /// 不考虑连接状态，拔掉网线不会改变主网卡。
+    pub fn select_primary_interface(interfaces: &[NetworkInterface]) -> Option<&NetworkInterface> {
+        interfaces
+            .iter()
+            .filter(|iface| iface.is_stable_identity())
+            .min_by_key(|iface| {
+                (
+                    iface.locally_administered,
+                    iface.assign_type != MacAssignType::Permanent,
+                    iface.wireless,
+                    iface.name.clone(),
+                )
+            })
+    }
+
+    /// 指纹匹配的默认阈值，得分不低于该值视为同一设备
+    pub const DEFAULT_MATCH_THRESHOLD: f64 = 0.6;
+
+    /// 指纹格式版本，组件的哈希方式变化时递增
+    ///
+    /// 版本 2 起组件以应用盐为密钥做 HMAC-SHA256，版本 1（无盐 SHA-256）的指纹不再接受。
+    pub const FINGERPRINT_VERSION: u32 = 2;
+
+    // 指纹组件的 HMAC 上下文，与设备ID的上下文区分
+    const FINGERPRINT_CONTEXT: &[u8] = b"firm_netter/fingerprint/v2\0";
+
+    // 各组件在匹配得分中的权重
+    const WEIGHT_BOARD_UUID: f64 = 3.0;
+    const WEIGHT_BOARD_SERIAL: f64 = 2.0;
+    const WEIGHT_MACHINE_ID: f64 = 2.0;
+    const WEIGHT_CPU: f64 = 1.0;
+    const WEIGHT_MACS: f64 = 2.0;
+    const WEIGHT_DISKS: f64 = 2.0;
+
+    // 厂商未填写时常见的占位值
+    const PLACEHOLDER_VALUES: &[&str] = &[
+        "",
+        "0",
+        "none",
+        "null",
+        "unknown",
+        "default string",
+        "to be filled by o.e.m.",
+        "not specified",
+        "system serial number",
+        "00000000-0000-0000-0000-000000000000",
+        "ffffffff-ffff-ffff-ffff-ffffffffffff",
+        "000000000000",
+    ];
+
+    /// 采集到的原始硬件标识（未哈希），只在本机内部使用
+    #[derive(Debug, Clone, Default, PartialEq, Eq)]
+    pub struct RawHardwareIds {
+        pub board_uuid: Option<String>,
+        pub board_serial: Option<String>,
+        pub cpu: Option<String>,
+        pub macs: Vec<String>,
+        pub disk_serials: Vec<String>,
+        pub machine_id: Option<String>,
+    }
+
+    /// 分组件哈希的硬件指纹
+    ///
+    /// 每个组件以应用专属的盐为密钥单独做 HMAC-SHA256，序列化结果中不包含原始序列号，
+    /// 不同产品（不同的盐）得到的指纹互不相关，也无法在不知道盐的情况下穷举 MAC 等取值。
+    /// 多值组件（MAC、磁盘序列号）排序去重，保证同一台机器序列化结果稳定。
+    #[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+    pub struct HardwareFingerprint {
+        pub version: u32,
+        pub board_uuid: Option<String>,
+        pub board_serial: Option<String>,
+        pub cpu: Option<String>,
+        pub macs: Vec<String>,
+        pub disk_serials: Vec<String>,
+        pub machine_id: Option<String>,
+    }
+
+    /// 指纹采集选项
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct FingerprintOptions {
+        /// 是否包含磁盘序列号；磁盘经常更换的场景（例如外置存储）可关闭
+        pub include_disks: bool,
+    }
+
+    impl Default for FingerprintOptions {
+        fn default() -> Self {
+            FingerprintOptions {
+                include_disks: true,
+            }
+        }
+    }
+
+    /// 两个指纹的比较结果
+    #[derive(Debug, Clone, PartialEq)]
+    pub struct FingerprintMatch {
+        /// 0.0 ~ 1.0，按组件权重加权
+        pub score: f64,
+        /// 两边都存在且一致的组件
+        pub matched: Vec<&'static str>,
+        /// 发生变化或只有一边存在的组件
+        pub changed: Vec<&'static str>,
+    }
+
+    impl FingerprintMatch {
+        /// 得分是否达到阈值
+        pub fn is_match(&self, threshold: f64) -> bool {
+            self.score >= threshold
+        }
+    }
+
+    fn is_placeholder(value: &str) -> bool {
+        PLACEHOLDER_VALUES.contains(&value.trim().to_ascii_lowercase().as_str())
+    }
+
+    // 统一大小写和分隔符，避免不同采集方式得到的同一值哈希不同
+    fn normalize_component(value: &str) -> Option<String> {
+        let trimmed = value.trim();
+        if is_placeholder(trimmed) {
+            return None;
+        }
+        Some(trimmed.to_ascii_lowercase())
+    }
+
+    fn normalize_mac(value: &str) -> Option<String> {
+        let mac: String = value
+            .chars()
+            .filter(|c| c.is_ascii_hexdigit())
+            .collect::<String>()
+            .to_ascii_lowercase();
+        if mac.len() != 12 || is_placeholder(&mac) {
+            return None;
+        }
+        Some(mac)
+    }
+
+    fn hash_component(salt: &[u8], name: &str, value: &str) -> Result<String, HardwareError> {
+        let mut message = FINGERPRINT_CONTEXT.to_vec();
+        message.extend_from_slice(format!("{}:{}", name, value).as_bytes());
+        let digest = hmac(HashAlgorithm::Sha256, salt, &message)
+            .map_err(|e| HardwareError::DerivationFailed(e.to_string()))?;
+        Ok(digest.to_hex())
+    }
+
+    fn hash_list(
+        salt: &[u8],
+        name: &str,
+        values: &[String],
+        normalize: fn(&str) -> Option<String>,
+    ) -> Result<Vec<String>, HardwareError> {
+        let mut hashed = values
+            .iter()
+            .filter_map(|v| normalize(v))
+            .map(|v| hash_component(salt, name, &v))
+            .collect::<Result<Vec<_>, _>>()?;
+        hashed.sort();
+        hashed.dedup();
+        Ok(hashed)
+    }
+
+    // 单值组件的相似度：两边都没有时不参与计分
+    fn score_single(a: &Option<String>, b: &Option<String>) -> Option<f64> {
+        match (a, b) {
+            (None, None) => None,
+            (Some(x), Some(y)) if x == y => Some(1.0),
+            _ => Some(0.0),
+        }
+    }
+
+    // 多值组件的相似度：交集 / 并集，换掉一块网卡只会部分扣分
+    fn score_list(a: &[String], b: &[String]) -> Option<f64> {
+        if a.is_empty() && b.is_empty() {
+            return None;
+        }
+        let common = a.iter().filter(|x| b.contains(x)).count();
+        let union = a.len() + b.len() - common;
+        Some(common as f64 / union as f64)
+    }
+
+    impl HardwareFingerprint {
+        /// 采集本机硬件信息并用应用专属的盐生成指纹，所有组件都拿不到时返回 `IdNotFound`
+        pub fn collect(salt: &[u8]) -> Result<Self, HardwareError> {
+            Self::collect_from(&SystemSource, salt)
+        }
+
+        /// 从指定数据源采集硬件信息并生成指纹
+        pub fn collect_from(
+            source: &dyn HardwareSource,
+            salt: &[u8],
+        ) -> Result<Self, HardwareError> {
+            Self::collect_with(source, salt, &FingerprintOptions::default())
+        }
+
+        /// 按选项采集硬件信息并生成指纹
+        pub fn collect_with(
+            source: &dyn HardwareSource,
+            salt: &[u8],
+            options: &FingerprintOptions,
+        ) -> Result<Self, HardwareError> {
+            let mut raw = RawHardwareIds::collect_from(source);
+            if !options.include_disks {
+                raw.disk_serials.clear();
+            }
+
+            let fingerprint = Self::from_raw(&raw, salt)?;
+            if fingerprint.is_empty() {
+                return Err(HardwareError::IdNotFound);
+            }
+            Ok(fingerprint)
+        }
+
+        /// 由原始标识生成指纹，占位值会被忽略；盐为空时返回 `InvalidSalt`
+        pub fn from_raw(raw: &RawHardwareIds, salt: &[u8]) -> Result<Self, HardwareError> {
+            if salt.is_empty() {
+                return Err(HardwareError::InvalidSalt);
+            }
+            let single = |name: &str, value: &Option<String>| {
+                value
+                    .as_deref()
+                    .and_then(normalize_component)
+                    .map(|v| hash_component(salt, name, &v))
+                    .transpose()
+            };
+
+            Ok(HardwareFingerprint {
+                version: FINGERPRINT_VERSION,
+                board_uuid: single("board_uuid", &raw.board_uuid)?,
+                board_serial: single("board_serial", &raw.board_serial)?,
+                cpu: single("cpu", &raw.cpu)?,
+                macs: hash_list(salt, "mac", &raw.macs, normalize_mac)?,
+                disk_serials: hash_list(
+                    salt,
+                    "disk_serial",
+                    &raw.disk_serials,
+                    normalize_component,
+                )?,
+                machine_id: single("machine_id", &raw.machine_id)?,
+            })
+        }
+
+        /// 是否没有任何组件
+        pub fn is_empty(&self) -> bool {
+            self.board_uuid.is_none()
+                && self.board_serial.is_none()
+                && self.cpu.is_none()
+                && self.macs.is_empty()
+                && self.disk_serials.is_empty()
+                && self.machine_id.is_none()
+        }
+
+        /// 序列化为稳定的 JSON 字符串
+        pub fn to_json(&self) -> Result<String, HardwareError> {
+            serde_json::to_string(self).map_err(|_| HardwareError::InvalidFormat)
+        }
+
+        /// 从 JSON 字符串恢复指纹
+        pub fn from_json(json: &str) -> Result<Self, HardwareError> {
+            let fingerprint: Self =
+                serde_json::from_str(json).map_err(|_| HardwareError::InvalidFormat)?;
+            if fingerprint.version != FINGERPRINT_VERSION {
+                return Err(HardwareError::InvalidFormat);
+            }
+            Ok(fingerprint)
+        }
+
+        /// 按组件加权比较两个指纹
+        pub fn compare(&self, other: &Self) -> FingerprintMatch {
+            let scores = [
+                (
+                    "board_uuid",
+                    WEIGHT_BOARD_UUID,
+                    score_single(&self.board_uuid, &other.board_uuid),
+                ),
+                (
+                    "board_serial",
+                    WEIGHT_BOARD_SERIAL,
+                    score_single(&self.board_serial, &other.board_serial),
+                ),
+                (
+                    "machine_id",
+                    WEIGHT_MACHINE_ID,
+                    score_single(&self.machine_id, &other.machine_id),
+                ),
+                ("cpu", WEIGHT_CPU, score_single(&self.cpu, &other.cpu)),
+                ("macs", WEIGHT_MACS, score_list(&self.macs, &other.macs)),
+                (
+                    "disk_serials",
+                    WEIGHT_DISKS,
+                    score_list(&self.disk_serials, &other.disk_serials),
+                ),
+            ];
+
+            let mut total = 0.0;
+            let mut earned = 0.0;
+            let mut matched = Vec::new();
+            let mut changed = Vec::new();
+            for (name, weight, score) in scores {
+                let Some(score) = score else { continue };
+                total += weight;
+                earned += weight * score;
+                if score >= 1.0 {
+                    matched.push(name);
+                } else {
+                    changed.push(name);
+                }
+            }
+
+            FingerprintMatch {
+                score: if total > 0.0 { earned / total } else { 0.0 },
+                matched,
+                changed,
+            }
+        }
+
+        /// 按默认阈值判断是否为同一设备
+        pub fn matches(&self, other: &Self) -> bool {
+            self.compare(other).is_match(DEFAULT_MATCH_THRESHOLD)
+        }
+    }
+
+    impl RawHardwareIds {
+        /// 采集本机的原始硬件标识，单个组件失败不影响其他组件
+        pub fn collect() -> Self {
+            Self::collect_from(&SystemSource)
+        }
+
+        /// 从指定数据源采集原始硬件标识
+        pub fn collect_from(source: &dyn HardwareSource) -> Self {
+            let mut raw = RawHardwareIds {
+                cpu: HardwareInfo::query_cpu_info_from(source)
+                    .ok()
+                    .map(|v| v.value),
+                ..Default::default()
+            };
+            Self::collect_platform(source, &mut raw);
+
+            // 平台没有枚举所有网卡时，至少使用主网卡
+            if raw.macs.is_empty()
+                && let Ok(mac) = HardwareInfo::query_primary_mac_from(source)
+            {
+                raw.macs.push(mac.value);
+            }
+            raw
+        }
+
+        #[cfg(target_os = "linux")]
+        fn collect_platform(source: &dyn HardwareSource, raw: &mut Self) {
+            let read = |path: &str| {
+                source
+                    .read_file(path)
+                    .ok()
+                    .map(|s| s.trim().to_string())
+                    .filter(|s| !s.is_empty())
+            };
+
+            raw.board_uuid = read("/sys/class/dmi/id/product_uuid");
+            raw.board_serial = read("/sys/class/dmi/id/board_serial");
+            raw.machine_id = read("/etc/machine-id").or_else(|| read("/var/lib/dbus/machine-id"));
+
+            // 只记录物理网卡的出厂地址，容器网桥和随机地址会频繁变化
+            if let Ok(interfaces) = HardwareInfo::list_network_interfaces_from(source) {
+                raw.macs = interfaces
+                    .into_iter()
+                    .filter(|iface| iface.is_stable_identity())
+                    .filter_map(|iface| iface.mac)
+                    .collect();
+            }
+
+            // 可移动设备（U盘、读卡器）经常插拔，不参与指纹
+            if let Ok(devices) = HardwareInfo::list_block_devices_from(source) {
+                raw.disk_serials = devices
+                    .iter()
+                    .filter(|disk| !disk.removable)
+                    .filter_map(|disk| disk.identity().map(|id| id.to_string()))
+                    .collect();
+            }
+        }
+
+        #[cfg(target_os = "windows")]
+        fn collect_platform(source: &dyn HardwareSource, raw: &mut Self) {
+            // WMIC 输出第一行为列名
+            let wmic = |args: &[&str]| -> Vec<String> {
+                source
+                    .run_command("wmic", args)
+                    .map(|stdout| {
+                        stdout
+                            .lines()
+                            .skip(1)
+                            .map(|l| l.trim().to_string())
+                            .filter(|l| !l.is_empty())
+                            .collect()
+                    })
+                    .unwrap_or_default()
+            };
+
+            raw.board_uuid = wmic(&["csproduct", "get", "uuid"]).into_iter().next();
+            raw.board_serial = wmic(&["baseboard", "get", "serialnumber"])
+                .into_iter()
+                .next();
+            raw.disk_serials = wmic(&["diskdrive", "get", "serialnumber"]);
+            raw.machine_id =
+                source.read_registry("SOFTWARE\\Microsoft\\Cryptography", "MachineGuid");
+        }
+
+        #[cfg(target_os = "macos")]
+        fn collect_platform(source: &dyn HardwareSource, raw: &mut Self) {
+            if let Ok(stdout) =
+                source.run_command("ioreg", &["-rd1", "-c", "IOPlatformExpertDevice"])
+            {
+                let field = |name: &str| {
+                    let re = Regex::new(&format!(r#""{}"\s*=\s*"([^"]+)""#, name)).unwrap();
+                    re.captures(&stdout)
+                        .and_then(|caps| caps.get(1))
+                        .map(|m| m.as_str().to_string())
+                };
+                raw.board_uuid = field("IOPlatformUUID");
+                raw.board_serial = field("IOPlatformSerialNumber");
+            }
+        }
+
+        #[cfg(target_os = "freebsd")]
+        fn collect_platform(source: &dyn HardwareSource, raw: &mut Self) {
+            raw.board_uuid = source
+                .run_command("sysctl", &["-n", "kern.hostuuid"])
+                .ok()
+                .map(|s| s.trim().to_string());
+            raw.board_serial = HardwareInfo::query_motherboard_id_from(source)
+                .ok()
+                .map(|v| v.value);
+            raw.machine_id = source
+                .read_file("/etc/hostid")
+                .ok()
+                .map(|s| s.trim().to_string());
+        }
+
+        #[cfg(not(any(
+            target_os = "windows",
+            target_os = "linux",
+            target_os = "macos",
+            target_os = "freebsd"
+        )))]
+        fn collect_platform(_source: &dyn HardwareSource, _raw: &mut Self) {}
+    }
+
+    /// Linux sysfs 中显示设备的位置
+    pub const SYSFS_DRM_PATH: &str = "/sys/class/drm";
+
+    /// 清单中的单个字段：采集成功时为值，失败时记录错误原因，
+    /// JSON 形如 `{"value": ...}` 或 `{"error": "..."}`
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    #[serde(rename_all = "snake_case")]
+    pub enum InventoryField<T> {
+        Value(T),
+        Error(String),
+    }
+
+    impl<T> InventoryField<T> {
+        pub fn value(&self) -> Option<&T> {
+            match self {
+                InventoryField::Value(value) => Some(value),
+                InventoryField::Error(_) => None,
+            }
+        }
+
+        pub fn is_ok(&self) -> bool {
+            matches!(self, InventoryField::Value(_))
+        }
+    }
+
+    impl<T> From<Result<T, HardwareError>> for InventoryField<T> {
+        fn from(result: Result<T, HardwareError>) -> Self {
+            match result {
+                Ok(value) => InventoryField::Value(value),
+                Err(e) => InventoryField::Error(e.to_string()),
+            }
+        }
+    }
+
+    /// 操作系统信息
+    #[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+    pub struct OsInfo {
+        /// 编译目标的系统类型，如 "linux"、"windows"
+        pub family: String,
+        pub arch: String,
+        /// 发行版全称，如 "Ubuntu 22.04.4 LTS"
+        pub name: Option<String>,
+        pub id: Option<String>,
+        pub version: Option<String>,
+    }
+
+    /// 解析 /etc/os-release，值两侧的引号会被去掉
+    pub fn parse_os_release(content: &str) -> OsInfo {
+        let mut info = OsInfo {
+            family: std::env::consts::OS.to_string(),
+            arch: std::env::consts::ARCH.to_string(),
+            ..Default::default()
+        };
+        let mut name = None;
+
+        for line in content.lines() {
+            let Some((key, value)) = line.trim().split_once('=') else {
+                continue;
+            };
+            let value = value
+                .trim()
+                .trim_matches('"')
+                .trim_matches('\'')
+                .to_string();
+            if value.is_empty() {
+                continue;
+            }
+            match key {
+                "PRETTY_NAME" => info.name = Some(value),
+                "NAME" => name = Some(value),
+                "ID" => info.id = Some(value),
+                "VERSION_ID" => info.version = Some(value),
+                _ => {}
+            }
+        }
+
+        if info.name.is_none() {
+            info.name = name;
+        }
+        info
+    }
+
+    /// DMI/SMBIOS 中的厂商、产品和 BIOS 信息
+    #[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+    pub struct DmiInfo {
+        pub sys_vendor: Option<String>,
+        pub product_name: Option<String>,
+        pub product_version: Option<String>,
+        pub board_vendor: Option<String>,
+        pub board_name: Option<String>,
+        pub bios_vendor: Option<String>,
+        pub bios_version: Option<String>,
+        pub bios_date: Option<String>,
+    }
+
+    /// 内存信息，单位为字节
+    #[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+    pub struct MemoryInfo {
+        pub total_bytes: u64,
+        pub available_bytes: Option<u64>,
+        pub swap_total_bytes: Option<u64>,
+        pub swap_free_bytes: Option<u64>,
+    }
+
+    /// 解析 /proc/meminfo，缺少 MemTotal 时返回 `InvalidFormat`
+    pub fn parse_meminfo(content: &str) -> Result<MemoryInfo, HardwareError> {
+        let mut values = BTreeMap::new();
+        for line in content.lines() {
+            if let Some((key, rest)) = line.split_once(':') {
+                let mut parts = rest.split_whitespace();
+                let Some(Ok(number)) = parts.next().map(str::parse::<u64>) else {
+                    continue;
+                };
+                let bytes = match parts.next() {
+                    Some("kB") => number * 1024,
+                    _ => number,
+                };
+                values.insert(key.trim(), bytes);
+            }
+        }
+
+        Ok(MemoryInfo {
+            total_bytes: *values.get("MemTotal").ok_or(HardwareError::InvalidFormat)?,
+            available_bytes: values.get("MemAvailable").copied(),
+            swap_total_bytes: values.get("SwapTotal").copied(),
+            swap_free_bytes: values.get("SwapFree").copied(),
+        })
+    }
+
+    /// 解析 /proc/uptime 的第一个字段，返回整秒数
+    pub fn parse_uptime(content: &str) -> Result<u64, HardwareError> {
+        content
+            .split_whitespace()
+            .next()
+            .and_then(|s| s.parse::<f64>().ok())
+            .filter(|s| s.is_finite() && *s >= 0.0)
+            .map(|s| s as u64)
+            .ok_or(HardwareError::InvalidFormat)
+    }
+
+    /// 一块显卡（DRM 设备）
+    #[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+    pub struct GpuDevice {
+        /// DRM 设备名，如 "card0"
+        pub card: String,
+        /// PCI 厂商ID，如 "0x10de"
+        pub vendor_id: Option<String>,
+        pub device_id: Option<String>,
+        /// 按厂商ID识别出的厂商名称
+        pub vendor: Option<String>,
+        pub driver: Option<String>,
+        /// 总线地址，如 "0000:01:00.0"
+        pub bus_address: Option<String>,
+    }
+
+    // 常见显卡厂商的 PCI ID
+    fn gpu_vendor_name(vendor_id: &str) -> Option<&'static str> {
+        let name = match vendor_id.to_ascii_lowercase().as_str() {
+            "0x10de" => "NVIDIA",
+            "0x1002" => "AMD",
+            "0x8086" => "Intel",
+            "0x1a03" => "ASPEED",
+            "0x102b" => "Matrox",
+            "0x15ad" => "VMware",
+            "0x1af4" => "Red Hat (virtio)",
+            "0x1234" => "QEMU",
+            "0x80ee" => "VirtualBox",
+            _ => return None,
+        };
+        Some(name)
+    }
+
+    /// 从指定数据源的 sysfs 格式目录读取显卡列表
+    ///
+    /// 只取 `cardN` 条目，跳过 `card0-HDMI-A-1` 这类显示接口；
+    /// 目录不存在说明没有加载任何 DRM 驱动，返回空列表。
+    pub fn read_gpu_devices_from(
+        source: &dyn HardwareSource,
+        root: &str,
+    ) -> Result<Vec<GpuDevice>, HardwareError> {
+        let names = match source.list_dir(root) {
+            Ok(names) => names,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e.into()),
+        };
+        let read = |path: String| {
+            source
+                .read_file(&path)
+                .ok()
+                .map(|s| s.trim().to_string())
+                .filter(|s| !s.is_empty())
+        };
+        let link_name = |path: String| {
+            source
+                .read_link(&path)
+                .ok()
+                .and_then(|p| p.file_name().map(|n| n.to_string_lossy().into_owned()))
+        };
+
+        let mut gpus = Vec::new();
+        for card in names {
+            let is_card = card
+                .strip_prefix("card")
+                .is_some_and(|n| !n.is_empty() && n.bytes().all(|b| b.is_ascii_digit()));
+            if !is_card {
+                continue;
+            }
+
+            let device = format!("{}/{}/device", root, card);
+            let vendor_id = read(format!("{}/vendor", device));
+            gpus.push(GpuDevice {
+                vendor: vendor_id
+                    .as_deref()
+                    .and_then(gpu_vendor_name)
+                    .map(str::to_string),
+                vendor_id,
+                device_id: read(format!("{}/device", device)),
+                driver: link_name(format!("{}/driver", device)),
+                bus_address: link_name(device),
+                card,
+            });
+        }
+
+        gpus.sort_by(|a, b| a.card.cmp(&b.card));
+        Ok(gpus)
+    }
+
+    /// 系统清单，用于技术支持收集现场设备的硬件信息
+    ///
+    /// 每个字段单独采集，某一项失败只会在该字段记录错误，不影响其他字段。
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    pub struct SystemInventory {
+        /// 采集时间（Unix 时间戳，秒）
+        pub collected_at: u64,
+        pub os: InventoryField<OsInfo>,
+        pub kernel: InventoryField<String>,
+        pub hostname: InventoryField<String>,
+        pub dmi: InventoryField<DmiInfo>,
+        pub cpu: InventoryField<CpuInfo>,
+        pub memory: InventoryField<MemoryInfo>,
+        pub disks: InventoryField<Vec<BlockDevice>>,
+        pub network_interfaces: InventoryField<Vec<NetworkInterface>>,
+        pub gpus: InventoryField<Vec<GpuDevice>>,
+        pub uptime_seconds: InventoryField<u64>,
+    }
+
+    impl SystemInventory {
+        /// 采集本机的系统清单
+        pub fn collect() -> Self {
+            Self::collect_from(&SystemSource)
+        }
+
+        /// 从指定数据源采集系统清单
+        pub fn collect_from(source: &dyn HardwareSource) -> Self {
+            SystemInventory {
+                collected_at: std::time::SystemTime::now()
+                    .duration_since(std::time::UNIX_EPOCH)
+                    .map(|d| d.as_secs())
+                    .unwrap_or(0),
+                os: Self::query_os(source).into(),
+                kernel: Self::query_kernel(source).into(),
+                hostname: Self::query_hostname(source).into(),
+                dmi: Self::query_dmi(source).into(),
+                cpu: HardwareInfo::get_cpu_details_from(source).into(),
+                memory: Self::query_memory(source).into(),
+                disks: HardwareInfo::list_block_devices_from(source).into(),
+                network_interfaces: HardwareInfo::list_network_interfaces_from(source).into(),
+                gpus: Self::query_gpus(source).into(),
+                uptime_seconds: Self::query_uptime(source).into(),
+            }
+        }
+
+        /// 序列化为 JSON 字符串
+        pub fn to_json(&self) -> Result<String, HardwareError> {
+            serde_json::to_string(self).map_err(|_| HardwareError::InvalidFormat)
+        }
+
+        /// 序列化为便于阅读的多行 JSON，适合直接附在工单里
+        pub fn to_json_pretty(&self) -> Result<String, HardwareError> {
+            serde_json::to_string_pretty(self).map_err(|_| HardwareError::InvalidFormat)
+        }
+
+        #[allow(unused_variables)]
+        fn query_os(source: &dyn HardwareSource) -> Result<OsInfo, HardwareError> {
+            #[cfg(target_os = "linux")]
+            {
+                let content = source
+                    .read_file("/etc/os-release")
+                    .or_else(|_| source.read_file("/usr/lib/os-release"))?;
+                Ok(parse_os_release(&content))
+            }
+
+            #[cfg(target_os = "macos")]
+            {
+                let mut info = parse_os_release("");
+                info.name = source
+                    .run_command("sw_vers", &["-productName"])
+                    .ok()
+                    .map(|s| s.trim().to_string());
+                info.version = source
+                    .run_command("sw_vers", &["-productVersion"])
+                    .ok()
+                    .map(|s| s.trim().to_string());
+                Ok(info)
+            }
+
+            #[cfg(not(any(target_os = "linux", target_os = "macos")))]
+            {
+                Ok(parse_os_release(""))
+            }
+        }
+
+        #[allow(unused_variables)]
+        fn query_kernel(source: &dyn HardwareSource) -> Result<String, HardwareError> {
+            #[cfg(target_os = "linux")]
+            {
+                Ok(source
+                    .read_file("/proc/sys/kernel/osrelease")?
+                    .trim()
+                    .to_string())
+            }
+
+            #[cfg(all(unix, not(target_os = "linux")))]
+            {
+                Ok(source.run_command("uname", &["-r"])?.trim().to_string())
+            }
+
+            #[cfg(not(unix))]
+            {
+                Err(HardwareError::UnsupportedPlatform)
+            }
+        }
+
+        fn query_hostname(source: &dyn HardwareSource) -> Result<String, HardwareError> {
+            #[cfg(target_os = "linux")]
+            let hostname = source.read_file("/proc/sys/kernel/hostname")?;
+
+            #[cfg(not(target_os = "linux"))]
+            let hostname = source.run_command("hostname", &[])?;
+
+            let hostname = hostname.trim();
+            if hostname.is_empty() {
+                return Err(HardwareError::IdNotFound);
+            }
+            Ok(hostname.to_string())
+        }
+
+        #[allow(unused_variables)]
+        fn query_dmi(source: &dyn HardwareSource) -> Result<DmiInfo, HardwareError> {
+            #[cfg(target_os = "linux")]
+            {
+                let read = |name: &str| {
+                    source
+                        .read_file(&format!("/sys/class/dmi/id/{}", name))
+                        .ok()
+                        .map(|s| s.trim().to_string())
+                        .filter(|s| !s.is_empty())
+                };
+                let info = DmiInfo {
+                    sys_vendor: read("sys_vendor"),
+                    product_name: read("product_name"),
+                    product_version: read("product_version"),
+                    board_vendor: read("board_vendor"),
+                    board_name: read("board_name"),
+                    bios_vendor: read("bios_vendor"),
+                    bios_version: read("bios_version"),
+                    bios_date: read("bios_date"),
+                };
+                if info == DmiInfo::default() {
+                    return Err(HardwareError::IdNotFound);
+                }
+                Ok(info)
+            }
+
+            #[cfg(not(target_os = "linux"))]
+            {
+                Err(HardwareError::UnsupportedPlatform)
+            }
+        }
+
+        #[allow(unused_variables)]
+        fn query_memory(source: &dyn HardwareSource) -> Result<MemoryInfo, HardwareError> {
+            #[cfg(target_os = "linux")]
+            {
+                parse_meminfo(&source.read_file("/proc/meminfo")?)
+            }
+
+            #[cfg(not(target_os = "linux"))]
+            {
+                Err(HardwareError::UnsupportedPlatform)
+            }
+        }
+
+        #[allow(unused_variables)]
+        fn query_gpus(source: &dyn HardwareSource) -> Result<Vec<GpuDevice>, HardwareError> {
+            #[cfg(target_os = "linux")]
+            {
+                read_gpu_devices_from(source, SYSFS_DRM_PATH)
+            }
+
+            #[cfg(not(target_os = "linux"))]
+            {
+                Err(HardwareError::UnsupportedPlatform)
+            }
+        }
+
+        #[allow(unused_variables)]
+        fn query_uptime(source: &dyn HardwareSource) -> Result<u64, HardwareError> {
+            #[cfg(target_os = "linux")]
+            {
+                parse_uptime(&source.read_file("/proc/uptime")?)
+            }
+
+            #[cfg(not(target_os = "linux"))]
+            {
+                Err(HardwareError::UnsupportedPlatform)
+            }
+        }
+    }
+
+    /// C接口错误码，与 `HardwareError` 一一对应
+    /// cbindgen:prefix-with-name
+    #[repr(C)]
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum HardwareErrorCode {
+        Success = 0,
+        UnsupportedPlatform = 1,
+        AccessDenied = 2,
+        IdNotFound = 3,
+        IoError = 4,
+        CommandFailed = 5,
+        InvalidFormat = 6,
+        NullPointerError = 7,
+        InvalidSalt = 8,
+        VirtualizedEnvironment = 9,
+        DerivationFailed = 10,
+    }
+
+    // C接口辅助函数：错误转换
+    pub(crate) fn hardware_error_to_code(err: &HardwareError) -> HardwareErrorCode {
+        match err {
+            HardwareError::UnsupportedPlatform => HardwareErrorCode::UnsupportedPlatform,
+            HardwareError::AccessDenied(_) => HardwareErrorCode::AccessDenied,
+            HardwareError::IdNotFound => HardwareErrorCode::IdNotFound,
+            HardwareError::IoError(_) => HardwareErrorCode::IoError,
+            HardwareError::CommandFailed(_) => HardwareErrorCode::CommandFailed,
+            HardwareError::InvalidFormat => HardwareErrorCode::InvalidFormat,
+            HardwareError::InvalidSalt => HardwareErrorCode::InvalidSalt,
+            HardwareError::VirtualizedEnvironment(_) => HardwareErrorCode::VirtualizedEnvironment,
+            HardwareError::DerivationFailed(_) => HardwareErrorCode::DerivationFailed,
+        }
+    }
+
+    // C接口辅助函数：把结果写成C字符串，调用方需用 free_c_string 释放
+    fn write_hardware_string(
+        result: Result<String, HardwareError>,
+        out_str: *mut *mut c_char,
+    ) -> HardwareErrorCode {
+        if out_str.is_null() {
+            return HardwareErrorCode::NullPointerError;
+        }
+
+        match result {
+            // 硬件信息中不应出现 NUL，出现时视为格式错误
+            Ok(value) => match CString::new(value) {
+                Ok(c_str) => {
+                    unsafe { *out_str = c_str.into_raw() };
+                    HardwareErrorCode::Success
+                }
+                Err(_) => HardwareErrorCode::InvalidFormat,
+            },
+            Err(e) => hardware_error_to_code(&e),
+        }
+    }
+
+    // C接口：获取设备唯一标识符，结果需用 free_c_string 释放
+    #[unsafe(no_mangle)]
+    pub extern "C" fn get_device_id_c(out_id: *mut *mut c_char) -> HardwareErrorCode {
+        write_hardware_string(HardwareInfo::get_device_id(), out_id)
+    }
+
+    // C接口：使用应用专属的盐派生 v2 设备ID，结果需用 free_c_string 释放
+    #[unsafe(no_mangle)]
+    pub extern "C" fn get_device_id_with_salt_c(
+        salt: *const u8,
+        salt_len: usize,
+        out_id: *mut *mut c_char,
+    ) -> HardwareErrorCode {
+        if salt.is_null() {
+            return HardwareErrorCode::NullPointerError;
+        }
+
+        let salt_slice = unsafe { std::slice::from_raw_parts(salt, salt_len) };
+        write_hardware_string(HardwareInfo::get_device_id_with_salt(salt_slice), out_id)
+    }
+
+    // C接口：获取主板ID，结果需用 free_c_string 释放
+    #[unsafe(no_mangle)]
+    pub extern "C" fn get_motherboard_id_c(out_id: *mut *mut c_char) -> HardwareErrorCode {
+        write_hardware_string(HardwareInfo::get_motherboard_id(), out_id)
+    }
+
+    // C接口：获取CPU信息，结果需用 free_c_string 释放
+    #[unsafe(no_mangle)]
+    pub extern "C" fn get_cpu_info_c(out_info: *mut *mut c_char) -> HardwareErrorCode {
+        write_hardware_string(HardwareInfo::get_cpu_info(), out_info)
+    }
+
+    // C接口：获取主网络接口的MAC地址，结果需用 free_c_string 释放
+    #[unsafe(no_mangle)]
+    pub extern "C" fn get_primary_mac_c(out_mac: *mut *mut c_char) -> HardwareErrorCode {
+        write_hardware_string(HardwareInfo::get_primary_mac(), out_mac)
+    }
+
+    // C接口辅助函数：把可序列化的值写成 JSON 字符串
+    fn write_hardware_json<T: Serialize>(
+        result: Result<T, HardwareError>,
+        out_json: *mut *mut c_char,
+    ) -> HardwareErrorCode {
+        write_hardware_string(
+            result.and_then(|value| {
+                serde_json::to_string(&value).map_err(|_| HardwareError::InvalidFormat)
+            }),
+            out_json,
+        )
+    }
+
+    // C接口：以 JSON 数组列出物理块设备，结果需用 free_c_string 释放
+    #[unsafe(no_mangle)]
+    pub extern "C" fn list_block_devices_json_c(out_json: *mut *mut c_char) -> HardwareErrorCode {
+        write_hardware_json(HardwareInfo::list_block_devices(), out_json)
+    }
+
+    // C接口：使用应用专属的盐采集硬件指纹并输出 JSON，include_disks 控制是否包含磁盘序列号，
+    // 结果需用 free_c_string 释放
+    #[unsafe(no_mangle)]
+    pub extern "C" fn get_hardware_fingerprint_json_c(
+        salt: *const u8,
+        salt_len: usize,
+        include_disks: bool,
+        out_json: *mut *mut c_char,
+    ) -> HardwareErrorCode {
+        if salt.is_null() {
+            return HardwareErrorCode::NullPointerError;
+        }
+
+        let salt_slice = unsafe { std::slice::from_raw_parts(salt, salt_len) };
+        let options = FingerprintOptions { include_disks };
+        write_hardware_json(
+            HardwareFingerprint::collect_with(&SystemSource, salt_slice, &options),
+            out_json,
+        )
+    }
+
+    // C接口：检测虚拟机和容器，结果为 JSON，需用 free_c_string 释放
+    #[unsafe(no_mangle)]
+    pub extern "C" fn detect_virtualization_json_c(
+        out_json: *mut *mut c_char,
+    ) -> HardwareErrorCode {
+        write_hardware_json(Ok(HardwareInfo::detect_virtualization()), out_json)
+    }
+
+    // C接口：按虚拟化策略派生 v2 设备ID，结果需用 free_c_string 释放
+    #[unsafe(no_mangle)]
+    pub extern "C" fn get_device_id_with_policy_c(
+        salt: *const u8,
+        salt_len: usize,
+        policy: VirtualizationPolicy,
+        out_id: *mut *mut c_char,
+    ) -> HardwareErrorCode {
+        if salt.is_null() {
+            return HardwareErrorCode::NullPointerError;
+        }
+
+        let salt_slice = unsafe { std::slice::from_raw_parts(salt, salt_len) };
+        write_hardware_string(
+            HardwareInfo::get_device_id_with_policy(salt_slice, policy),
+            out_id,
+        )
+    }
+
+    // C接口：采集系统清单并输出 JSON，单项采集失败记录在对应字段中，
+    // 结果需用 free_c_string 释放
+    #[unsafe(no_mangle)]
+    pub extern "C" fn get_system_inventory_json_c(out_json: *mut *mut c_char) -> HardwareErrorCode {
+        write_hardware_json(Ok(SystemInventory::collect()), out_json)
+    }
+
+    // 测试代码
+    #[test]
+    fn main() {
+        match HardwareInfo::get_device_id() {
+            Ok(id) => println!("Device ID: {}", id),
+            Err(e) => eprintln!("Error getting device ID: {}", e),
+        }
+
+        match HardwareInfo::get_motherboard_id() {
+            Ok(id) => println!("Motherboard ID: {}", id),
+            Err(e) => eprintln!("Error getting motherboard ID: {}", e),
+        }
+
+        match HardwareInfo::get_cpu_info() {
+            Ok(info) => println!("CPU Info: {}", info),
+            Err(e) => eprintln!("Error getting CPU info: {}", e),
+        }
+
+        match HardwareInfo::get_primary_mac() {
+            Ok(mac) => println!("Primary MAC: {}", mac),
+            Err(e) => eprintln!("Error getting MAC address: {}", e),
+        }
+
+        // C接口：空指针应直接返回错误码
+        assert_eq!(
+            get_device_id_c(std::ptr::null_mut()),
+            HardwareErrorCode::NullPointerError
+        );
+        let mut c_info: *mut c_char = std::ptr::null_mut();
+        let code = get_cpu_info_c(&mut c_info);
+        match HardwareInfo::get_cpu_info() {
+            Ok(info) => {
+                assert_eq!(code, HardwareErrorCode::Success);
+                let c_str = unsafe { CString::from_raw(c_info) };
+                assert_eq!(c_str.to_str().unwrap(), info);
+            }
+            Err(e) => assert_eq!(code, hardware_error_to_code(&e)),
+        }
+    }
+
+    #[test]
+    fn test_c_string_and_json_exports() {
+        // 取回C接口输出的字符串并释放
+        fn take(ptr: *mut c_char) -> String {
+            assert!(!ptr.is_null());
+            unsafe { CString::from_raw(ptr) }.into_string().unwrap()
+        }
+
+        let mut out: *mut c_char = std::ptr::null_mut();
+        assert_eq!(
+            write_hardware_string(Ok("ABC-123".to_string()), &mut out),
+            HardwareErrorCode::Success
+        );
+        assert_eq!(take(out), "ABC-123");
+
+        // 值中含 NUL 无法转换为C字符串，输出指针保持不变
+        let mut out: *mut c_char = std::ptr::null_mut();
+        assert_eq!(
+            write_hardware_string(Ok("ABC\0DEF".to_string()), &mut out),
+            HardwareErrorCode::InvalidFormat
+        );
+        assert!(out.is_null());
+        assert_eq!(
+            write_hardware_string(Ok("ABC".to_string()), std::ptr::null_mut()),
+            HardwareErrorCode::NullPointerError
+        );
+
+        let cases = [
+            (
+                HardwareError::UnsupportedPlatform,
+                HardwareErrorCode::UnsupportedPlatform,
+            ),
+            (
+                HardwareError::AccessDenied("denied".into()),
+                HardwareErrorCode::AccessDenied,
+            ),
+            (HardwareError::IdNotFound, HardwareErrorCode::IdNotFound),
+            (
+                HardwareError::IoError(std::io::Error::other("io")),
+                HardwareErrorCode::IoError,
+            ),
+            (
+                HardwareError::CommandFailed("exit 1".into()),
+                HardwareErrorCode::CommandFailed,
+            ),
+            (
+                HardwareError::InvalidFormat,
+                HardwareErrorCode::InvalidFormat,
+            ),
+            (HardwareError::InvalidSalt, HardwareErrorCode::InvalidSalt),
+            (
+                HardwareError::VirtualizedEnvironment("kvm".into()),
+                HardwareErrorCode::VirtualizedEnvironment,
+            ),
+            (
+                HardwareError::DerivationFailed("hmac".into()),
+                HardwareErrorCode::DerivationFailed,
+            ),
+        ];
+        for (err, expected) in cases {
+            assert_eq!(write_hardware_string(Err(err), &mut out), expected);
+            assert!(out.is_null());
+        }
+
+        let disks = vec!["sda".to_string(), "nvme0n1".to_string()];
+        assert_eq!(
+            write_hardware_json(Ok(disks), &mut out),
+            HardwareErrorCode::Success
+        );
+        assert_eq!(take(out), r#"["sda","nvme0n1"]"#);
+        out = std::ptr::null_mut();
+        assert_eq!(
+            write_hardware_json::<Vec<String>>(Err(HardwareError::IdNotFound), &mut out),
+            HardwareErrorCode::IdNotFound
+        );
+        assert!(out.is_null());
+
+        // 导出函数：空指针和空盐
+        let salt = b"firm_netter-test";
+        assert_eq!(
+            get_device_id_with_salt_c(std::ptr::null(), 0, &mut out),
+            HardwareErrorCode::NullPointerError
+        );
+        assert_eq!(
+            get_device_id_with_salt_c(salt.as_ptr(), salt.len(), std::ptr::null_mut()),
+            HardwareErrorCode::NullPointerError
+        );
+        assert_eq!(
+            get_hardware_fingerprint_json_c(std::ptr::null(), 0, false, &mut out),
+            HardwareErrorCode::NullPointerError
+        );
+        assert_eq!(
+            get_hardware_fingerprint_json_c(salt.as_ptr(), 0, false, &mut out),
+            HardwareErrorCode::InvalidSalt
+        );
+        assert_eq!(
+            get_device_id_with_policy_c(std::ptr::null(), 0, VirtualizationPolicy::Allow, &mut out),
+            HardwareErrorCode::NullPointerError
+        );
+        assert!(out.is_null());
+
+        // 导出函数与对应的 Rust 接口结果一致
+        let code = get_device_id_with_salt_c(salt.as_ptr(), salt.len(), &mut out);
+        match HardwareInfo::get_device_id_with_salt(salt) {
+            Ok(id) => {
+                assert_eq!(code, HardwareErrorCode::Success);
+                assert_eq!(take(out), id);
+            }
+            Err(e) => assert_eq!(code, hardware_error_to_code(&e)),
+        }
+
+        out = std::ptr::null_mut();
+        let code = list_block_devices_json_c(&mut out);
+        match HardwareInfo::list_block_devices() {
+            Ok(devices) => {
+                assert_eq!(code, HardwareErrorCode::Success);
+                let parsed: Vec<BlockDevice> = serde_json::from_str(&take(out)).unwrap();
+                assert_eq!(parsed, devices);
+            }
+            Err(e) => assert_eq!(code, hardware_error_to_code(&e)),
+        }
+
+        out = std::ptr::null_mut();
+        assert_eq!(
+            detect_virtualization_json_c(&mut out),
+            HardwareErrorCode::Success
+        );
+        let parsed: VirtualizationInfo = serde_json::from_str(&take(out)).unwrap();
+        assert_eq!(parsed, HardwareInfo::detect_virtualization());
+
+        out = std::ptr::null_mut();
+        assert_eq!(
+            get_system_inventory_json_c(&mut out),
+            HardwareErrorCode::Success
+        );
+        serde_json::from_str::<SystemInventory>(&take(out)).unwrap();
+    }
+
+    // 生成的头文件必须能被C和C++编译器接受，C中所有枚举值共享同一作用域
+    #[cfg(unix)]
+    #[test]
+    fn test_c_header_compiles() {
+        let header = env!("FIRMNETTER_HEADER");
+        for (var, default, language) in [("CC", "cc", "c"), ("CXX", "c++", "c++")] {
+            let compiler = std::env::var(var).unwrap_or_else(|_| default.to_string());
+            let output = Command::new(&compiler)
+                .args(["-fsyntax-only", "-Wall", "-Werror", "-x", language, header])
+                .output()
+                .unwrap_or_else(|e| panic!("无法运行 {}: {}", compiler, e));
+            assert!(
+                output.status.success(),
+                "{} 无法编译 {}:\n{}",
+                compiler,
+                header,
+                String::from_utf8_lossy(&output.stderr)
+            );
+        }
+    }
+
+    #[test]
+    fn test_fingerprint_fuzzy_match() {
+        let raw = RawHardwareIds {
+            board_uuid: Some("4C4C4544-0042-3510-8056-B4C04F4E3132".into()),
+            board_serial: Some("To be filled by O.E.M.".into()),
+            cpu: Some("Intel(R) Core(TM) i7-8700 CPU @ 3.20GHz".into()),
+            macs: vec!["AA:BB:CC:DD:EE:01".into(), "aa-bb-cc-dd-ee-02".into()],
+            disk_serials: vec!["S3Z9NB0K123456".into()],
+            machine_id: Some("3d1219c7c4c5404aaa1f6d2a48adfda4".into()),
+        };
+        const SALT: &[u8] = b"com.example.product";
+        let original = HardwareFingerprint::from_raw(&raw, SALT).unwrap();
+        assert!(original.board_serial.is_none());
+        assert_eq!(original.macs.len(), 2);
+        // 组件哈希固定写死，任何改变哈希方式的修改都必须递增 FINGERPRINT_VERSION
+        assert_eq!(
+            original.board_uuid.as_deref(),
+            Some("cbed751f57b86aab7ee7c1194eac1200681d8738b40a69810ebb3ed95c931a1f")
+        );
+
+        // 不同产品的盐得到完全无关的组件哈希
+        let other_product = HardwareFingerprint::from_raw(&raw, b"com.example.other").unwrap();
+        assert_ne!(other_product.board_uuid, original.board_uuid);
+        assert!(
+            other_product
+                .macs
+                .iter()
+                .all(|m| !original.macs.contains(m))
+        );
+        assert_eq!(original.compare(&other_product).score, 0.0);
+        assert!(matches!(
+            HardwareFingerprint::from_raw(&raw, b""),
+            Err(HardwareError::InvalidSalt)
+        ));
+
+        // 序列化稳定，且不包含原始值
+        let json = original.to_json().unwrap();
+        assert!(!json.contains("S3Z9NB0K123456"));
+        assert_eq!(HardwareFingerprint::from_json(&json).unwrap(), original);
+        // 版本 1 的无盐指纹不再接受
+        let v1 = json.replace("\"version\":2", "\"version\":1");
+        assert!(matches!(
+            HardwareFingerprint::from_json(&v1),
+            Err(HardwareError::InvalidFormat)
+        ));
+        assert_eq!(original.compare(&original).score, 1.0);
+
+        // 换掉一块网卡仍视为同一设备
+        let mut nic_swapped = raw.clone();
+        nic_swapped.macs[1] = "aa:bb:cc:dd:ee:99".into();
+        let result = original.compare(&HardwareFingerprint::from_raw(&nic_swapped, SALT).unwrap());
+        assert!(result.score < 1.0 && result.is_match(DEFAULT_MATCH_THRESHOLD));
+        assert_eq!(result.changed, vec!["macs"]);
+
+        // 完全不同的机器
+        let other = HardwareFingerprint::from_raw(
+            &RawHardwareIds {
+                board_uuid: Some("11111111-2222-3333-4444-555555555555".into()),
+                cpu: Some("AMD Ryzen 7 5800X".into()),
+                macs: vec!["02:00:00:00:00:01".into()],
+                machine_id: Some("0123456789abcdef0123456789abcdef".into()),
+                ..Default::default()
+            },
+            SALT,
+        )
+        .unwrap();
+        assert!(!original.matches(&other));
+    }
+
+    #[test]
+    fn test_device_id_v2_is_stable() {
+        // 回归测试：期望值固定写死，任何改变派生方式的修改都会使其失败
+        let id = HardwareInfo::derive_device_id(
+            b"com.example.product",
+            "board:4C4C4544-0042-3510-8056-B4C04F4E3132",
+        )
+        .unwrap();
+        assert_eq!(
+            id,
+            "v2:4c1b9f4b0f22ce679b598a405ae2e0517eaf8366448800d4a47183873f7b2595"
+        );
+
+        let other_product = HardwareInfo::derive_device_id(
+            b"com.example.other",
+            "board:4C4C4544-0042-3510-8056-B4C04F4E3132",
+        )
+        .unwrap();
+        assert_eq!(
+            other_product,
+            "v2:49e8ea0a7cd325ac8365ca586a0e94a8e1defbe53f6fa611d995755b83e8602d"
+        );
+
+        assert!(matches!(
+            HardwareInfo::derive_device_id(b"", "board:x"),
+            Err(HardwareError::InvalidSalt)
+        ));
+
+        // 只需要 root 权限的来源不参与派生
+        assert!(!readable_by_all_users(&SourcedValue::new(
+            "4C4C4544-0042-3510-8056-B4C04F4E3132",
+            ValueSource::Command("dmidecode -t 1".into()),
+        )));
+        assert!(readable_by_all_users(&SourcedValue::new(
+            "4C4C4544-0042-3510-8056-B4C04F4E3132",
+            ValueSource::Command("wmic csproduct get uuid".into()),
+        )));
+
+        // 从数据源到ID的完整流程也固定下来，采集逻辑的改动不能悄悄改变已发放的ID
+        #[cfg(target_os = "linux")]
+        {
+            // 普通用户读不到 UUID 和序列号，只能读到主板型号
+            let machine = |mac: &str| {
+                FixtureSource::new()
+                    .with_file("/sys/class/dmi/id/board_name", "X570 AORUS ELITE\n")
+                    .with_file(
+                        "/sys/class/dmi/id/board_vendor",
+                        "Gigabyte Technology Co., Ltd.\n",
+                    )
+                    .with_file(
+                        PROC_CPUINFO_PATH,
+                        "processor\t: 0\nmodel name\t: AMD Ryzen 7 5800X 8-Core Processor\n",
+                    )
+                    .with_file("/sys/class/net/enp5s0/address", mac)
+                    .with_file("/sys/class/net/enp5s0/addr_assign_type", "0\n")
+                    .with_link("/sys/class/net/enp5s0/device", "../../../0000:05:00.0")
+            };
+            let user = machine("00:1b:21:3a:4f:5c\n");
+            assert_eq!(
+                HardwareInfo::device_id_material_from(&user).unwrap(),
+                "cpu:AMD Ryzen 7 5800X 8-Core Processor\nmac:001b213a4f5c"
+            );
+            assert_eq!(
+                HardwareInfo::get_device_id_with_salt_from(&user, b"com.example.product").unwrap(),
+                "v2:c0bdfd0fc134a2c8071027a5f99a5d685c5824805767faec1d21578b3c373000"
+            );
+            assert_ne!(
+                HardwareInfo::get_device_id_with_salt_from(&user, b"com.example.product").unwrap(),
+                HardwareInfo::get_device_id_with_salt_from(
+                    &machine("00:1b:21:3a:4f:5d\n"),
+                    b"com.example.product"
+                )
+                .unwrap()
+            );
+
+            // 回归测试：同一台机器上 root 能读到 UUID 和序列号，ID 仍与普通用户相同
+            let root = machine("00:1b:21:3a:4f:5c\n")
+                .with_file(
+                    "/sys/class/dmi/id/product_uuid",
+                    "4C4C4544-0042-3510-8056-B4C04F4E3132\n",
+                )
+                .with_file("/sys/class/dmi/id/board_serial", "M80-C4004400123\n")
+                .with_file("/sys/class/dmi/id/product_serial", "5X12B34\n");
+            assert_eq!(
+                HardwareInfo::device_id_material_from(&root).unwrap(),
+                HardwareInfo::device_id_material_from(&user).unwrap()
+            );
+            assert_eq!(
+                HardwareInfo::get_device_id_with_salt_from(&root, b"com.example.product").unwrap(),
+                "v2:c0bdfd0fc134a2c8071027a5f99a5d685c5824805767faec1d21578b3c373000"
+            );
+
+            // 设备树序列号所有用户可读，优先于CPU信息+MAC地址，root 下同样不受 DMI 影响
+            let arm_user =
+                FixtureSource::new().with_file(DEVICETREE_SERIAL_PATH, "10000000a3b4c5d6\0");
+            let arm_root = FixtureSource::new()
+                .with_file(DEVICETREE_SERIAL_PATH, "10000000a3b4c5d6\0")
+                .with_file(
+                    "/sys/class/dmi/id/product_uuid",
+                    "4C4C4544-0042-3510-8056-B4C04F4E3132\n",
+                );
+            assert_eq!(
+                HardwareInfo::device_id_material_from(&arm_user).unwrap(),
+                "board:10000000a3b4c5d6"
+            );
+            assert_eq!(
+                HardwareInfo::device_id_material_from(&arm_root).unwrap(),
+                "board:10000000a3b4c5d6"
+            );
+        }
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_device_id_policy() {
+        let salt = b"com.example.product";
+        let cpu_mac = "cpu:AMD EPYC 7763 64-Core Processor\nmac:005056a1b2c3";
+        let machine = |machine_id: &str| {
+            FixtureSource::new()
+                .with_file(
+                    PROC_CPUINFO_PATH,
+                    "processor\t: 0\nmodel name\t: AMD EPYC 7763 64-Core Processor\n",
+                )
+                .with_file("/sys/class/net/ens192/address", "00:50:56:a1:b2:c3\n")
+                .with_file("/sys/class/net/ens192/addr_assign_type", "0\n")
+                .with_link("/sys/class/net/ens192/device", "../../../0000:0b:00.0")
+                .with_file("/etc/machine-id", machine_id)
+        };
+        let vm = |machine_id: &str| {
+            machine(machine_id)
+                .with_file("/sys/class/dmi/id/sys_vendor", "VMware, Inc.\n")
+                .with_cpuid_hypervisor("VMwareVMware")
+        };
+        let policy_id = |source: &FixtureSource, policy| {
+            HardwareInfo::get_device_id_with_policy_from(source, salt, policy)
+        };
+
+        // 物理机上三种策略结果相同
+        let bare_metal = machine("4d1f0a6b8c2e4e7f9a3b5c7d9e1f2a3b\n");
+        let allow = HardwareInfo::derive_device_id(salt, cpu_mac).unwrap();
+        for policy in [
+            VirtualizationPolicy::Allow,
+            VirtualizationPolicy::MixInstance,
+            VirtualizationPolicy::Refuse,
+        ] {
+            assert_eq!(policy_id(&bare_metal, policy).unwrap(), allow);
+        }
+
+        // 虚拟机：Refuse 拒绝，MixInstance 只混入 machine-id，不重复加入MAC
+        let original = vm("4d1f0a6b8c2e4e7f9a3b5c7d9e1f2a3b\n");
+        assert_eq!(
+            policy_id(&original, VirtualizationPolicy::Allow).unwrap(),
+            allow
+        );
+        assert!(matches!(
+            policy_id(&original, VirtualizationPolicy::Refuse),
+            Err(HardwareError::VirtualizedEnvironment(_))
+        ));
+        let mixed = policy_id(&original, VirtualizationPolicy::MixInstance).unwrap();
+        assert_eq!(
+            mixed,
+            HardwareInfo::derive_device_id(
+                salt,
+                &format!("{}\ninstance-id:4d1f0a6b8c2e4e7f9a3b5c7d9e1f2a3b", cpu_mac)
+            )
+            .unwrap()
+        );
+        assert_ne!(mixed, allow);
+
+        // 克隆后MAC不变、machine-id 重新生成，MixInstance 仍能区分两个实例
+        let clone = vm("9b8a7c6d5e4f40312a1b2c3d4e5f6a7b\n");
+        assert_eq!(
+            policy_id(&clone, VirtualizationPolicy::Allow).unwrap(),
+            allow
+        );
+        assert_ne!(
+            policy_id(&clone, VirtualizationPolicy::MixInstance).unwrap(),
+            mixed
+        );
+
+        // 设备ID输入来自序列号时才加入MAC
+        let board = original.with_file(DEVICETREE_SERIAL_PATH, "10000000a3b4c5d6\0");
+        assert_eq!(
+            policy_id(&board, VirtualizationPolicy::MixInstance).unwrap(),
+            HardwareInfo::derive_device_id(
+                salt,
+                "board:10000000a3b4c5d6\ninstance-mac:005056a1b2c3\ninstance-id:4d1f0a6b8c2e4e7f9a3b5c7d9e1f2a3b"
+            )
+            .unwrap()
+        );
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_network_interface_enumeration() {
+        use std::os::unix::fs::symlink;
+
+        let root = std::env::temp_dir().join(format!("fn_sysfs_net_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        let device = root.join("devices");
+        let add = |name: &str, mac: &str, assign: &str, driver: Option<&str>, wireless: bool| {
+            let dir = root.join("net").join(name);
+            std::fs::create_dir_all(&dir).unwrap();
+            std::fs::write(dir.join("address"), format!("{}\n", mac)).unwrap();
+            std::fs::write(dir.join("addr_assign_type"), assign).unwrap();
+            std::fs::write(dir.join("operstate"), "up\n").unwrap();
+            std::fs::write(dir.join("type"), "1\n").unwrap();
+            if let Some(driver) = driver {
+                let dev = device.join(name);
+                std::fs::create_dir_all(device.join("drivers").join(driver)).unwrap();
+                std::fs::create_dir_all(&dev).unwrap();
+                symlink(device.join("drivers").join(driver), dev.join("driver")).unwrap();
+                symlink(&dev, dir.join("device")).unwrap();
+            }
+            if wireless {
+                std::fs::create_dir_all(dir.join("wireless")).unwrap();
+            }
+        };
+        add("docker0", "02:42:ac:11:00:01", "3", None, false);
+        add("wlp2s0", "3C:A9:F4:00:00:02", "0", Some("iwlwifi"), true);
+        add("enp3s0", "00:1b:21:00:00:03", "0", Some("e1000e"), false);
+        add("wlp2s1", "ba:11:22:33:44:55", "1", Some("iwlwifi"), true);
+
+        let interfaces = read_network_interfaces(&root.join("net")).unwrap();
+        assert_eq!(interfaces.len(), 4);
+        let docker = interfaces.iter().find(|i| i.name == "docker0").unwrap();
+        assert!(!docker.physical && docker.locally_administered);
+        let wifi = interfaces.iter().find(|i| i.name == "wlp2s0").unwrap();
+        assert_eq!(wifi.mac.as_deref(), Some("3c:a9:f4:00:00:02"));
+        assert_eq!(wifi.driver.as_deref(), Some("iwlwifi"));
+        assert!(wifi.wireless && wifi.physical);
+        assert_eq!(wifi.link_state, LinkState::Up);
+
+        // 有线网卡优先，虚拟网桥和随机地址被排除
+        let primary = select_primary_interface(&interfaces).unwrap();
+        assert_eq!(primary.name, "enp3s0");
+        let without_wired: Vec<_> = interfaces
+            .iter()
+            .filter(|i| i.name != "enp3s0")
+            .cloned()
+            .collect();
+        assert_eq!(
+            select_primary_interface(&without_wired).unwrap().name,
+            "wlp2s0"
+        );
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_native_sources() {
+        let x86 = "processor\t: 0\nvendor_id\t: GenuineIntel\nmodel name\t: Intel(R) Xeon(R) CPU @ 2.20GHz\n";
+        assert_eq!(
+            parse_cpuinfo_model(x86).as_deref(),
+            Some("Intel(R) Xeon(R) CPU @ 2.20GHz")
+        );
+        let arm = "processor\t: 0\nBogoMIPS\t: 38.40\nCPU part\t: 0xd08\n\nHardware\t: BCM2835\n";
+        assert_eq!(parse_cpuinfo_model(arm).as_deref(), Some("BCM2835"));
+        assert_eq!(parse_cpuinfo_model("processor\t: 0\n"), None);
+
+        let value = SourcedValue::new(
+            "4c4c4544",
+            ValueSource::File("/sys/class/dmi/id/product_uuid".into()),
+        );
+        assert!(is_unique_board_identifier(&value));
+        assert!(!is_unique_board_identifier(&SourcedValue::new(
+            "Z390 AORUS PRO",
+            ValueSource::Registry(
+                "HKLM\\HARDWARE\\DESCRIPTION\\System\\BIOS\\BaseBoardProduct".into()
+            ),
+        )));
+        assert!(!is_unique_board_identifier(&SourcedValue::new(
+            "To be filled by O.E.M.",
+            ValueSource::Command("dmidecode -t 1".into()),
+        )));
+        assert_eq!(
+            value.source.to_string(),
+            "file:/sys/class/dmi/id/product_uuid"
+        );
+        assert_eq!(
+            serde_json::to_string(&value).unwrap(),
+            r#"{"value":"4c4c4544","source":{"kind":"file","detail":"/sys/class/dmi/id/product_uuid"}}"#
+        );
+    }
+
+    #[cfg(all(target_os = "linux", target_endian = "little"))]
+    #[test]
+    fn test_netlink_parsing() {
+        // 在虚拟机上抓取的 RTM_GETLINK 转储（lo、eth0 和 NLMSG_DONE），
+        // 只保留了 IFLA_IFNAME、IFLA_MTU、IFLA_ADDRESS 和 IFLA_PERM_ADDRESS 属性
+        const DUMP: &str = "3c0000001000020001000000f71b000000000403010000004900010000000000\
+                            070003006c6f000008000400000001000a00010000000000000000004c000000\
+                            1000020001000000f71b00000000010004000000431001000000000009000300\
+                            657468300000000008000400780500000a00010002fc0000000100000a003600\
+                            02fc000000010000140000000300020001000000f71b000000000000";
+        let dump = hex::decode(DUMP).unwrap();
+
+        let mut links = Vec::new();
+        assert!(parse_netlink_dump(&dump, 1, &mut links).unwrap());
+        assert_eq!(
+            links,
+            vec![
+                NetlinkLink {
+                    index: 1,
+                    if_type: 772,
+                    flags: 0x10049,
+                    mac: Some([0; 6]),
+                    perm_mac: None,
+                },
+                NetlinkLink {
+                    index: 4,
+                    if_type: ARPHRD_ETHER as u16,
+                    flags: 0x11043,
+                    mac: Some([0x02, 0xfc, 0, 0, 0, 1]),
+                    perm_mac: Some([0x02, 0xfc, 0, 0, 0, 1]),
+                },
+            ]
+        );
+        // 永久地址即使是本地管理地址也可以使用
+        assert_eq!(select_netlink_mac(&links).as_deref(), Some("02fc00000001"));
+
+        // 截断的消息报错，没有 NLMSG_DONE 时继续接收
+        assert!(parse_netlink_dump(&dump[..50], 1, &mut Vec::new()).is_err());
+        let mut partial = Vec::new();
+        assert!(!parse_netlink_dump(&dump[..0x3c], 1, &mut partial).unwrap());
+        assert_eq!(partial.len(), 1);
+        assert_eq!(parse_rtm_newlink(&dump[dump.len() - 20..]), None);
+
+        // 序号不匹配的消息（包括 NLMSG_DONE）被忽略
+        let mut other = Vec::new();
+        assert!(!parse_netlink_dump(&dump, 2, &mut other).unwrap());
+        assert!(other.is_empty());
+
+        // 分多次收到的转储可以拼起来，recv 返回 0 时报错而不是一直循环
+        let mut chunks = vec![&dump[..0x3c], &dump[0x3c..]].into_iter();
+        let received = receive_netlink_dump(1, |buf| {
+            let chunk = chunks.next().unwrap();
+            buf[..chunk.len()].copy_from_slice(chunk);
+            Ok(chunk.len())
+        })
+        .unwrap();
+        assert_eq!(received, links);
+        let eof = receive_netlink_dump(1, |_| Ok(0)).unwrap_err();
+        assert_eq!(eof.kind(), std::io::ErrorKind::UnexpectedEof);
+        let mut calls = 0;
+        let stray = receive_netlink_dump(2, |buf| {
+            calls += 1;
+            if calls > 1 {
+                return Ok(0);
+            }
+            buf[..dump.len()].copy_from_slice(&dump);
+            Ok(dump.len())
+        });
+        assert!(stray.is_err());
+
+        let ether = |index: i32, mac: [u8; 6], perm_mac: Option<[u8; 6]>| NetlinkLink {
+            index,
+            if_type: ARPHRD_ETHER as u16,
+            flags: 0,
+            mac: Some(mac),
+            perm_mac,
+        };
+        let vendor = [0x00, 0x1b, 0x21, 0x3a, 0x4f, 0x5c];
+        let vendor2 = [0x00, 0x1b, 0x21, 0x3a, 0x4f, 0x5d];
+        let random = [0x02, 0x42, 0xac, 0x11, 0x00, 0x02];
+
+        // 永久地址优先于当前地址，即使接口序号更大
+        let links = [ether(2, vendor, None), ether(3, random, Some(vendor2))];
+        assert_eq!(select_netlink_mac(&links).as_deref(), Some("001b213a4f5d"));
+
+        // 没有永久地址时取厂商地址，本地管理地址（veth、网桥）被排除
+        let links = [ether(2, random, None), ether(5, vendor, None)];
+        assert_eq!(select_netlink_mac(&links).as_deref(), Some("001b213a4f5c"));
+        let links = [ether(2, random, None), ether(3, [0; 6], Some([0; 6]))];
+        assert_eq!(select_netlink_mac(&links), None);
+
+        // 同一优先级取接口序号最小的，回环接口被排除
+        let mut loopback = ether(1, vendor2, None);
+        loopback.flags = libc::IFF_LOOPBACK as u32;
+        let links = [ether(7, vendor2, None), ether(4, vendor, None), loopback];
+        assert_eq!(select_netlink_mac(&links).as_deref(), Some("001b213a4f5c"));
+    }
+
+    #[test]
+    fn test_parsers_with_fixtures() {
+        const DMIDECODE_DELL: &str = "# dmidecode 3.3\nGetting SMBIOS data from sysfs.\nSMBIOS 3.2.0 present.\n\nHandle 0x0001, DMI type 1, 27 bytes\nSystem Information\n\tManufacturer: Dell Inc.\n\tProduct Name: OptiPlex 7070\n\tVersion: Not Specified\n\tSerial Number: 8XK2Q53\n\tUUID: 4c4c4544-0058-4b10-8032-b8c04f513533\n\tWake-up Type: Power Switch\n";
+        const DMIDECODE_KVM: &str = "Handle 0x0100, DMI type 1, 27 bytes\nSystem Information\n\tManufacturer: QEMU\n\tProduct Name: Standard PC (Q35 + ICH9, 2009)\n\tSerial Number: None\n\tUUID: 9b6c2f7e-3d1a-4c57-8f0e-2a4b6c8d0e1f\n";
+        const LSHW: &str = "desktop-01\n    description: Desktop Computer\n    product: OptiPlex 7070 (085A)\n    vendor: Dell Inc.\n    serial: 8XK2Q53\n    width: 64 bits\n";
+        const IP_LINK: &str = "1: lo: <LOOPBACK,UP,LOWER_UP> mtu 65536 qdisc noqueue state UNKNOWN mode DEFAULT group default qlen 1000\n    link/loopback 00:00:00:00:00:00 brd 00:00:00:00:00:00\n2: enp3s0: <BROADCAST,MULTICAST,UP,LOWER_UP> mtu 1500 qdisc fq_codel state UP mode DEFAULT group default qlen 1000\n    link/ether 00:1b:21:3a:4f:5c brd ff:ff:ff:ff:ff:ff\n";
+        const GETMAC: &str = "\r\n\"00-1B-21-3A-4F-5C\",\"\\Device\\Tcpip_{6A3C1D2E-0000-4B5A-9C8D-112233445566}\"\r\n";
+        const WMIC_CPU: &str = "Name                                      \r\r\nIntel(R) Core(TM) i7-9700 CPU @ 3.00GHz  \r\r\n\r\r\n";
+        const SYSTEM_PROFILER: &str = "Hardware:\n\n    Hardware Overview:\n\n      Model Name: MacBook Pro\n      Model Identifier: MacBookPro18,3\n      Serial Number (system): C02XK0AAMD6M\n      Hardware UUID: 5E1C4B2A-7F3D-4E6A-9B8C-0D1E2F3A4B5C\n";
+        const IFCONFIG_MACOS: &str = "en0: flags=8863<UP,BROADCAST,SMART,RUNNING,SIMPLEX,MULTICAST> mtu 1500\n\toptions=6463<RXCSUM,TXCSUM,TSO4,TSO6,CHANNEL_IO>\n\tether a4:83:e7:12:34:56\n\tinet 192.168.1.20 netmask 0xffffff00 broadcast 192.168.1.255\n";
+        const PCICONF: &str = "hostb0@pci0:0:0:0:\tclass=0x060000 rev=0x07 hdr=0x00 vendor=0x8086 device=0x3ec2\n    vendor     = 'Intel Corporation'\n    device     = '8th Gen Core Processor Host Bridge/DRAM Registers'\n    class      = bridge\n\nvgapci0@pci0:0:2:0:\tclass=0x030000 rev=0x00 hdr=0x00 vendor=0x8086 device=0x3e92\n";
+
+        assert_eq!(
+            parse_dmidecode_system(DMIDECODE_DELL).as_deref(),
+            Some("8XK2Q53")
+        );
+        assert_eq!(
+            parse_dmidecode_system(DMIDECODE_KVM).as_deref(),
+            Some("9b6c2f7e-3d1a-4c57-8f0e-2a4b6c8d0e1f")
+        );
+        assert_eq!(parse_dmidecode_serial(DMIDECODE_KVM), None);
+        assert_eq!(parse_lshw_serial(LSHW).as_deref(), Some("8XK2Q53"));
+        assert_eq!(parse_ip_link_mac(IP_LINK).as_deref(), Some("001b213a4f5c"));
+        assert_eq!(parse_getmac_csv(GETMAC).as_deref(), Some("001B213A4F5C"));
+        assert_eq!(
+            parse_wmic_value(WMIC_CPU).as_deref(),
+            Some("Intel(R) Core(TM) i7-9700 CPU @ 3.00GHz")
+        );
+        assert_eq!(
+            parse_system_profiler(SYSTEM_PROFILER).as_deref(),
+            Some("5E1C4B2A-7F3D-4E6A-9B8C-0D1E2F3A4B5C")
+        );
+        assert_eq!(
+            parse_ifconfig_mac(IFCONFIG_MACOS).as_deref(),
+            Some("a483e7123456")
+        );
+        assert_eq!(
+            parse_pciconf_host_bridge(PCICONF).as_deref(),
+            Some("Intel Corporation_8th Gen Core Processor Host Bridge/DRAM Registers")
+        );
+
+        #[cfg(target_os = "linux")]
+        {
+            // 没有DMI文件的机器回退到 dmidecode
+            let source = FixtureSource::new()
+                .with_file(
+                    PROC_CPUINFO_PATH,
+                    "processor\t: 0\nmodel name\t: AMD EPYC 7B13\n",
+                )
+                .with_command("dmidecode -t 1", DMIDECODE_KVM)
+                .with_command("ip link show", IP_LINK);
+            let board = HardwareInfo::query_motherboard_id_from(&source).unwrap();
+            assert_eq!(board.value, "9b6c2f7e-3d1a-4c57-8f0e-2a4b6c8d0e1f");
+            assert_eq!(board.source, ValueSource::Command("dmidecode -t 1".into()));
+            assert_eq!(
+                HardwareInfo::query_cpu_info_from(&source).unwrap().value,
+                "AMD EPYC 7B13"
+            );
+            // 既没有 sysfs 也没有 netlink 时使用 ip 命令
+            let mac = HardwareInfo::query_primary_mac_from(&source).unwrap();
+            assert_eq!(mac.value, "001b213a4f5c");
+
+            let source = source.with_netlink_mac(Some("001b213a4f5d"));
+            let mac = HardwareInfo::query_primary_mac_from(&source).unwrap();
+            assert_eq!(mac.source, ValueSource::Netlink);
+
+            // sysfs 优先，docker 网桥被排除
+            let source = FixtureSource::new()
+                .with_file("/sys/class/dmi/id/product_uuid", "4C4C4544-0058-4B10\n")
+                .with_file("/sys/class/net/docker0/address", "02:42:ac:11:00:01\n")
+                .with_file("/sys/class/net/enp3s0/address", "00:1b:21:3a:4f:5c\n")
+                .with_file("/sys/class/net/enp3s0/addr_assign_type", "0\n")
+                .with_link("/sys/class/net/enp3s0/device", "../../../0000:03:00.0")
+                .with_link(
+                    "/sys/class/net/enp3s0/device/driver",
+                    "../../bus/pci/drivers/e1000e",
+                );
+            let mac = HardwareInfo::query_primary_mac_from(&source).unwrap();
+            assert_eq!(mac.value, "001b213a4f5c");
+            assert_eq!(
+                mac.source,
+                ValueSource::File("/sys/class/net/enp3s0/address".into())
+            );
+            let interfaces = HardwareInfo::list_network_interfaces_from(&source).unwrap();
+            assert_eq!(interfaces[1].driver.as_deref(), Some("e1000e"));
+
+            // 容器中只有随机地址的 veth：sysfs 和 netlink 都没有合格网卡时回退到 ip 命令
+            let container = FixtureSource::new()
+                .with_file("/sys/class/net/eth0/address", "02:42:ac:11:00:02\n")
+                .with_file("/sys/class/net/eth0/addr_assign_type", "1\n")
+                .with_netlink_mac(None)
+                .with_command(
+                    "ip link show",
+                    "2: eth0@if7: <BROADCAST,MULTICAST,UP,LOWER_UP> mtu 1500\n    link/ether 02:42:ac:11:00:02 brd ff:ff:ff:ff:ff:ff link-netnsid 0\n",
+                );
+            let mac = HardwareInfo::query_primary_mac_from(&container).unwrap();
+            assert_eq!(mac.value, "0242ac110002");
+            assert_eq!(mac.source, ValueSource::Command("ip link show".into()));
+            assert_eq!(
+                HardwareInfo::query_motherboard_id_from(&source)
+                    .unwrap()
+                    .value,
+                "4C4C4544-0058-4B10"
+            );
+        }
+    }
+
+    #[test]
+    fn test_cpu_details() {
+        // 1 个封装、2 个物理核心、4 个线程
+        let mut cpuinfo = String::new();
+        for (processor, core) in [(0, 0), (1, 1), (2, 0), (3, 1)] {
+            cpuinfo.push_str(&format!(
+                "processor\t: {}\nvendor_id\t: GenuineIntel\ncpu family\t: 6\nmodel\t\t: 158\nmodel name\t: Intel(R) Core(TM) i5-8350U CPU @ 1.70GHz\nstepping\t: 10\nmicrocode\t: 0xf0\ncpu MHz\t\t: 1896.000\nphysical id\t: 0\ncore id\t\t: {}\nflags\t\t: fpu sse2 aes avx2\n\n",
+                processor, core
+            ));
+        }
+        let info = CpuInfo::parse_proc_cpuinfo(&cpuinfo);
+        assert_eq!(info.vendor.as_deref(), Some("GenuineIntel"));
+        assert_eq!(
+            (info.family, info.model, info.stepping),
+            (Some(6), Some(158), Some(10))
+        );
+        assert_eq!(info.microcode.as_deref(), Some("0xf0"));
+        assert_eq!(info.logical_processors, 4);
+        assert_eq!(info.physical_cores, Some(2));
+        assert_eq!(info.sockets, Some(1));
+        assert!(info.has_flag("avx2") && !info.has_flag("avx512f"));
+
+        let mut source = FixtureSource::new()
+            .with_file("/cpu/online", "0-1\n")
+            .with_file("/cpu/cpu0/cpufreq/cpuinfo_max_freq", "3600000\n")
+            .with_file("/cpu/cpu0/cpufreq/cpuinfo_min_freq", "400000\n");
+        for cpu in 0..2 {
+            source = source
+                .with_file(
+                    &format!("/cpu/cpu{}/topology/physical_package_id", cpu),
+                    "0",
+                )
+                .with_file(&format!("/cpu/cpu{}/topology/core_id", cpu), "0");
+        }
+        for (index, level, kind, size) in [
+            (0, "1", "Data", "32K"),
+            (2, "2", "Unified", "256K"),
+            (3, "3", "Unified", "6M"),
+        ] {
+            let dir = format!("/cpu/cpu0/cache/index{}", index);
+            source = source
+                .with_file(&format!("{}/level", dir), level)
+                .with_file(&format!("{}/type", dir), kind)
+                .with_file(&format!("{}/size", dir), size);
+        }
+        let mut detailed = info.clone();
+        detailed.apply_sysfs(&source, "/cpu");
+        assert_eq!(detailed.logical_processors, 2);
+        assert_eq!(detailed.physical_cores, Some(1));
+        assert_eq!(detailed.max_mhz, Some(3600.0));
+        assert_eq!(detailed.caches.len(), 3);
+        assert_eq!(detailed.caches[2].size_kib, 6 * 1024);
+        assert_eq!(
+            detailed.to_string(),
+            "Intel(R) Core(TM) i5-8350U CPU @ 1.70GHz (GenuineIntel, family 6 model 158 stepping 10, 1C/2T, 3600 MHz)"
+        );
+
+        let arm = CpuInfo::parse_proc_cpuinfo(
+            "processor\t: 0\nBogoMIPS\t: 108.00\nFeatures\t: fp asimd evtstrm crc32\nCPU implementer\t: 0x41\nCPU architecture: 8\nCPU variant\t: 0x0\nCPU part\t: 0xd08\nCPU revision\t: 3\n\nHardware\t: BCM2835\n",
+        );
+        assert_eq!(arm.vendor.as_deref(), Some("0x41"));
+        assert_eq!(
+            (arm.family, arm.model, arm.stepping),
+            (Some(8), Some(0xd08), Some(3))
+        );
+        assert_eq!(arm.model_name.as_deref(), Some("BCM2835"));
+        assert!(arm.has_flag("asimd"));
+    }
+
+    #[test]
+    fn test_block_devices() {
+        let source = FixtureSource::new()
+            // NVMe 固态硬盘，带 udev 数据
+            .with_link("/sys/block/nvme0n1/device", "../../nvme0")
+            .with_file("/sys/block/nvme0n1/dev", "259:0\n")
+            .with_file("/sys/block/nvme0n1/size", "1000215216\n")
+            .with_file("/sys/block/nvme0n1/queue/rotational", "0\n")
+            .with_file("/sys/block/nvme0n1/removable", "0\n")
+            .with_file("/sys/block/nvme0n1/device/model", "Samsung SSD 970 EVO Plus 500GB          \n")
+            .with_file(
+                "/run/udev/data/b259:0",
+                "S:disk/by-id/nvme-eui.0025388b91b2c3d4\nE:ID_SERIAL_SHORT=S4EVNF0M123456A\nE:ID_WWN=eui.0025388b91b2c3d4\nE:ID_MODEL=Samsung_SSD_970_EVO_Plus_500GB\nG:systemd\n",
+            )
+            // 机械硬盘，只有 sysfs
+            .with_link("/sys/block/sda/device", "../../../0:0:0:0")
+            .with_file("/sys/block/sda/size", "7814037168\n")
+            .with_file("/sys/block/sda/queue/rotational", "1\n")
+            .with_file("/sys/block/sda/removable", "0\n")
+            .with_file("/sys/block/sda/device/vendor", "ATA     \n")
+            .with_file("/sys/block/sda/device/wwid", "naa.5000c500a1b2c3d4\n")
+            // U盘
+            .with_link("/sys/block/sdb/device", "../../../6:0:0:0")
+            .with_file("/sys/block/sdb/removable", "1\n")
+            .with_file("/sys/block/sdb/device/serial", "4C530001230812345678\n")
+            // loop 设备没有 device 链接
+            .with_file("/sys/block/loop0/size", "0\n");
+
+        let disks = read_block_devices_from(&source, "/sys/block", "/run/udev/data").unwrap();
+        assert_eq!(
+            disks.iter().map(|d| d.name.as_str()).collect::<Vec<_>>(),
+            vec!["nvme0n1", "sda", "sdb"]
+        );
+        let nvme = &disks[0];
+        assert_eq!(nvme.serial.as_deref(), Some("S4EVNF0M123456A"));
+        assert_eq!(nvme.wwn.as_deref(), Some("eui.0025388b91b2c3d4"));
+        assert_eq!(
+            nvme.model.as_deref(),
+            Some("Samsung SSD 970 EVO Plus 500GB")
+        );
+        assert_eq!(nvme.size_bytes, 1000215216 * 512);
+        assert!(!nvme.rotational && !nvme.removable);
+        let hdd = &disks[1];
+        assert!(hdd.rotational);
+        assert_eq!(hdd.identity(), Some("naa.5000c500a1b2c3d4"));
+        assert!(disks[2].removable);
+
+        // 指纹只包含固定磁盘，且可以关闭
+        #[cfg(target_os = "linux")]
+        {
+            let source = source.with_file("/etc/machine-id", "3d1219c7c4c5404aaa1f6d2a48adfda4\n");
+            let with_disks = HardwareFingerprint::collect_from(&source, b"salt").unwrap();
+            assert_eq!(with_disks.disk_serials.len(), 2);
+            let options = FingerprintOptions {
+                include_disks: false,
+            };
+            let without = HardwareFingerprint::collect_with(&source, b"salt", &options).unwrap();
+            assert!(without.disk_serials.is_empty());
+            assert_eq!(with_disks.compare(&without).changed, vec!["disk_serials"]);
+        }
+    }
+
+    #[test]
+    fn test_virtualization_detection() {
+        // 物理机：没有任何迹象
+        let bare_metal = FixtureSource::new()
+            .with_file("/sys/class/dmi/id/sys_vendor", "Dell Inc.\n")
+            .with_file(PROC_CPUINFO_PATH, "processor\t: 0\nflags\t\t: fpu sse2\n")
+            .with_file("/proc/1/cgroup", "0::/init.scope\n");
+        let info = detect_virtualization_from(&bare_metal);
+        assert_eq!(info.confidence, Confidence::None);
+        assert!(!info.is_virtualized(Confidence::Low));
+
+        // VMware 虚拟机：DMI 和 CPUID 都命中
+        let vmware = FixtureSource::new()
+            .with_file("/sys/class/dmi/id/sys_vendor", "VMware, Inc.\n")
+            .with_cpuid_hypervisor("VMwareVMware");
+        let info = detect_virtualization_from(&vmware);
+        assert_eq!(info.hypervisor, Some(Hypervisor::VMware));
+        assert_eq!(info.confidence, Confidence::High);
+        assert_eq!(info.evidence.len(), 2);
+
+        // 只有 cpuinfo 标志：类型未知，中等可信度
+        let flagged = FixtureSource::new().with_file(
+            PROC_CPUINFO_PATH,
+            "processor\t: 0\nflags\t\t: fpu hypervisor\n",
+        );
+        let info = detect_virtualization_from(&flagged);
+        assert_eq!(info.hypervisor, Some(Hypervisor::Other("unknown".into())));
+        assert_eq!(info.confidence, Confidence::Medium);
+
+        // Kubernetes 中的容器
+        let pod = FixtureSource::new().with_file(
+            "/proc/1/cgroup",
+            "12:memory:/kubepods/besteffort/pod5f3c/0123456789abcdef\n",
+        );
+        let info = detect_virtualization_from(&pod);
+        assert_eq!(info.container, Some(ContainerRuntime::Kubernetes));
+        assert!(info.is_container() && !info.is_virtual_machine());
+
+        // 开启 VBS 的物理 Windows 主机：只有 CPUID 的 "Microsoft Hv"
+        let vbs_host = FixtureSource::new()
+            .with_registry(WINDOWS_BIOS_KEY, "SystemManufacturer", "Dell Inc.")
+            .with_registry(WINDOWS_BIOS_KEY, "SystemProductName", "Latitude 7420")
+            .with_cpuid_hypervisor("Microsoft Hv");
+        let info = detect_virtualization_from(&vbs_host);
+        assert_eq!(info.confidence, Confidence::Low);
+        assert_eq!(info.hypervisor, None);
+        assert!(!info.is_virtualized(Confidence::Medium));
+
+        // Hyper-V 虚拟机：注册表中的产品名确认了 CPUID 的结果
+        let hyperv_guest = FixtureSource::new()
+            .with_registry(
+                WINDOWS_BIOS_KEY,
+                "SystemManufacturer",
+                "Microsoft Corporation",
+            )
+            .with_registry(WINDOWS_BIOS_KEY, "SystemProductName", "Virtual Machine")
+            .with_cpuid_hypervisor("Microsoft Hv");
+        let info = detect_virtualization_from(&hyperv_guest);
+        assert_eq!(info.hypervisor, Some(Hypervisor::HyperV));
+        assert_eq!(info.confidence, Confidence::High);
+
+        let podman =
+            FixtureSource::new().with_file("/run/.containerenv", "engine=\"podman-4.9\"\n");
+        assert_eq!(
+            detect_virtualization_from(&podman).container,
+            Some(ContainerRuntime::Podman)
+        );
+        assert_eq!(hypervisor_from_cpuid("KVMKVMKVM\0\0\0"), Hypervisor::Kvm);
+        assert_eq!(hypervisor_from_dmi("Microsoft Corporation"), None);
+        assert_eq!(
+            hypervisor_from_dmi("Virtual Machine"),
+            Some(Hypervisor::HyperV)
+        );
+    }
+
+    #[test]
+    fn test_system_inventory() {
+        let os = parse_os_release(
+            "NAME=\"Ubuntu\"\nVERSION_ID=\"22.04\"\nID=ubuntu\nPRETTY_NAME=\"Ubuntu 22.04.4 LTS\"\n",
+        );
+        assert_eq!(os.name.as_deref(), Some("Ubuntu 22.04.4 LTS"));
+        assert_eq!(os.id.as_deref(), Some("ubuntu"));
+        assert_eq!(os.version.as_deref(), Some("22.04"));
+
+        let memory = parse_meminfo(
+            "MemTotal:        8000000 kB\nMemFree:  100 kB\nMemAvailable:    4000000 kB\nSwapTotal:             0 kB\nHugePages_Total:       0\n",
+        )
+        .unwrap();
+        assert_eq!(memory.total_bytes, 8_000_000 * 1024);
+        assert_eq!(memory.available_bytes, Some(4_000_000 * 1024));
+        assert_eq!(memory.swap_total_bytes, Some(0));
+        assert!(parse_meminfo("MemFree: 1 kB\n").is_err());
+
+        assert_eq!(parse_uptime("3168.30 2264.66\n").unwrap(), 3168);
+        assert!(parse_uptime("").is_err());
+
+        let source = FixtureSource::new()
+            .with_file("/sys/class/drm/card0/device/vendor", "0x10de\n")
+            .with_file("/sys/class/drm/card0/device/device", "0x2204\n")
+            .with_link("/sys/class/drm/card0/device", "../../../0000:01:00.0")
+            .with_link(
+                "/sys/class/drm/card0/device/driver",
+                "../../../bus/pci/drivers/nvidia",
+            )
+            .with_file("/sys/class/drm/card0-HDMI-A-1/status", "connected\n");
+        let gpus = read_gpu_devices_from(&source, SYSFS_DRM_PATH).unwrap();
+        assert_eq!(gpus.len(), 1);
+        assert_eq!(gpus[0].vendor.as_deref(), Some("NVIDIA"));
+        assert_eq!(gpus[0].driver.as_deref(), Some("nvidia"));
+        assert_eq!(gpus[0].bus_address.as_deref(), Some("0000:01:00.0"));
+        assert!(
+            read_gpu_devices_from(&FixtureSource::new(), SYSFS_DRM_PATH)
+                .unwrap()
+                .is_empty()
+        );
+
+        // 单项失败不影响其他字段
+        let inventory = SystemInventory::collect_from(
+            &FixtureSource::new()
+                .with_file("/proc/sys/kernel/hostname", "field-unit-17\n")
+                .with_file("/proc/uptime", "42.5 10.0\n"),
+        );
+        #[cfg(target_os = "linux")]
+        {
+            assert_eq!(
+                inventory.hostname,
+                InventoryField::Value("field-unit-17".to_string())
+            );
+            assert_eq!(inventory.uptime_seconds.value(), Some(&42));
+            assert!(!inventory.memory.is_ok());
+        }
+
+        let json = inventory.to_json().unwrap();
+        let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert!(value["memory"]["error"].is_string());
+        let restored: SystemInventory = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored, inventory);
     }
 }
@@ -0,0 +1,595 @@
+pub mod hash {
+    use crate::safe::safe::{CryptoError, CryptoErrorCode, base64_encode, crypto_error_to_code};
+    use memmap2::Mmap;
+    use openssl::{
+        md::{Md, MdRef},
+        md_ctx::MdCtx,
+        memcmp,
+        nid::Nid,
+        pkey::PKey,
+    };
+    use std::ffi::{CStr, CString, c_char};
+    use std::fs::File;
+    use std::io::{self, Read, Write};
+    use std::path::Path;
+
+    // openssl crate 没有导出 BLAKE2 的 Nid 常量，数值取自 OpenSSL 的 obj_mac.h（1.1.0 起不变）
+    const NID_BLAKE2B512: i32 = 1056;
+    const NID_BLAKE2S256: i32 = 1057;
+
+    const READ_CHUNK: usize = 64 * 1024;
+
+    /// 支持的摘要算法
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    pub enum HashAlgorithm {
+        Sha224,
+        Sha256,
+        Sha384,
+        Sha512,
+        Sha3_224,
+        Sha3_256,
+        Sha3_384,
+        Sha3_512,
+        Blake2b512,
+        Blake2s256,
+    }
+
+    impl HashAlgorithm {
+        fn nid(self) -> Nid {
+            match self {
+                HashAlgorithm::Sha224 => Nid::SHA224,
+                HashAlgorithm::Sha256 => Nid::SHA256,
+                HashAlgorithm::Sha384 => Nid::SHA384,
+                HashAlgorithm::Sha512 => Nid::SHA512,
+                HashAlgorithm::Sha3_224 => Nid::SHA3_224,
+                HashAlgorithm::Sha3_256 => Nid::SHA3_256,
+                HashAlgorithm::Sha3_384 => Nid::SHA3_384,
+                HashAlgorithm::Sha3_512 => Nid::SHA3_512,
+                HashAlgorithm::Blake2b512 => Nid::from_raw(NID_BLAKE2B512),
+                HashAlgorithm::Blake2s256 => Nid::from_raw(NID_BLAKE2S256),
+            }
+        }
+
+        fn md(self) -> Result<&'static MdRef, CryptoError> {
+            Md::from_nid(self.nid()).ok_or_else(|| {
+                CryptoError::HashFailed(format!("当前 OpenSSL 不支持 {}", self.name()))
+            })
+        }
+
+        /// 摘要长度（字节）
+        pub fn output_len(self) -> usize {
+            match self {
+                HashAlgorithm::Sha224 | HashAlgorithm::Sha3_224 => 28,
+                HashAlgorithm::Sha256 | HashAlgorithm::Sha3_256 | HashAlgorithm::Blake2s256 => 32,
+                HashAlgorithm::Sha384 | HashAlgorithm::Sha3_384 => 48,
+                HashAlgorithm::Sha512 | HashAlgorithm::Sha3_512 | HashAlgorithm::Blake2b512 => 64,
+            }
+        }
+
+        pub fn name(self) -> &'static str {
+            match self {
+                HashAlgorithm::Sha224 => "SHA-224",
+                HashAlgorithm::Sha256 => "SHA-256",
+                HashAlgorithm::Sha384 => "SHA-384",
+                HashAlgorithm::Sha512 => "SHA-512",
+                HashAlgorithm::Sha3_224 => "SHA3-224",
+                HashAlgorithm::Sha3_256 => "SHA3-256",
+                HashAlgorithm::Sha3_384 => "SHA3-384",
+                HashAlgorithm::Sha3_512 => "SHA3-512",
+                HashAlgorithm::Blake2b512 => "BLAKE2b-512",
+                HashAlgorithm::Blake2s256 => "BLAKE2s-256",
+            }
+        }
+    }
+
+    /// 摘要或 HMAC 结果
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub struct Digest {
+        algorithm: HashAlgorithm,
+        bytes: Vec<u8>,
+    }
+
+    impl Digest {
+        pub fn algorithm(&self) -> HashAlgorithm {
+            self.algorithm
+        }
+
+        pub fn as_bytes(&self) -> &[u8] {
+            &self.bytes
+        }
+
+        pub fn into_bytes(self) -> Vec<u8> {
+            self.bytes
+        }
+
+        /// 小写十六进制
+        pub fn to_hex(&self) -> String {
+            hex::encode(&self.bytes)
+        }
+
+        /// 标准 Base64
+        pub fn to_base64(&self) -> String {
+            base64_encode(&self.bytes)
+        }
+
+        /// 与期望值做常量时间比较
+        pub fn matches(&self, expected: &[u8]) -> bool {
+            self.bytes.len() == expected.len() && memcmp::eq(&self.bytes, expected)
+        }
+    }
+
+    impl AsRef<[u8]> for Digest {
+        fn as_ref(&self) -> &[u8] {
+            &self.bytes
+        }
+    }
+
+    fn hash_error(err: openssl::error::ErrorStack) -> CryptoError {
+        CryptoError::HashFailed(err.to_string())
+    }
+
+    /// 增量摘要，实现了 `io::Write`，可直接配合 `io::copy` 使用
+    pub struct Hasher {
+        algorithm: HashAlgorithm,
+        ctx: MdCtx,
+    }
+
+    impl Hasher {
+        pub fn new(algorithm: HashAlgorithm) -> Result<Self, CryptoError> {
+            let mut ctx = MdCtx::new().map_err(hash_error)?;
+            ctx.digest_init(algorithm.md()?).map_err(hash_error)?;
+            Ok(Hasher { algorithm, ctx })
+        }
+
+        pub fn update(&mut self, data: &[u8]) -> Result<(), CryptoError> {
+            self.ctx.digest_update(data).map_err(hash_error)
+        }
+
+        pub fn finalize(mut self) -> Result<Digest, CryptoError> {
+            let mut bytes = vec![0u8; self.algorithm.output_len()];
+            let len = self.ctx.digest_final(&mut bytes).map_err(hash_error)?;
+            bytes.truncate(len);
+            Ok(Digest {
+                algorithm: self.algorithm,
+                bytes,
+            })
+        }
+    }
+
+    impl Write for Hasher {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.update(buf).map_err(io::Error::other)?;
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    /// 增量 HMAC
+    pub struct Hmac {
+        algorithm: HashAlgorithm,
+        ctx: MdCtx,
+    }
+
+    impl Hmac {
+        pub fn new(algorithm: HashAlgorithm, key: &[u8]) -> Result<Self, CryptoError> {
+            let pkey = PKey::hmac(key).map_err(hash_error)?;
+            let mut ctx = MdCtx::new().map_err(hash_error)?;
+            ctx.digest_sign_init(Some(algorithm.md()?), &pkey)
+                .map_err(hash_error)?;
+            Ok(Hmac { algorithm, ctx })
+        }
+
+        pub fn update(&mut self, data: &[u8]) -> Result<(), CryptoError> {
+            self.ctx.digest_sign_update(data).map_err(hash_error)
+        }
+
+        pub fn finalize(mut self) -> Result<Digest, CryptoError> {
+            let mut bytes = Vec::new();
+            self.ctx
+                .digest_sign_final_to_vec(&mut bytes)
+                .map_err(hash_error)?;
+            Ok(Digest {
+                algorithm: self.algorithm,
+                bytes,
+            })
+        }
+
+        /// 常量时间校验，标签不符时返回 `MacVerificationFailed`
+        pub fn verify(self, expected: &[u8]) -> Result<(), CryptoError> {
+            if self.finalize()?.matches(expected) {
+                Ok(())
+            } else {
+                Err(CryptoError::MacVerificationFailed)
+            }
+        }
+    }
+
+    impl Write for Hmac {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.update(buf).map_err(io::Error::other)?;
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    /// 一次性计算摘要
+    pub fn hash(algorithm: HashAlgorithm, data: &[u8]) -> Result<Digest, CryptoError> {
+        let mut hasher = Hasher::new(algorithm)?;
+        hasher.update(data)?;
+        hasher.finalize()
+    }
+
+    pub fn hash_hex(algorithm: HashAlgorithm, data: &[u8]) -> Result<String, CryptoError> {
+        hash(algorithm, data).map(|d| d.to_hex())
+    }
+
+    pub fn hash_base64(algorithm: HashAlgorithm, data: &[u8]) -> Result<String, CryptoError> {
+        hash(algorithm, data).map(|d| d.to_base64())
+    }
+
+    /// 从任意 Reader 分块读取并计算摘要
+    pub fn hash_reader<R: Read>(
+        algorithm: HashAlgorithm,
+        reader: &mut R,
+    ) -> Result<Digest, CryptoError> {
+        let mut hasher = Hasher::new(algorithm)?;
+        let mut buf = vec![0u8; READ_CHUNK];
+        loop {
+            let n = reader
+                .read(&mut buf)
+                .map_err(|e| CryptoError::IoError(e.to_string()))?;
+            if n == 0 {
+                break;
+            }
+            hasher.update(&buf[..n])?;
+        }
+        hasher.finalize()
+    }
+
+    /// 以只读 mmap 方式计算文件摘要，空文件按空输入处理
+    pub fn hash_file<P: AsRef<Path>>(
+        algorithm: HashAlgorithm,
+        path: P,
+    ) -> Result<Digest, CryptoError> {
+        let file = File::open(path).map_err(|e| CryptoError::IoError(e.to_string()))?;
+        let len = file
+            .metadata()
+            .map_err(|e| CryptoError::IoError(e.to_string()))?
+            .len();
+        if len == 0 {
+            return hash(algorithm, &[]);
+        }
+        let map = unsafe { Mmap::map(&file) }.map_err(|e| CryptoError::IoError(e.to_string()))?;
+        hash(algorithm, &map)
+    }
+
+    /// 一次性计算 HMAC
+    pub fn hmac(algorithm: HashAlgorithm, key: &[u8], data: &[u8]) -> Result<Digest, CryptoError> {
+        let mut mac = Hmac::new(algorithm, key)?;
+        mac.update(data)?;
+        mac.finalize()
+    }
+
+    /// 校验 HMAC，比较为常量时间
+    pub fn hmac_verify(
+        algorithm: HashAlgorithm,
+        key: &[u8],
+        data: &[u8],
+        expected: &[u8],
+    ) -> Result<(), CryptoError> {
+        let mut mac = Hmac::new(algorithm, key)?;
+        mac.update(data)?;
+        mac.verify(expected)
+    }
+
+    // C接口结构体：摘要算法
+    /// cbindgen:prefix-with-name
+    #[repr(C)]
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum CHashAlgorithm {
+        Sha224 = 0,
+        Sha256 = 1,
+        Sha384 = 2,
+        Sha512 = 3,
+        Sha3_224 = 4,
+        Sha3_256 = 5,
+        Sha3_384 = 6,
+        Sha3_512 = 7,
+        Blake2b512 = 8,
+        Blake2s256 = 9,
+    }
+
+    impl From<CHashAlgorithm> for HashAlgorithm {
+        fn from(algorithm: CHashAlgorithm) -> Self {
+            match algorithm {
+                CHashAlgorithm::Sha224 => HashAlgorithm::Sha224,
+                CHashAlgorithm::Sha256 => HashAlgorithm::Sha256,
+                CHashAlgorithm::Sha384 => HashAlgorithm::Sha384,
+                CHashAlgorithm::Sha512 => HashAlgorithm::Sha512,
+                CHashAlgorithm::Sha3_224 => HashAlgorithm::Sha3_224,
+                CHashAlgorithm::Sha3_256 => HashAlgorithm::Sha3_256,
+                CHashAlgorithm::Sha3_384 => HashAlgorithm::Sha3_384,
+                CHashAlgorithm::Sha3_512 => HashAlgorithm::Sha3_512,
+                CHashAlgorithm::Blake2b512 => HashAlgorithm::Blake2b512,
+                CHashAlgorithm::Blake2s256 => HashAlgorithm::Blake2s256,
+            }
+        }
+    }
+
+    // C接口结构体：摘要输出编码
+    /// cbindgen:prefix-with-name
+    #[repr(C)]
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum CDigestEncoding {
+        Hex = 0,
+        Base64 = 1,
+    }
+
+    // C接口辅助函数：按编码把摘要写成C字符串，需用 free_c_string 释放
+    fn write_digest(
+        result: Result<Digest, CryptoError>,
+        encoding: CDigestEncoding,
+        out_str: *mut *mut c_char,
+    ) -> CryptoErrorCode {
+        let digest = match result {
+            Ok(d) => d,
+            Err(e) => return crypto_error_to_code(&e),
+        };
+        let encoded = match encoding {
+            CDigestEncoding::Hex => digest.to_hex(),
+            CDigestEncoding::Base64 => digest.to_base64(),
+        };
+        let c_str = match CString::new(encoded) {
+            Ok(s) => s,
+            Err(_) => return CryptoErrorCode::Base64EncodeError,
+        };
+
+        unsafe { *out_str = c_str.into_raw() };
+        CryptoErrorCode::Success
+    }
+
+    // C接口：一次性计算摘要，结果需用 free_c_string 释放
+    #[unsafe(no_mangle)]
+    pub extern "C" fn hash_data_c(
+        algorithm: CHashAlgorithm,
+        encoding: CDigestEncoding,
+        data: *const u8,
+        data_len: usize,
+        out_str: *mut *mut c_char,
+    ) -> CryptoErrorCode {
+        if (data.is_null() && data_len != 0) || out_str.is_null() {
+            return CryptoErrorCode::NullPointerError;
+        }
+
+        let data_slice = if data_len == 0 {
+            &[][..]
+        } else {
+            unsafe { std::slice::from_raw_parts(data, data_len) }
+        };
+        write_digest(hash(algorithm.into(), data_slice), encoding, out_str)
+    }
+
+    // C接口：计算文件摘要（mmap），结果需用 free_c_string 释放
+    #[unsafe(no_mangle)]
+    pub extern "C" fn hash_file_c(
+        algorithm: CHashAlgorithm,
+        encoding: CDigestEncoding,
+        path: *const c_char,
+        out_str: *mut *mut c_char,
+    ) -> CryptoErrorCode {
+        if path.is_null() || out_str.is_null() {
+            return CryptoErrorCode::NullPointerError;
+        }
+
+        let path_str = unsafe { CStr::from_ptr(path).to_string_lossy() };
+        write_digest(
+            hash_file(algorithm.into(), path_str.as_ref()),
+            encoding,
+            out_str,
+        )
+    }
+
+    // C接口：计算 HMAC，结果需用 free_c_string 释放
+    #[unsafe(no_mangle)]
+    pub extern "C" fn hmac_c(
+        algorithm: CHashAlgorithm,
+        encoding: CDigestEncoding,
+        key: *const u8,
+        key_len: usize,
+        data: *const u8,
+        data_len: usize,
+        out_str: *mut *mut c_char,
+    ) -> CryptoErrorCode {
+        if key.is_null() || (data.is_null() && data_len != 0) || out_str.is_null() {
+            return CryptoErrorCode::NullPointerError;
+        }
+
+        let key_slice = unsafe { std::slice::from_raw_parts(key, key_len) };
+        let data_slice = if data_len == 0 {
+            &[][..]
+        } else {
+            unsafe { std::slice::from_raw_parts(data, data_len) }
+        };
+        write_digest(
+            hmac(algorithm.into(), key_slice, data_slice),
+            encoding,
+            out_str,
+        )
+    }
+
+    // C接口：校验 HMAC（原始字节标签），不匹配时返回 MacVerificationFailed
+    #[unsafe(no_mangle)]
+    pub extern "C" fn hmac_verify_c(
+        algorithm: CHashAlgorithm,
+        key: *const u8,
+        key_len: usize,
+        data: *const u8,
+        data_len: usize,
+        tag: *const u8,
+        tag_len: usize,
+    ) -> CryptoErrorCode {
+        if key.is_null() || (data.is_null() && data_len != 0) || tag.is_null() {
+            return CryptoErrorCode::NullPointerError;
+        }
+
+        let key_slice = unsafe { std::slice::from_raw_parts(key, key_len) };
+        let data_slice = if data_len == 0 {
+            &[][..]
+        } else {
+            unsafe { std::slice::from_raw_parts(data, data_len) }
+        };
+        let tag_slice = unsafe { std::slice::from_raw_parts(tag, tag_len) };
+        match hmac_verify(algorithm.into(), key_slice, data_slice, tag_slice) {
+            Ok(()) => CryptoErrorCode::Success,
+            Err(e) => crypto_error_to_code(&e),
+        }
+    }
+
+    // C接口：创建增量摘要句柄，使用 hasher_update 追加数据，
+    // 最后调用 hasher_finalize_c 取结果（句柄随之释放）或 hasher_free 放弃
+    #[unsafe(no_mangle)]
+    pub extern "C" fn hasher_new(
+        algorithm: CHashAlgorithm,
+        out_handle: *mut *mut Hasher,
+    ) -> CryptoErrorCode {
+        if out_handle.is_null() {
+            return CryptoErrorCode::NullPointerError;
+        }
+
+        match Hasher::new(algorithm.into()) {
+            Ok(hasher) => {
+                unsafe { *out_handle = Box::into_raw(Box::new(hasher)) };
+                CryptoErrorCode::Success
+            }
+            Err(e) => crypto_error_to_code(&e),
+        }
+    }
+
+    // C接口：向增量摘要追加数据
+    #[unsafe(no_mangle)]
+    pub extern "C" fn hasher_update(
+        handle: *mut Hasher,
+        data: *const u8,
+        data_len: usize,
+    ) -> CryptoErrorCode {
+        let Some(hasher) = (unsafe { handle.as_mut() }) else {
+            return CryptoErrorCode::NullPointerError;
+        };
+        if data_len == 0 {
+            return CryptoErrorCode::Success;
+        }
+        if data.is_null() {
+            return CryptoErrorCode::NullPointerError;
+        }
+
+        let data_slice = unsafe { std::slice::from_raw_parts(data, data_len) };
+        match hasher.update(data_slice) {
+            Ok(()) => CryptoErrorCode::Success,
+            Err(e) => crypto_error_to_code(&e),
+        }
+    }
+
+    // C接口：结束增量摘要并释放句柄，结果需用 free_c_string 释放
+    #[unsafe(no_mangle)]
+    pub extern "C" fn hasher_finalize_c(
+        handle: *mut Hasher,
+        encoding: CDigestEncoding,
+        out_str: *mut *mut c_char,
+    ) -> CryptoErrorCode {
+        if handle.is_null() || out_str.is_null() {
+            return CryptoErrorCode::NullPointerError;
+        }
+
+        let hasher = unsafe { Box::from_raw(handle) };
+        write_digest(hasher.finalize(), encoding, out_str)
+    }
+
+    // C接口：放弃增量摘要并释放句柄
+    #[unsafe(no_mangle)]
+    pub extern "C" fn hasher_free(handle: *mut Hasher) {
+        if !handle.is_null() {
+            unsafe {
+                let _ = Box::from_raw(handle);
+            }
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn test_known_digests() {
+            let cases = [
+                (
+                    HashAlgorithm::Sha256,
+                    "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad",
+                ),
+                (
+                    HashAlgorithm::Sha3_256,
+                    "3a985da74fe225b2045c172d6bd390bd855f086e3e9d525b46bfe24511431532",
+                ),
+                (
+                    HashAlgorithm::Blake2s256,
+                    "508c5e8c327c14e2e1a72ba34eeb452f37458b209ed63a294d999b4c86675982",
+                ),
+                (
+                    HashAlgorithm::Blake2b512,
+                    "ba80a53f981c4d0d6a2797b69f12f6e94c212f14685ac4b74b12bb6fdbffa2d1\
+                     7d87c5392aab792dc252d5de4533cc9518d38aa8dbf1925ab92386edd4009923",
+                ),
+            ];
+            for (algorithm, expected) in cases {
+                let digest = hash(algorithm, b"abc").unwrap();
+                assert_eq!(digest.to_hex(), expected, "{}", algorithm.name());
+                assert_eq!(digest.as_bytes().len(), algorithm.output_len());
+            }
+
+            // 增量计算与一次性计算一致
+            let mut hasher = Hasher::new(HashAlgorithm::Sha512).unwrap();
+            hasher.update(b"a").unwrap();
+            io::copy(&mut &b"bc"[..], &mut hasher).unwrap();
+            assert_eq!(
+                hasher.finalize().unwrap(),
+                hash(HashAlgorithm::Sha512, b"abc").unwrap()
+            );
+        }
+
+        #[test]
+        fn test_hmac_rfc4231_case2() {
+            let expected = "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843";
+            let data = b"what do ya want for nothing?";
+            let tag = hmac(HashAlgorithm::Sha256, b"Jefe", data).unwrap();
+            assert_eq!(tag.to_hex(), expected);
+
+            hmac_verify(HashAlgorithm::Sha256, b"Jefe", data, tag.as_bytes()).unwrap();
+            assert!(matches!(
+                hmac_verify(HashAlgorithm::Sha256, b"Jeff", data, tag.as_bytes()),
+                Err(CryptoError::MacVerificationFailed)
+            ));
+        }
+
+        #[test]
+        fn test_hash_file() {
+            let path = std::env::temp_dir().join(format!("fn_hash_{}.txt", std::process::id()));
+            std::fs::write(&path, b"abc").unwrap();
+            let digest = hash_file(HashAlgorithm::Sha256, &path).unwrap();
+            let mut reader = File::open(&path).unwrap();
+            let streamed = hash_reader(HashAlgorithm::Sha256, &mut reader).unwrap();
+
+            std::fs::write(&path, b"").unwrap();
+            let empty = hash_file(HashAlgorithm::Sha256, &path).unwrap();
+            std::fs::remove_file(&path).unwrap();
+
+            assert_eq!(digest, hash(HashAlgorithm::Sha256, b"abc").unwrap());
+            assert_eq!(streamed, digest);
+            assert_eq!(empty, hash(HashAlgorithm::Sha256, b"").unwrap());
+        }
+    }
+}
@@ -7,8 +7,12 @@ pub mod devices_id;
 pub use devices_id as other_devices_id;
 pub mod list;
 pub use list::list as other_list;
+pub mod hash;
+pub mod random;
 pub mod safe;
 pub mod web;
 
+pub use hash::hash as other_hash;
+pub use random::random as other_random;
 pub use safe::safe as other_safe;
 pub use web::web as other_web;
@@ -804,7 +804,6 @@ pub mod c_list {
     use crate::other_list::{DoublyLinkedList, Node};
 
     // 不透明指针类型，对C完全隐藏实现细节
-    #[repr(C)]
     pub struct CDoublyLinkedList {
         inner: DoublyLinkedList<*mut c_void>,
     }
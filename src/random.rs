@@ -0,0 +1,330 @@
+pub mod random {
+    use crate::safe::safe::{CryptoError, CryptoErrorCode, crypto_error_to_code};
+    use openssl::rand::rand_bytes;
+    use std::ffi::{CStr, CString, c_char};
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    /// 令牌字母表
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum TokenAlphabet {
+        /// 0-9
+        Numeric,
+        /// 0-9a-f
+        HexLower,
+        /// A-Za-z0-9
+        Alphanumeric,
+        /// A-Za-z0-9-_，可直接放进 URL
+        UrlSafe,
+        /// RFC 4648 Base32 字母表 A-Z2-7，适合人工输入
+        Base32,
+        /// 去掉 0/O/1/I/l 等易混淆字符的字母数字
+        Unambiguous,
+    }
+
+    impl TokenAlphabet {
+        pub fn chars(self) -> &'static [u8] {
+            match self {
+                TokenAlphabet::Numeric => b"0123456789",
+                TokenAlphabet::HexLower => b"0123456789abcdef",
+                TokenAlphabet::Alphanumeric => {
+                    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789"
+                }
+                TokenAlphabet::UrlSafe => {
+                    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_"
+                }
+                TokenAlphabet::Base32 => b"ABCDEFGHIJKLMNOPQRSTUVWXYZ234567",
+                TokenAlphabet::Unambiguous => {
+                    b"ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz23456789"
+                }
+            }
+        }
+    }
+
+    /// 用 OpenSSL CSPRNG 填满缓冲区
+    pub fn fill_random(buf: &mut [u8]) -> Result<(), CryptoError> {
+        rand_bytes(buf).map_err(|e| CryptoError::RandomFailed(e.to_string()))
+    }
+
+    /// 任意长度的随机字节
+    pub fn random_bytes(len: usize) -> Result<Vec<u8>, CryptoError> {
+        let mut buf = vec![0u8; len];
+        fill_random(&mut buf)?;
+        Ok(buf)
+    }
+
+    pub fn random_u64() -> Result<u64, CryptoError> {
+        let mut buf = [0u8; 8];
+        fill_random(&mut buf)?;
+        Ok(u64::from_le_bytes(buf))
+    }
+
+    /// 返回 [low, high) 内均匀分布的整数，使用拒绝采样避免取模偏差
+    pub fn random_range(low: u64, high: u64) -> Result<u64, CryptoError> {
+        if low >= high {
+            return Err(CryptoError::InvalidRandomParameters(format!(
+                "范围无效: [{}, {})",
+                low, high
+            )));
+        }
+        let span = high - low;
+        // 丢弃最后不完整的一段，使剩余取值能被 span 整除
+        let zone = u64::MAX - (u64::MAX % span + 1) % span;
+        loop {
+            let value = random_u64()?;
+            if value <= zone {
+                return Ok(low + value % span);
+            }
+        }
+    }
+
+    /// 返回 [low, high] 内均匀分布的整数
+    pub fn random_range_inclusive(low: u64, high: u64) -> Result<u64, CryptoError> {
+        if low > high {
+            return Err(CryptoError::InvalidRandomParameters(format!(
+                "范围无效: [{}, {}]",
+                low, high
+            )));
+        }
+        match high.checked_add(1) {
+            Some(end) => random_range(low, end),
+            // [low, u64::MAX]：low 为 0 时任何值都可以，否则对剩余范围重新采样
+            None if low == 0 => random_u64(),
+            None => random_range(low - 1, u64::MAX).map(|v| v + 1),
+        }
+    }
+
+    /// 从给定字符集中均匀选取字符生成令牌
+    pub fn random_token_from(alphabet: &[u8], len: usize) -> Result<String, CryptoError> {
+        if alphabet.is_empty() || !alphabet.is_ascii() {
+            return Err(CryptoError::InvalidRandomParameters(
+                "字母表必须是非空的 ASCII 字符".into(),
+            ));
+        }
+        let mut token = String::with_capacity(len);
+        for _ in 0..len {
+            let index = random_range(0, alphabet.len() as u64)? as usize;
+            token.push(alphabet[index] as char);
+        }
+        Ok(token)
+    }
+
+    pub fn random_token(alphabet: TokenAlphabet, len: usize) -> Result<String, CryptoError> {
+        random_token_from(alphabet.chars(), len)
+    }
+
+    fn format_uuid(bytes: &[u8; 16]) -> String {
+        let hex = hex::encode(bytes);
+        format!(
+            "{}-{}-{}-{}-{}",
+            &hex[0..8],
+            &hex[8..12],
+            &hex[12..16],
+            &hex[16..20],
+            &hex[20..32]
+        )
+    }
+
+    /// 随机 UUID（版本 4）
+    pub fn uuid_v4_bytes() -> Result<[u8; 16], CryptoError> {
+        let mut bytes = [0u8; 16];
+        fill_random(&mut bytes)?;
+        bytes[6] = (bytes[6] & 0x0f) | 0x40;
+        bytes[8] = (bytes[8] & 0x3f) | 0x80;
+        Ok(bytes)
+    }
+
+    /// 时间有序 UUID（版本 7）：前 48 位为 Unix 毫秒时间戳，其余为随机数
+    pub fn uuid_v7_bytes() -> Result<[u8; 16], CryptoError> {
+        let millis = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_millis() as u64)
+            .unwrap_or(0);
+        let mut bytes = [0u8; 16];
+        fill_random(&mut bytes[6..])?;
+        bytes[..6].copy_from_slice(&millis.to_be_bytes()[2..]);
+        bytes[6] = (bytes[6] & 0x0f) | 0x70;
+        bytes[8] = (bytes[8] & 0x3f) | 0x80;
+        Ok(bytes)
+    }
+
+    pub fn uuid_v4() -> Result<String, CryptoError> {
+        uuid_v4_bytes().map(|b| format_uuid(&b))
+    }
+
+    pub fn uuid_v7() -> Result<String, CryptoError> {
+        uuid_v7_bytes().map(|b| format_uuid(&b))
+    }
+
+    // C接口结构体：令牌字母表
+    /// cbindgen:prefix-with-name
+    #[repr(C)]
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum CTokenAlphabet {
+        Numeric = 0,
+        HexLower = 1,
+        Alphanumeric = 2,
+        UrlSafe = 3,
+        Base32 = 4,
+        Unambiguous = 5,
+    }
+
+    impl From<CTokenAlphabet> for TokenAlphabet {
+        fn from(alphabet: CTokenAlphabet) -> Self {
+            match alphabet {
+                CTokenAlphabet::Numeric => TokenAlphabet::Numeric,
+                CTokenAlphabet::HexLower => TokenAlphabet::HexLower,
+                CTokenAlphabet::Alphanumeric => TokenAlphabet::Alphanumeric,
+                CTokenAlphabet::UrlSafe => TokenAlphabet::UrlSafe,
+                CTokenAlphabet::Base32 => TokenAlphabet::Base32,
+                CTokenAlphabet::Unambiguous => TokenAlphabet::Unambiguous,
+            }
+        }
+    }
+
+    // C接口辅助函数：把结果写成C字符串，需用 free_c_string 释放
+    fn write_c_string(
+        result: Result<String, CryptoError>,
+        out_str: *mut *mut c_char,
+    ) -> CryptoErrorCode {
+        let value = match result {
+            Ok(v) => v,
+            Err(e) => return crypto_error_to_code(&e),
+        };
+        match CString::new(value) {
+            Ok(c_str) => {
+                unsafe { *out_str = c_str.into_raw() };
+                CryptoErrorCode::Success
+            }
+            Err(_) => CryptoErrorCode::InvalidRandomParameters,
+        }
+    }
+
+    // C接口：用随机字节填满调用方提供的缓冲区
+    #[unsafe(no_mangle)]
+    pub extern "C" fn random_bytes_c(buf: *mut u8, len: usize) -> CryptoErrorCode {
+        if len == 0 {
+            return CryptoErrorCode::Success;
+        }
+        if buf.is_null() {
+            return CryptoErrorCode::NullPointerError;
+        }
+
+        let slice = unsafe { std::slice::from_raw_parts_mut(buf, len) };
+        match fill_random(slice) {
+            Ok(()) => CryptoErrorCode::Success,
+            Err(e) => crypto_error_to_code(&e),
+        }
+    }
+
+    // C接口：生成 [low, high) 内的随机整数
+    #[unsafe(no_mangle)]
+    pub extern "C" fn random_range_c(low: u64, high: u64, out_value: *mut u64) -> CryptoErrorCode {
+        if out_value.is_null() {
+            return CryptoErrorCode::NullPointerError;
+        }
+
+        match random_range(low, high) {
+            Ok(value) => {
+                unsafe { *out_value = value };
+                CryptoErrorCode::Success
+            }
+            Err(e) => crypto_error_to_code(&e),
+        }
+    }
+
+    // C接口：生成指定字母表的随机令牌，需用 free_c_string 释放
+    #[unsafe(no_mangle)]
+    pub extern "C" fn random_token_c(
+        alphabet: CTokenAlphabet,
+        len: usize,
+        out_str: *mut *mut c_char,
+    ) -> CryptoErrorCode {
+        if out_str.is_null() {
+            return CryptoErrorCode::NullPointerError;
+        }
+        write_c_string(random_token(alphabet.into(), len), out_str)
+    }
+
+    // C接口：从自定义字符集（ASCII）生成随机令牌，需用 free_c_string 释放
+    #[unsafe(no_mangle)]
+    pub extern "C" fn random_token_custom_c(
+        alphabet: *const c_char,
+        len: usize,
+        out_str: *mut *mut c_char,
+    ) -> CryptoErrorCode {
+        if alphabet.is_null() || out_str.is_null() {
+            return CryptoErrorCode::NullPointerError;
+        }
+
+        let chars = unsafe { CStr::from_ptr(alphabet).to_bytes() };
+        write_c_string(random_token_from(chars, len), out_str)
+    }
+
+    // C接口：生成 UUIDv4 字符串，需用 free_c_string 释放
+    #[unsafe(no_mangle)]
+    pub extern "C" fn uuid_v4_c(out_str: *mut *mut c_char) -> CryptoErrorCode {
+        if out_str.is_null() {
+            return CryptoErrorCode::NullPointerError;
+        }
+        write_c_string(uuid_v4(), out_str)
+    }
+
+    // C接口：生成 UUIDv7 字符串，需用 free_c_string 释放
+    #[unsafe(no_mangle)]
+    pub extern "C" fn uuid_v7_c(out_str: *mut *mut c_char) -> CryptoErrorCode {
+        if out_str.is_null() {
+            return CryptoErrorCode::NullPointerError;
+        }
+        write_c_string(uuid_v7(), out_str)
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn test_random_range_and_tokens() {
+            assert_eq!(random_bytes(12).unwrap().len(), 12);
+            assert!(random_range(5, 5).is_err());
+
+            let mut seen = [false; 6];
+            for _ in 0..500 {
+                let value = random_range(10, 16).unwrap();
+                assert!((10..16).contains(&value));
+                seen[(value - 10) as usize] = true;
+            }
+            assert!(seen.iter().all(|s| *s));
+            assert_eq!(random_range_inclusive(7, 7).unwrap(), 7);
+            assert!(random_range_inclusive(u64::MAX - 1, u64::MAX).unwrap() >= u64::MAX - 1);
+
+            let token = random_token(TokenAlphabet::Unambiguous, 32).unwrap();
+            assert_eq!(token.len(), 32);
+            assert!(
+                token
+                    .bytes()
+                    .all(|c| TokenAlphabet::Unambiguous.chars().contains(&c))
+            );
+            assert!(random_token_from(b"", 4).is_err());
+            assert_eq!(random_token_from(b"x", 3).unwrap(), "xxx");
+        }
+
+        #[test]
+        fn test_uuid_layout() {
+            let v4 = uuid_v4().unwrap();
+            assert_eq!(v4.len(), 36);
+            assert_eq!(&v4[14..15], "4");
+            assert!("89ab".contains(&v4[19..20]));
+
+            let before = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap()
+                .as_millis() as u64;
+            let v7 = uuid_v7_bytes().unwrap();
+            let mut millis = [0u8; 8];
+            millis[2..].copy_from_slice(&v7[..6]);
+            assert!(u64::from_be_bytes(millis) >= before);
+            assert_eq!(v7[6] >> 4, 7);
+            assert_eq!(v7[8] >> 6, 0b10);
+        }
+    }
+}
@@ -1,12 +1,39 @@
 pub mod safe {
+    use crate::hash::hash::{HashAlgorithm, hmac, hmac_verify};
     use base64::{Engine as _, engine::general_purpose};
+    use memmap2::Mmap;
     use openssl::{
+        asn1::{Asn1Time, Asn1TimeRef},
+        bn::{BigNum, BigNumContext},
+        derive::Deriver,
+        ec::{EcGroup, EcKey, EcPoint, PointConversionForm},
+        ecdsa::EcdsaSig,
         error::ErrorStack,
+        hash::MessageDigest,
+        md::{Md, MdRef},
+        memcmp,
+        nid::Nid,
+        pkcs5,
+        pkey::{HasPrivate, HasPublic, Id, PKey, PKeyRef, Private, Public},
+        pkey_ctx::{HkdfMode, PkeyCtx},
         rand,
+        rsa::{Padding, Rsa},
+        sign::{RsaPssSaltlen, Signer, Verifier},
+        stack::Stack,
         symm::{Cipher, Crypter, Mode},
+        x509::{
+            X509, X509NameBuilder, X509NameRef, X509ReqBuilder, X509StoreContext,
+            extension::SubjectAlternativeName, store::X509StoreBuilder, verify::X509VerifyParam,
+        },
     };
+    use serde::{Deserialize, Serialize};
     use std::ffi::{CStr, CString, c_char};
+    use std::fs::File;
+    use std::io::{Read, Write};
+    use std::path::Path;
+    use std::ptr;
     use thiserror::Error;
+    use zeroize::{Zeroize, Zeroizing};
 
     // 常量定义（保持不变）
     pub const AES_128_KEY_LEN: usize = 16;
@@ -25,6 +52,8 @@ pub mod safe {
             "密钥长度无效: 必须为 {AES_128_KEY_LEN}, {AES_192_KEY_LEN} 或 {AES_256_KEY_LEN} 字节"
         )]
         InvalidKeyLength,
+        #[error("盐长度无效: 至少需要 {MIN_SALT_LEN} 字节")]
+        InvalidSaltLength,
     }
 
     #[derive(Debug, Error)]
@@ -53,6 +82,66 @@ pub mod safe {
         InvalidCiphertextFormat,
         #[error("UTF-8解码失败: {0}")]
         Utf8DecodingFailed(String),
+        #[error("密钥派生参数无效: {0}")]
+        InvalidKdfParameters(String),
+        #[error("密钥派生失败: {0}")]
+        KeyDerivationFailed(String),
+        #[error("密钥生成失败: {0}")]
+        KeyGenerationFailed(String),
+        #[error("密钥无效: {0}")]
+        InvalidKey(String),
+        #[error("签名失败: {0}")]
+        SignatureFailed(String),
+        #[error("签名验证失败")]
+        SignatureVerificationFailed,
+        #[error("IO错误: {0}")]
+        IoError(String),
+        #[error("不支持的RSA密钥长度: {bits} 位，仅支持 2048、3072 或 4096 位")]
+        UnsupportedRsaKeySize { bits: u32 },
+        #[error("RSA明文过长: 最多 {max} 字节，实际 {actual} 字节")]
+        RsaPlaintextTooLong { max: usize, actual: usize },
+        #[error("私钥解密失败: 口令错误或数据损坏")]
+        KeyDecryptionFailed,
+        #[error("RSA运算失败: {0}")]
+        RsaOperationFailed(String),
+        #[error("固定IV的CBC加密已禁用，请使用随机IV或AES-GCM")]
+        FixedIvEncryptionDisabled,
+        #[error("内存锁定失败: {0}")]
+        MemoryLockFailed(String),
+        #[error("密钥不存在: {0}")]
+        KeyNotFound(String),
+        #[error("密钥已存在: {0}")]
+        KeyAlreadyExists(String),
+        #[error("密钥已吊销: {0}")]
+        KeyRevoked(String),
+        #[error("密钥库无效: {0}")]
+        InvalidKeystore(String),
+        #[error("摘要计算失败: {0}")]
+        HashFailed(String),
+        #[error("消息认证码校验失败")]
+        MacVerificationFailed,
+        #[error("编码数据无效: {0}")]
+        InvalidEncoding(String),
+        #[error("令牌格式无效: {0}")]
+        InvalidToken(String),
+        #[error("令牌已过期")]
+        TokenExpired,
+        #[error("令牌尚未生效")]
+        TokenNotYetValid,
+        #[error("令牌声明无效: {0}")]
+        InvalidClaim(String),
+        #[error("随机数生成失败: {0}")]
+        RandomFailed(String),
+        #[error("随机数参数无效: {0}")]
+        InvalidRandomParameters(String),
+        #[error("证书处理失败: {0}")]
+        CertificateError(String),
+        #[error("证书链验证失败: {0}")]
+        CertificateVerificationFailed(String),
+        #[error("口令不匹配")]
+        PasswordMismatch,
+        #[error("口令哈希格式无效: {0}")]
+        InvalidPasswordHash(String),
     }
 
     impl From<ErrorStack> for CryptoError {
@@ -79,6 +168,35 @@ pub mod safe {
         Utf8DecodingFailed = 11,
         KeyGenerationFailed = 12,
         NullPointerError = 13,
+        InvalidKdfParameters = 14,
+        KeyDerivationFailed = 15,
+        InvalidKey = 16,
+        SignatureFailed = 17,
+        SignatureVerificationFailed = 18,
+        IoError = 19,
+        UnsupportedRsaKeySize = 20,
+        RsaPlaintextTooLong = 21,
+        KeyDecryptionFailed = 22,
+        RsaOperationFailed = 23,
+        FixedIvEncryptionDisabled = 24,
+        MemoryLockFailed = 25,
+        KeyNotFound = 26,
+        KeyAlreadyExists = 27,
+        KeyRevoked = 28,
+        InvalidKeystore = 29,
+        HashFailed = 30,
+        MacVerificationFailed = 31,
+        InvalidEncoding = 32,
+        InvalidToken = 33,
+        TokenExpired = 34,
+        TokenNotYetValid = 35,
+        InvalidClaim = 36,
+        RandomFailed = 37,
+        InvalidRandomParameters = 38,
+        CertificateError = 39,
+        CertificateVerificationFailed = 40,
+        PasswordMismatch = 41,
+        InvalidPasswordHash = 42,
     }
 
     // C接口结构体：加密解密选项
@@ -93,10 +211,116 @@ pub mod safe {
     #[repr(C)]
     #[derive(Debug, Clone, Copy, PartialEq, Eq)]
     pub enum CAesCbcMode {
+        #[deprecated(
+            note = "全零 IV 加密已禁用（返回 FixedIvEncryptionDisabled），请改用 RandomIv；旧密文仍可解密"
+        )]
         FixedIv = 0,
         RandomIv = 1,
     }
 
+    // C接口结构体：口令派生算法
+    #[repr(C)]
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum CKdfAlgorithm {
+        Pbkdf2Sha256 = 0,
+        Scrypt = 1,
+        Argon2id = 2,
+    }
+
+    // C接口结构体：口令派生参数
+    // PBKDF2 只使用 iterations；
+    // scrypt 中 memory_cost 为 log2(N)，block_size 为 r，parallelism 为 p；
+    // Argon2id 中 iterations 为迭代次数，memory_cost 单位为 KiB，parallelism 为 lanes
+    #[repr(C)]
+    #[derive(Debug, Clone, Copy)]
+    pub struct CKdfParams {
+        pub algorithm: CKdfAlgorithm,
+        pub iterations: u32,
+        pub memory_cost: u32,
+        pub block_size: u32,
+        pub parallelism: u32,
+    }
+
+    // C接口结构体：HKDF 哈希算法
+    #[repr(C)]
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum CHkdfHash {
+        Sha256 = 0,
+        Sha512 = 1,
+    }
+
+    // C接口结构体：签名算法
+    #[repr(C)]
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum CSignatureAlgorithm {
+        Ed25519 = 0,
+        EcdsaP256 = 1,
+    }
+
+    // C接口结构体：密钥编码格式
+    #[repr(C)]
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum CKeyFormat {
+        Pem = 0,
+        Der = 1,
+        Raw = 2,
+    }
+
+    // C接口结构体：混合加密的密钥协商算法
+    #[repr(C)]
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum CKeyAgreementAlgorithm {
+        X25519 = 0,
+        EcdhP256 = 1,
+    }
+
+    // C接口结构体：RSA OAEP/PSS 哈希算法
+    /// cbindgen:prefix-with-name
+    #[repr(C)]
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum CRsaHash {
+        Sha1 = 0,
+        Sha256 = 1,
+        Sha384 = 2,
+        Sha512 = 3,
+    }
+
+    // C接口结构体：编码方式
+    /// cbindgen:prefix-with-name
+    #[repr(C)]
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum CEncoding {
+        Base64 = 0,
+        Base64NoPad = 1,
+        Base64Url = 2,
+        Base64UrlNoPad = 3,
+        Base64Mime = 4,
+        Hex = 5,
+        Base32 = 6,
+    }
+
+    // C接口结构体：JWS 签名算法
+    #[repr(C)]
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum CJwsAlgorithm {
+        HS256 = 0,
+        HS384 = 1,
+        HS512 = 2,
+        RS256 = 3,
+        ES256 = 4,
+        EdDSA = 5,
+    }
+
+    // C接口结构体：JWT 声明校验选项，audience / issuer 为空指针时不校验
+    #[repr(C)]
+    #[derive(Debug, Clone, Copy)]
+    pub struct CJwtValidation {
+        pub leeway_secs: u64,
+        pub audience: *const c_char,
+        pub issuer: *const c_char,
+        pub require_exp: bool,
+    }
+
     // 密钥生成（保持原逻辑）
     pub fn generate_key<const N: usize>() -> Result<[u8; N], KeyError> {
         if N != AES_128_KEY_LEN && N != AES_192_KEY_LEN && N != AES_256_KEY_LEN {
@@ -109,10 +333,155 @@ pub mod safe {
     }
 
     pub fn generate_key_base64<const N: usize>() -> Result<String, KeyError> {
-        let key = generate_key::<N>()?;
-        Ok(base64_encode(&key))
+        let key = Zeroizing::new(generate_key::<N>()?);
+        Ok(base64_encode(key.as_ref()))
+    }
+
+    // 受保护的密钥：释放时清零，可选锁定内存，Debug 不输出内容，比较为常量时间
+    pub struct SecretKey {
+        bytes: Box<[u8]>,
+        locked: bool,
+    }
+
+    impl SecretKey {
+        /// 生成随机 AES 密钥，长度限制与 `generate_key` 一致
+        pub fn generate(len: usize) -> Result<Self, KeyError> {
+            if len != AES_128_KEY_LEN && len != AES_192_KEY_LEN && len != AES_256_KEY_LEN {
+                return Err(KeyError::InvalidKeyLength);
+            }
+            let mut key = SecretKey::zeroed(len);
+            rand::rand_bytes(&mut key.bytes).map_err(|_| KeyError::RandomFailed)?;
+            Ok(key)
+        }
+
+        fn zeroed(len: usize) -> Self {
+            SecretKey {
+                bytes: vec![0u8; len].into_boxed_slice(),
+                locked: false,
+            }
+        }
+
+        /// 复制一份密钥材料，调用方仍需自行清理原始数据
+        pub fn from_slice(bytes: &[u8]) -> Self {
+            let mut key = SecretKey::zeroed(bytes.len());
+            key.bytes.copy_from_slice(bytes);
+            key
+        }
+
+        /// 接管 Vec 中的密钥材料，原 Vec 会被清零
+        pub fn from_vec(mut bytes: Vec<u8>) -> Self {
+            let key = SecretKey::from_slice(&bytes);
+            bytes.zeroize();
+            key
+        }
+
+        pub fn from_base64(encoded: &str) -> Result<Self, CryptoError> {
+            let decoded = Zeroizing::new(base64_decode(encoded)?);
+            Ok(SecretKey::from_slice(&decoded))
+        }
+
+        /// 导出为 Base64，返回值同样会在释放时清零
+        pub fn to_base64(&self) -> Zeroizing<String> {
+            Zeroizing::new(base64_encode(&self.bytes))
+        }
+
+        /// 取出密钥字节用于加解密调用，不要把结果复制到不受保护的缓冲区
+        pub fn expose_secret(&self) -> &[u8] {
+            &self.bytes
+        }
+
+        pub fn len(&self) -> usize {
+            self.bytes.len()
+        }
+
+        pub fn is_empty(&self) -> bool {
+            self.bytes.is_empty()
+        }
+
+        /// 用 mlock 锁定密钥所在内存，防止被换出到交换分区
+        pub fn lock_memory(&mut self) -> Result<(), CryptoError> {
+            if self.locked || self.bytes.is_empty() {
+                return Ok(());
+            }
+            lock_memory(&self.bytes)?;
+            self.locked = true;
+            Ok(())
+        }
+
+        pub fn is_locked(&self) -> bool {
+            self.locked
+        }
+    }
+
+    impl Drop for SecretKey {
+        fn drop(&mut self) {
+            self.bytes.zeroize();
+            if self.locked {
+                unlock_memory(&self.bytes);
+            }
+        }
+    }
+
+    impl Clone for SecretKey {
+        // 副本不继承内存锁定状态，需要时重新调用 lock_memory
+        fn clone(&self) -> Self {
+            SecretKey::from_slice(&self.bytes)
+        }
+    }
+
+    impl PartialEq for SecretKey {
+        fn eq(&self, other: &Self) -> bool {
+            self.bytes.len() == other.bytes.len() && memcmp::eq(&self.bytes, &other.bytes)
+        }
+    }
+
+    impl Eq for SecretKey {}
+
+    impl std::fmt::Debug for SecretKey {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            f.debug_struct("SecretKey")
+                .field("len", &self.bytes.len())
+                .field("locked", &self.locked)
+                .field("bytes", &"[REDACTED]")
+                .finish()
+        }
+    }
+
+    impl From<Vec<u8>> for SecretKey {
+        fn from(bytes: Vec<u8>) -> Self {
+            SecretKey::from_vec(bytes)
+        }
+    }
+
+    #[cfg(unix)]
+    fn lock_memory(bytes: &[u8]) -> Result<(), CryptoError> {
+        let ret = unsafe { libc::mlock(bytes.as_ptr() as *const libc::c_void, bytes.len()) };
+        if ret == 0 {
+            Ok(())
+        } else {
+            Err(CryptoError::MemoryLockFailed(
+                std::io::Error::last_os_error().to_string(),
+            ))
+        }
+    }
+
+    #[cfg(unix)]
+    fn unlock_memory(bytes: &[u8]) {
+        unsafe {
+            libc::munlock(bytes.as_ptr() as *const libc::c_void, bytes.len());
+        }
+    }
+
+    #[cfg(not(unix))]
+    fn lock_memory(_bytes: &[u8]) -> Result<(), CryptoError> {
+        Err(CryptoError::MemoryLockFailed(
+            "当前平台不支持内存锁定".into(),
+        ))
     }
 
+    #[cfg(not(unix))]
+    fn unlock_memory(_bytes: &[u8]) {}
+
     // Base64编解码（保持原逻辑）
     pub fn base64_encode(data: &[u8]) -> String {
         general_purpose::STANDARD.encode(data)
@@ -124,6 +493,278 @@ pub mod safe {
             .map_err(|e| CryptoError::Base64DecodeError(e.to_string()))
     }
 
+    // 编解码：Base64 各变体、十六进制、Base32（RFC 4648）
+    const MIME_LINE_LEN: usize = 76;
+    const BASE32_ALPHABET: &[u8; 32] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ234567";
+    // 57 字节正好编码为一行 MIME，且同时是 3（Base64）和 5（Base32）的倍数
+    const CODEC_STREAM_CHUNK: usize = 57 * 5 * 192;
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum Encoding {
+        /// 标准字母表，带填充（与 `base64_encode` 相同）
+        Base64,
+        /// 标准字母表，不带填充
+        Base64NoPad,
+        /// URL 安全字母表，带填充
+        Base64Url,
+        /// URL 安全字母表，不带填充（JWT 使用）
+        Base64UrlNoPad,
+        /// 标准字母表，每 76 字符以 CRLF 换行，解码时忽略空白
+        Base64Mime,
+        /// 小写十六进制，解码时不区分大小写
+        Hex,
+        /// RFC 4648 Base32，带填充，解码时不区分大小写且填充可省略
+        Base32,
+    }
+
+    impl Encoding {
+        // 流式处理时每次编码的输入字节数需是该值的倍数
+        fn input_quantum(self) -> usize {
+            match self {
+                Encoding::Hex => 1,
+                Encoding::Base32 => 5,
+                _ => 3,
+            }
+        }
+
+        // 流式解码时每次解码的字符数需是该值的倍数
+        fn output_quantum(self) -> usize {
+            match self {
+                Encoding::Hex => 2,
+                Encoding::Base32 => 8,
+                _ => 4,
+            }
+        }
+    }
+
+    fn base64_engine(encoding: Encoding) -> &'static general_purpose::GeneralPurpose {
+        match encoding {
+            Encoding::Base64NoPad => &general_purpose::STANDARD_NO_PAD,
+            Encoding::Base64Url => &general_purpose::URL_SAFE,
+            Encoding::Base64UrlNoPad => &general_purpose::URL_SAFE_NO_PAD,
+            _ => &general_purpose::STANDARD,
+        }
+    }
+
+    pub fn encode(encoding: Encoding, data: &[u8]) -> String {
+        match encoding {
+            Encoding::Hex => hex::encode(data),
+            Encoding::Base32 => base32_encode(data),
+            Encoding::Base64Mime => {
+                let encoded = general_purpose::STANDARD.encode(data);
+                let lines: Vec<&str> = encoded
+                    .as_bytes()
+                    .chunks(MIME_LINE_LEN)
+                    .map(|line| std::str::from_utf8(line).unwrap_or_default())
+                    .collect();
+                lines.join("\r\n")
+            }
+            _ => base64_engine(encoding).encode(data),
+        }
+    }
+
+    pub fn decode(encoding: Encoding, data: &str) -> Result<Vec<u8>, CryptoError> {
+        match encoding {
+            Encoding::Hex => {
+                hex::decode(data.trim()).map_err(|e| CryptoError::InvalidEncoding(e.to_string()))
+            }
+            Encoding::Base32 => base32_decode(data),
+            Encoding::Base64Mime => {
+                let compact: String = data.chars().filter(|c| !c.is_ascii_whitespace()).collect();
+                general_purpose::STANDARD
+                    .decode(compact)
+                    .map_err(|e| CryptoError::Base64DecodeError(e.to_string()))
+            }
+            _ => base64_engine(encoding)
+                .decode(data)
+                .map_err(|e| CryptoError::Base64DecodeError(e.to_string())),
+        }
+    }
+
+    pub fn hex_encode(data: &[u8]) -> String {
+        encode(Encoding::Hex, data)
+    }
+
+    pub fn hex_decode(data: &str) -> Result<Vec<u8>, CryptoError> {
+        decode(Encoding::Hex, data)
+    }
+
+    pub fn base32_encode(data: &[u8]) -> String {
+        let mut out = String::with_capacity(data.len().div_ceil(5) * 8);
+        for chunk in data.chunks(5) {
+            let mut block = [0u8; 5];
+            block[..chunk.len()].copy_from_slice(chunk);
+            let bits = block.iter().fold(0u64, |acc, &b| (acc << 8) | b as u64);
+            let chars = (chunk.len() * 8).div_ceil(5);
+            for i in 0..8 {
+                if i < chars {
+                    let index = (bits >> (35 - i * 5)) & 0x1f;
+                    out.push(BASE32_ALPHABET[index as usize] as char);
+                } else {
+                    out.push('=');
+                }
+            }
+        }
+        out
+    }
+
+    pub fn base32_decode(data: &str) -> Result<Vec<u8>, CryptoError> {
+        let trimmed = data.trim().trim_end_matches('=');
+        // 每组 8 个字符中有效字符只可能是 2/4/5/7/8 个
+        if matches!(trimmed.len() % 8, 1 | 3 | 6) {
+            return Err(CryptoError::InvalidEncoding(format!(
+                "Base32 长度无效: {}",
+                trimmed.len()
+            )));
+        }
+
+        let mut out = Vec::with_capacity(trimmed.len() * 5 / 8);
+        let mut buffer = 0u64;
+        let mut bits = 0;
+        for c in trimmed.bytes() {
+            let value = match c.to_ascii_uppercase() {
+                c @ b'A'..=b'Z' => c - b'A',
+                c @ b'2'..=b'7' => c - b'2' + 26,
+                _ => {
+                    return Err(CryptoError::InvalidEncoding(format!(
+                        "Base32 字符无效: {:?}",
+                        c as char
+                    )));
+                }
+            };
+            buffer = (buffer << 5) | value as u64;
+            bits += 5;
+            if bits >= 8 {
+                bits -= 8;
+                out.push((buffer >> bits) as u8);
+                buffer &= (1 << bits) - 1;
+            }
+        }
+        Ok(out)
+    }
+
+    // 尽量填满缓冲区，只有到达末尾时才返回较短的长度
+    fn fill_buffer<R: Read>(reader: &mut R, buf: &mut [u8]) -> Result<usize, CryptoError> {
+        let mut filled = 0;
+        while filled < buf.len() {
+            match reader.read(&mut buf[filled..]) {
+                Ok(0) => break,
+                Ok(n) => filled += n,
+                Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(CryptoError::IoError(e.to_string())),
+            }
+        }
+        Ok(filled)
+    }
+
+    /// 流式编码，返回写出的字符数
+    pub fn encode_stream<R: Read, W: Write>(
+        encoding: Encoding,
+        reader: &mut R,
+        writer: &mut W,
+    ) -> Result<u64, CryptoError> {
+        debug_assert_eq!(CODEC_STREAM_CHUNK % encoding.input_quantum(), 0);
+        let mut buf = vec![0u8; CODEC_STREAM_CHUNK];
+        let mut written = 0u64;
+        loop {
+            let n = fill_buffer(reader, &mut buf)?;
+            if n == 0 {
+                break;
+            }
+            let mut text = encode(encoding, &buf[..n]);
+            // 块大小是 57 的倍数，块与块之间补上换行即可保持每行 76 字符
+            if encoding == Encoding::Base64Mime && written > 0 {
+                text.insert_str(0, "\r\n");
+            }
+            writer
+                .write_all(text.as_bytes())
+                .map_err(|e| CryptoError::IoError(e.to_string()))?;
+            written += text.len() as u64;
+            if n < buf.len() {
+                break;
+            }
+        }
+        writer
+            .flush()
+            .map_err(|e| CryptoError::IoError(e.to_string()))?;
+        Ok(written)
+    }
+
+    /// 流式解码，输入中的空白字符会被忽略，返回写出的字节数
+    pub fn decode_stream<R: Read, W: Write>(
+        encoding: Encoding,
+        reader: &mut R,
+        writer: &mut W,
+    ) -> Result<u64, CryptoError> {
+        let quantum = encoding.output_quantum();
+        let mut buf = vec![0u8; CODEC_STREAM_CHUNK];
+        let mut pending: Vec<u8> = Vec::with_capacity(CODEC_STREAM_CHUNK + quantum);
+        let mut written = 0u64;
+        loop {
+            let n = fill_buffer(reader, &mut buf)?;
+            let at_end = n < buf.len();
+            pending.extend(buf[..n].iter().filter(|b| !b.is_ascii_whitespace()));
+
+            let ready = if at_end {
+                pending.len()
+            } else {
+                pending.len() - pending.len() % quantum
+            };
+            if ready > 0 {
+                let text = std::str::from_utf8(&pending[..ready])
+                    .map_err(|e| CryptoError::InvalidEncoding(e.to_string()))?;
+                let decoded = decode(encoding, text)?;
+                writer
+                    .write_all(&decoded)
+                    .map_err(|e| CryptoError::IoError(e.to_string()))?;
+                written += decoded.len() as u64;
+                pending.drain(..ready);
+            }
+            if at_end {
+                break;
+            }
+        }
+        writer
+            .flush()
+            .map_err(|e| CryptoError::IoError(e.to_string()))?;
+        Ok(written)
+    }
+
+    /// 编码文件，输出写入另一个文件
+    pub fn encode_file<P: AsRef<Path>, Q: AsRef<Path>>(
+        encoding: Encoding,
+        input: P,
+        output: Q,
+    ) -> Result<u64, CryptoError> {
+        let mut reader = File::open(input).map_err(|e| CryptoError::IoError(e.to_string()))?;
+        let file = File::create(output).map_err(|e| CryptoError::IoError(e.to_string()))?;
+        encode_stream(encoding, &mut reader, &mut std::io::BufWriter::new(file))
+    }
+
+    /// 解码文件，输出写入另一个文件
+    pub fn decode_file<P: AsRef<Path>, Q: AsRef<Path>>(
+        encoding: Encoding,
+        input: P,
+        output: Q,
+    ) -> Result<u64, CryptoError> {
+        let mut reader = File::open(input).map_err(|e| CryptoError::IoError(e.to_string()))?;
+        let file = File::create(output).map_err(|e| CryptoError::IoError(e.to_string()))?;
+        decode_stream(encoding, &mut reader, &mut std::io::BufWriter::new(file))
+    }
+
+    // 执行一次完整的 update + finalize，输出缓冲区按 OpenSSL 要求预留一个块的余量
+    fn run_crypter(
+        crypter: &mut Crypter,
+        cipher: Cipher,
+        input: &[u8],
+    ) -> Result<Vec<u8>, ErrorStack> {
+        let mut output = vec![0u8; input.len() + cipher.block_size()];
+        let mut count = crypter.update(input, &mut output)?;
+        count += crypter.finalize(&mut output[count..])?;
+        output.truncate(count);
+        Ok(output)
+    }
+
     // GCM模式核心逻辑（保持原逻辑）
     fn select_cipher(key: &[u8]) -> Result<Cipher, CryptoError> {
         match key.len() {
@@ -153,6 +794,16 @@ pub mod safe {
         key: &[u8],
         plaintext: &[u8],
         options: &EncryptionOptions,
+    ) -> Result<Vec<u8>, CryptoError> {
+        gcm_encrypt(key, plaintext, &[], options)
+    }
+
+    // GCM 加密，aad 为附加认证数据，不加密但参与标签计算
+    fn gcm_encrypt(
+        key: &[u8],
+        plaintext: &[u8],
+        aad: &[u8],
+        options: &EncryptionOptions,
     ) -> Result<Vec<u8>, CryptoError> {
         if options.nonce_length == 0 || options.tag_length == 0 {
             return Err(CryptoError::InvalidNonceLength);
@@ -165,10 +816,11 @@ pub mod safe {
 
         let mut encrypter = Crypter::new(cipher, Mode::Encrypt, key, Some(&iv))?;
         encrypter.pad(false);
+        if !aad.is_empty() {
+            encrypter.aad_update(aad)?;
+        }
 
-        let mut ciphertext = Vec::new();
-        encrypter.update(plaintext, &mut ciphertext)?;
-        encrypter.finalize(&mut ciphertext)?;
+        let ciphertext = run_crypter(&mut encrypter, cipher, plaintext)?;
 
         let mut tag = vec![0u8; options.tag_length];
         encrypter.get_tag(&mut tag)?;
@@ -209,6 +861,16 @@ pub mod safe {
         key: &[u8],
         ciphertext: &[u8],
         options: &DecryptionOptions,
+    ) -> Result<Vec<u8>, CryptoError> {
+        gcm_decrypt(key, ciphertext, &[], options)
+    }
+
+    // GCM 解密，aad 必须与加密时一致，否则标签校验失败
+    fn gcm_decrypt(
+        key: &[u8],
+        ciphertext: &[u8],
+        aad: &[u8],
+        options: &DecryptionOptions,
     ) -> Result<Vec<u8>, CryptoError> {
         if options.nonce_length == 0 || options.tag_length == 0 {
             return Err(CryptoError::InvalidNonceLength);
@@ -229,10 +891,21 @@ pub mod safe {
         let mut decrypter = Crypter::new(cipher, Mode::Decrypt, key, Some(iv))?;
         decrypter.pad(false);
         decrypter.set_tag(tag)?;
+        if !aad.is_empty() {
+            decrypter
+                .aad_update(aad)
+                .map_err(|e| CryptoError::DecryptionFailed(e.to_string()))?;
+        }
 
-        let mut plaintext = Vec::new();
-        decrypter.update(cipher_data, &mut plaintext)?;
-        decrypter.finalize(&mut plaintext)?;
+        let mut plaintext = vec![0u8; cipher_data.len() + cipher.block_size()];
+        let mut count = decrypter
+            .update(cipher_data, &mut plaintext)
+            .map_err(|e| CryptoError::DecryptionFailed(e.to_string()))?;
+        // GCM 的 finalize 失败即认证标签不匹配
+        count += decrypter
+            .finalize(&mut plaintext[count..])
+            .map_err(|_| CryptoError::TagVerificationFailed)?;
+        plaintext.truncate(count);
 
         Ok(plaintext)
     }
@@ -249,365 +922,5232 @@ pub mod safe {
         decrypt(key, &ciphertext)
     }
 
-    // AES-CBC-192 模式（保持原逻辑）
+    // AES-CBC 模式（128/192/256 位密钥，PKCS#7 填充）
+    pub const CBC_HMAC_TAG_LEN: usize = 32; // HMAC-SHA256 标签长度
+
     #[derive(Debug, Clone, Copy, PartialEq, Eq)]
     pub enum AesCbcMode {
+        #[deprecated(
+            note = "全零 IV 加密已禁用（返回 FixedIvEncryptionDisabled），请改用 RandomIv；旧密文仍可解密"
+        )]
         FixedIv,
         RandomIv,
     }
 
-    pub fn encrypt_cbc_192(
+    fn select_cbc_cipher(key: &[u8]) -> Result<Cipher, CryptoError> {
+        match key.len() {
+            AES_128_KEY_LEN => Ok(Cipher::aes_128_cbc()),
+            AES_192_KEY_LEN => Ok(Cipher::aes_192_cbc()),
+            AES_256_KEY_LEN => Ok(Cipher::aes_256_cbc()),
+            len => Err(CryptoError::UnsupportedKeyLength { actual: len }),
+        }
+    }
+
+    fn random_iv() -> Result<[u8; AES_BLOCK_SIZE], CryptoError> {
+        let mut iv = [0u8; AES_BLOCK_SIZE];
+        rand::rand_bytes(&mut iv)
+            .map_err(|e| CryptoError::EncryptionFailed(format!("生成随机IV失败: {}", e)))?;
+        Ok(iv)
+    }
+
+    fn cbc_encrypt_with_iv(
         key: &[u8],
-        plaintext: &str,
-        mode: AesCbcMode,
-    ) -> Result<String, CryptoError> {
-        if key.len() != AES_192_KEY_LEN {
-            return Err(CryptoError::UnsupportedKeyLength { actual: key.len() });
+        iv: &[u8],
+        plaintext: &[u8],
+    ) -> Result<Vec<u8>, CryptoError> {
+        let cipher = select_cbc_cipher(key)?;
+        let mut encrypter = Crypter::new(cipher, Mode::Encrypt, key, Some(iv))?;
+        encrypter.pad(true);
+        Ok(run_crypter(&mut encrypter, cipher, plaintext)?)
+    }
+
+    fn cbc_decrypt_with_iv(
+        key: &[u8],
+        iv: &[u8],
+        ciphertext: &[u8],
+    ) -> Result<Vec<u8>, CryptoError> {
+        if iv.len() != AES_BLOCK_SIZE {
+            return Err(CryptoError::InvalidNonceLength);
+        }
+        let cipher = select_cbc_cipher(key)?;
+        let mut decrypter = Crypter::new(cipher, Mode::Decrypt, key, Some(iv))?;
+        decrypter.pad(true);
+        run_crypter(&mut decrypter, cipher, ciphertext)
+            .map_err(|e| CryptoError::DecryptionFailed(e.to_string()))
+    }
+
+    // HMAC-SHA256，按顺序处理多段数据
+    fn hmac_sha256(key: &[u8], parts: &[&[u8]]) -> Result<Vec<u8>, CryptoError> {
+        let pkey = PKey::hmac(key).map_err(|e| CryptoError::InvalidKey(e.to_string()))?;
+        let mut signer = Signer::new(MessageDigest::sha256(), &pkey)?;
+        for part in parts {
+            signer.update(part)?;
         }
+        Ok(signer.sign_to_vec()?)
+    }
 
-        let iv = match mode {
-            AesCbcMode::FixedIv => vec![0u8; AES_BLOCK_SIZE],
+    /// AES-CBC 加密任意字节数据，输出为 IV | 密文
+    ///
+    /// `FixedIv` 的全零 IV 会泄露相同明文前缀，新数据不再允许使用，传入时返回
+    /// `FixedIvEncryptionDisabled`；确需生成旧格式时使用 `encrypt_cbc_legacy_fixed_iv`。
+    pub fn encrypt_cbc(
+        key: &[u8],
+        plaintext: &[u8],
+        mode: AesCbcMode,
+    ) -> Result<Vec<u8>, CryptoError> {
+        match mode {
+            #[allow(deprecated)]
+            AesCbcMode::FixedIv => Err(CryptoError::FixedIvEncryptionDisabled),
             AesCbcMode::RandomIv => {
-                let mut iv = vec![0u8; AES_BLOCK_SIZE];
-                rand::rand_bytes(&mut iv)
-                    .map_err(|e| CryptoError::EncryptionFailed(format!("生成随机IV失败: {}", e)))?;
-                iv
+                let iv = random_iv()?;
+                let ciphertext = cbc_encrypt_with_iv(key, &iv, plaintext)?;
+                let mut result = Vec::with_capacity(AES_BLOCK_SIZE + ciphertext.len());
+                result.extend(&iv);
+                result.extend(&ciphertext);
+                Ok(result)
             }
-        };
-
-        let cipher = Cipher::aes_192_cbc();
-        let mut encrypter = Crypter::new(cipher, Mode::Encrypt, key, Some(&iv))?;
-        encrypter.pad(true);
+        }
+    }
 
-        let mut ciphertext = Vec::new();
-        encrypter.update(plaintext.as_bytes(), &mut ciphertext)?;
-        encrypter.finalize(&mut ciphertext)?;
+    /// 显式选择使用全零 IV 加密，只输出密文
+    ///
+    /// 仅供必须与旧系统互通的场景使用，已有数据请用 `migrate_legacy_ciphertext` 迁移到 AES-GCM。
+    pub fn encrypt_cbc_legacy_fixed_iv(
+        key: &[u8],
+        plaintext: &[u8],
+    ) -> Result<Vec<u8>, CryptoError> {
+        cbc_encrypt_with_iv(key, &[0u8; AES_BLOCK_SIZE], plaintext)
+    }
 
+    /// AES-CBC 解密，`FixedIv` 模式下输入为不含 IV 的旧格式密文
+    pub fn decrypt_cbc(key: &[u8], data: &[u8], mode: AesCbcMode) -> Result<Vec<u8>, CryptoError> {
         match mode {
-            AesCbcMode::FixedIv => Ok(base64_encode(&ciphertext)),
+            #[allow(deprecated)]
+            AesCbcMode::FixedIv => cbc_decrypt_with_iv(key, &[0u8; AES_BLOCK_SIZE], data),
             AesCbcMode::RandomIv => {
-                let iv_b64 = base64_encode(&iv);
-                let ciphertext_b64 = base64_encode(&ciphertext);
-                Ok(format!("R|{}|{}", iv_b64, ciphertext_b64))
+                let min_length = AES_BLOCK_SIZE * 2;
+                if data.len() < min_length {
+                    return Err(CryptoError::InvalidCiphertextLength {
+                        min_length,
+                        actual: data.len(),
+                    });
+                }
+                let (iv, ciphertext) = data.split_at(AES_BLOCK_SIZE);
+                cbc_decrypt_with_iv(key, iv, ciphertext)
             }
         }
     }
 
+    /// AES-CBC + HMAC-SHA256（先加密后认证）
+    ///
+    /// 总是使用随机 IV，输出为 IV | 密文 | HMAC(IV | 密文)。
+    /// `mac_key` 必须与加密密钥相互独立，长度至少 16 字节。
+    pub fn encrypt_cbc_hmac(
+        key: &[u8],
+        mac_key: &[u8],
+        plaintext: &[u8],
+    ) -> Result<Vec<u8>, CryptoError> {
+        if mac_key.len() < AES_128_KEY_LEN {
+            return Err(CryptoError::InvalidKey(format!(
+                "HMAC 密钥至少需要 {} 字节",
+                AES_128_KEY_LEN
+            )));
+        }
+
+        let mut result = encrypt_cbc(key, plaintext, AesCbcMode::RandomIv)?;
+        let tag = hmac_sha256(mac_key, &[&result])?;
+        result.extend(&tag);
+        Ok(result)
+    }
+
+    /// 校验 HMAC 后再解密，标签比较为常量时间
+    pub fn decrypt_cbc_hmac(
+        key: &[u8],
+        mac_key: &[u8],
+        data: &[u8],
+    ) -> Result<Vec<u8>, CryptoError> {
+        if mac_key.len() < AES_128_KEY_LEN {
+            return Err(CryptoError::InvalidKey(format!(
+                "HMAC 密钥至少需要 {} 字节",
+                AES_128_KEY_LEN
+            )));
+        }
+        let min_length = AES_BLOCK_SIZE * 2 + CBC_HMAC_TAG_LEN;
+        if data.len() < min_length {
+            return Err(CryptoError::InvalidCiphertextLength {
+                min_length,
+                actual: data.len(),
+            });
+        }
+
+        let (body, tag) = data.split_at(data.len() - CBC_HMAC_TAG_LEN);
+        let expected = hmac_sha256(mac_key, &[body])?;
+        if !memcmp::eq(&expected, tag) {
+            return Err(CryptoError::TagVerificationFailed);
+        }
+        decrypt_cbc(key, body, AesCbcMode::RandomIv)
+    }
+
+    // AES-CBC-192 兼容接口：保留旧的 Base64 文本格式（FixedIv 为纯密文，RandomIv 为 R|iv|密文）
+    // FixedIv 加密同样被禁用，解密仍然支持两种格式
+    pub fn encrypt_cbc_192(
+        key: &[u8],
+        plaintext: &str,
+        mode: AesCbcMode,
+    ) -> Result<String, CryptoError> {
+        if key.len() != AES_192_KEY_LEN {
+            return Err(CryptoError::UnsupportedKeyLength { actual: key.len() });
+        }
+
+        let data = encrypt_cbc(key, plaintext.as_bytes(), mode)?;
+        let (iv, ciphertext) = data.split_at(AES_BLOCK_SIZE);
+        Ok(format!(
+            "R|{}|{}",
+            base64_encode(iv),
+            base64_encode(ciphertext)
+        ))
+    }
+
     pub fn decrypt_cbc_192(key: &[u8], ciphertext_base64: &str) -> Result<String, CryptoError> {
         if key.len() != AES_192_KEY_LEN {
             return Err(CryptoError::UnsupportedKeyLength { actual: key.len() });
         }
 
-        if ciphertext_base64.starts_with("R|") {
+        let plaintext = if ciphertext_base64.starts_with("R|") {
             let parts: Vec<&str> = ciphertext_base64.splitn(3, '|').collect();
             if parts.len() != 3 {
                 return Err(CryptoError::InvalidCiphertextFormat);
             }
 
-            let iv_b64 = parts[1];
-            let ciphertext_b64 = parts[2];
-            let iv = base64_decode(iv_b64)?;
-            let ciphertext = base64_decode(ciphertext_b64)?;
-
-            if iv.len() != AES_BLOCK_SIZE {
-                return Err(CryptoError::InvalidNonceLength);
-            }
-
-            let cipher = Cipher::aes_192_cbc();
-            let mut decrypter = Crypter::new(cipher, Mode::Decrypt, key, Some(&iv))?;
-            decrypter.pad(true);
-
-            let mut plaintext = Vec::new();
-            decrypter.update(&ciphertext, &mut plaintext)?;
-            decrypter.finalize(&mut plaintext)?;
-
-            String::from_utf8(plaintext).map_err(|e| CryptoError::Utf8DecodingFailed(e.to_string()))
+            let iv = base64_decode(parts[1])?;
+            let ciphertext = base64_decode(parts[2])?;
+            cbc_decrypt_with_iv(key, &iv, &ciphertext)?
         } else {
             let ciphertext = base64_decode(ciphertext_base64)?;
-            let iv = [0u8; AES_BLOCK_SIZE];
-
-            let cipher = Cipher::aes_192_cbc();
-            let mut decrypter = Crypter::new(cipher, Mode::Decrypt, key, Some(&iv))?;
-            decrypter.pad(true);
+            cbc_decrypt_with_iv(key, &[0u8; AES_BLOCK_SIZE], &ciphertext)?
+        };
 
-            let mut plaintext = Vec::new();
-            decrypter.update(&ciphertext, &mut plaintext)?;
-            decrypter.finalize(&mut plaintext)?;
+        String::from_utf8(plaintext).map_err(|e| CryptoError::Utf8DecodingFailed(e.to_string()))
+    }
 
-            String::from_utf8(plaintext).map_err(|e| CryptoError::Utf8DecodingFailed(e.to_string()))
+    // 旧 CBC 密文迁移到 AES-GCM
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum LegacyCbcFormat {
+        /// 全零 IV，Base64 纯密文
+        FixedIv,
+        /// `R|iv|密文`，两段均为 Base64
+        RandomIv,
+    }
+
+    /// 根据文本判断旧 CBC 密文的格式
+    pub fn detect_legacy_cbc_format(ciphertext: &str) -> LegacyCbcFormat {
+        if ciphertext.starts_with("R|") {
+            LegacyCbcFormat::RandomIv
+        } else {
+            LegacyCbcFormat::FixedIv
+        }
+    }
+
+    /// 解密任意旧格式的 CBC 密文，旧密钥可以是 128/192/256 位
+    pub fn decrypt_legacy_cbc(old_key: &[u8], ciphertext: &str) -> Result<Vec<u8>, CryptoError> {
+        let ciphertext = ciphertext.trim();
+        match detect_legacy_cbc_format(ciphertext) {
+            LegacyCbcFormat::RandomIv => {
+                let parts: Vec<&str> = ciphertext.splitn(3, '|').collect();
+                if parts.len() != 3 {
+                    return Err(CryptoError::InvalidCiphertextFormat);
+                }
+                let iv = base64_decode(parts[1])?;
+                let data = base64_decode(parts[2])?;
+                cbc_decrypt_with_iv(old_key, &iv, &data)
+            }
+            LegacyCbcFormat::FixedIv => {
+                let data = base64_decode(ciphertext)?;
+                cbc_decrypt_with_iv(old_key, &[0u8; AES_BLOCK_SIZE], &data)
+            }
+        }
+    }
+
+    /// 把一条旧 CBC 密文重新加密为 AES-GCM（Base64，与 `encrypt_to_base64` 格式相同）
+    ///
+    /// `new_key` 为空时沿用旧密钥。
+    pub fn migrate_legacy_ciphertext(
+        old_key: &[u8],
+        new_key: Option<&[u8]>,
+        ciphertext: &str,
+    ) -> Result<String, CryptoError> {
+        let plaintext = decrypt_legacy_cbc(old_key, ciphertext)?;
+        encrypt_to_base64(new_key.unwrap_or(old_key), &plaintext)
+    }
+
+    /// 逐条迁移，返回 (序号, 结果)，适合边读数据库边写回
+    pub fn migrate_legacy_iter<'a, I, S>(
+        old_key: &'a [u8],
+        new_key: Option<&'a [u8]>,
+        items: I,
+    ) -> impl Iterator<Item = (usize, Result<String, CryptoError>)> + 'a
+    where
+        I: IntoIterator<Item = S>,
+        I::IntoIter: 'a,
+        S: AsRef<str>,
+    {
+        items.into_iter().enumerate().map(move |(index, item)| {
+            (
+                index,
+                migrate_legacy_ciphertext(old_key, new_key, item.as_ref()),
+            )
+        })
+    }
+
+    /// 批量迁移结果：成功和失败都带原始序号
+    #[derive(Debug, Default)]
+    pub struct MigrationReport {
+        pub migrated: Vec<(usize, String)>,
+        pub failed: Vec<(usize, CryptoError)>,
+    }
+
+    impl MigrationReport {
+        pub fn total(&self) -> usize {
+            self.migrated.len() + self.failed.len()
+        }
+
+        pub fn is_complete(&self) -> bool {
+            self.failed.is_empty()
+        }
+    }
+
+    /// 批量迁移，单条失败不会中断其余条目
+    pub fn migrate_legacy_batch<I, S>(
+        old_key: &[u8],
+        new_key: Option<&[u8]>,
+        items: I,
+    ) -> MigrationReport
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let mut report = MigrationReport::default();
+        for (index, result) in migrate_legacy_iter(old_key, new_key, items) {
+            match result {
+                Ok(ciphertext) => report.migrated.push((index, ciphertext)),
+                Err(e) => report.failed.push((index, e)),
+            }
+        }
+        report
+    }
+
+    // 口令派生密钥（PBKDF2 / scrypt / Argon2id）
+    pub const DEFAULT_SALT_LEN: usize = 16;
+    pub const MIN_SALT_LEN: usize = 8;
+
+    // 解密时允许的参数上限，防止恶意密文头部把内存或CPU耗尽
+    pub const MAX_PBKDF2_ITERATIONS: u32 = 10_000_000;
+    pub const MAX_SCRYPT_LOG_N: u32 = 24;
+    /// scrypt 的内存上限（字节），按 128 * r * (N + p + 2) 计算，同时作为 OpenSSL 的 maxmem
+    pub const MAX_SCRYPT_MEMORY: u64 = 1024 * 1024 * 1024;
+    pub const MAX_SCRYPT_PARALLELISM: u32 = 16;
+    pub const MAX_ARGON2_ITERATIONS: u32 = 32;
+    /// Argon2id 的内存上限（KiB），与 scrypt 相同为 1 GiB
+    pub const MAX_ARGON2_MEMORY_KIB: u32 = 1024 * 1024;
+    pub const MAX_ARGON2_LANES: u32 = 16;
+
+    // 口令加密输出格式：版本(1) | 算法(1) | 参数(3 x u32, 大端) | 盐长度(1) | 盐 | GCM密文
+    const PASSWORD_FORMAT_VERSION: u8 = 1;
+    const PASSWORD_HEADER_LEN: usize = 1 + 1 + 12 + 1;
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum KdfAlgorithm {
+        Pbkdf2Sha256,
+        Scrypt,
+        Argon2id,
+    }
+
+    impl KdfAlgorithm {
+        fn id(self) -> u8 {
+            match self {
+                KdfAlgorithm::Pbkdf2Sha256 => 0,
+                KdfAlgorithm::Scrypt => 1,
+                KdfAlgorithm::Argon2id => 2,
+            }
+        }
+
+        fn from_id(id: u8) -> Option<Self> {
+            match id {
+                0 => Some(KdfAlgorithm::Pbkdf2Sha256),
+                1 => Some(KdfAlgorithm::Scrypt),
+                2 => Some(KdfAlgorithm::Argon2id),
+                _ => None,
+            }
+        }
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum KdfParams {
+        Pbkdf2Sha256 {
+            iterations: u32,
+        },
+        Scrypt {
+            log_n: u32,
+            r: u32,
+            p: u32,
+        },
+        Argon2id {
+            iterations: u32,
+            memory_kib: u32,
+            lanes: u32,
+        },
+    }
+
+    impl KdfParams {
+        /// 各算法的推荐默认参数
+        pub fn default_for(algorithm: KdfAlgorithm) -> Self {
+            match algorithm {
+                KdfAlgorithm::Pbkdf2Sha256 => KdfParams::Pbkdf2Sha256 {
+                    iterations: 600_000,
+                },
+                KdfAlgorithm::Scrypt => KdfParams::Scrypt {
+                    log_n: 17,
+                    r: 8,
+                    p: 1,
+                },
+                KdfAlgorithm::Argon2id => KdfParams::Argon2id {
+                    iterations: 3,
+                    memory_kib: 64 * 1024,
+                    lanes: 4,
+                },
+            }
+        }
+
+        pub fn algorithm(&self) -> KdfAlgorithm {
+            match self {
+                KdfParams::Pbkdf2Sha256 { .. } => KdfAlgorithm::Pbkdf2Sha256,
+                KdfParams::Scrypt { .. } => KdfAlgorithm::Scrypt,
+                KdfParams::Argon2id { .. } => KdfAlgorithm::Argon2id,
+            }
+        }
+
+        /// 检查参数是否在允许范围内
+        pub fn validate(&self) -> Result<(), CryptoError> {
+            match *self {
+                KdfParams::Pbkdf2Sha256 { iterations } => {
+                    if iterations == 0 || iterations > MAX_PBKDF2_ITERATIONS {
+                        return Err(CryptoError::InvalidKdfParameters(format!(
+                            "PBKDF2 迭代次数必须在 1..={} 之间",
+                            MAX_PBKDF2_ITERATIONS
+                        )));
+                    }
+                }
+                KdfParams::Scrypt { log_n, r, p } => {
+                    if log_n == 0 || log_n > MAX_SCRYPT_LOG_N {
+                        return Err(CryptoError::InvalidKdfParameters(format!(
+                            "scrypt log2(N) 必须在 1..={} 之间",
+                            MAX_SCRYPT_LOG_N
+                        )));
+                    }
+                    if r == 0 || p == 0 || p > MAX_SCRYPT_PARALLELISM {
+                        return Err(CryptoError::InvalidKdfParameters(format!(
+                            "scrypt r 必须大于 0，p 必须在 1..={} 之间",
+                            MAX_SCRYPT_PARALLELISM
+                        )));
+                    }
+                    if scrypt_memory(log_n, r, p) > MAX_SCRYPT_MEMORY {
+                        return Err(CryptoError::InvalidKdfParameters(format!(
+                            "scrypt 内存占用 128*r*(N+p+2) 不能超过 {} 字节",
+                            MAX_SCRYPT_MEMORY
+                        )));
+                    }
+                }
+                KdfParams::Argon2id {
+                    iterations,
+                    memory_kib,
+                    lanes,
+                } => {
+                    if iterations == 0 || iterations > MAX_ARGON2_ITERATIONS {
+                        return Err(CryptoError::InvalidKdfParameters(format!(
+                            "Argon2id 迭代次数必须在 1..={} 之间",
+                            MAX_ARGON2_ITERATIONS
+                        )));
+                    }
+                    if lanes == 0 || lanes > MAX_ARGON2_LANES {
+                        return Err(CryptoError::InvalidKdfParameters(format!(
+                            "Argon2id lanes 必须在 1..={} 之间",
+                            MAX_ARGON2_LANES
+                        )));
+                    }
+                    if (memory_kib as u64) < (lanes as u64) * 8
+                        || memory_kib > MAX_ARGON2_MEMORY_KIB
+                    {
+                        return Err(CryptoError::InvalidKdfParameters(format!(
+                            "Argon2id 内存必须在 8*lanes..={} KiB 之间",
+                            MAX_ARGON2_MEMORY_KIB
+                        )));
+                    }
+                }
+            }
+            Ok(())
+        }
+
+        fn to_words(self) -> [u32; 3] {
+            match self {
+                KdfParams::Pbkdf2Sha256 { iterations } => [iterations, 0, 0],
+                KdfParams::Scrypt { log_n, r, p } => [log_n, r, p],
+                KdfParams::Argon2id {
+                    iterations,
+                    memory_kib,
+                    lanes,
+                } => [iterations, memory_kib, lanes],
+            }
+        }
+
+        fn from_words(algorithm: KdfAlgorithm, words: [u32; 3]) -> Self {
+            match algorithm {
+                KdfAlgorithm::Pbkdf2Sha256 => KdfParams::Pbkdf2Sha256 {
+                    iterations: words[0],
+                },
+                KdfAlgorithm::Scrypt => KdfParams::Scrypt {
+                    log_n: words[0],
+                    r: words[1],
+                    p: words[2],
+                },
+                KdfAlgorithm::Argon2id => KdfParams::Argon2id {
+                    iterations: words[0],
+                    memory_kib: words[1],
+                    lanes: words[2],
+                },
+            }
+        }
+    }
+
+    // EVP_PBE_scrypt 实际占用约 128 * r * (N + p + 2) 字节
+    fn scrypt_memory(log_n: u32, r: u32, p: u32) -> u64 {
+        128u64
+            .saturating_mul(r as u64)
+            .saturating_mul((1u64 << log_n.min(63)) + p as u64 + 2)
+    }
+
+    impl Default for KdfParams {
+        fn default() -> Self {
+            KdfParams::default_for(KdfAlgorithm::Argon2id)
+        }
+    }
+
+    /// 生成指定长度的随机盐
+    pub fn generate_salt(len: usize) -> Result<Vec<u8>, KeyError> {
+        if len < MIN_SALT_LEN {
+            return Err(KeyError::InvalidSaltLength);
+        }
+
+        let mut salt = vec![0u8; len];
+        rand::rand_bytes(&mut salt).map_err(|_| KeyError::RandomFailed)?;
+        Ok(salt)
+    }
+
+    /// 使用口令和盐派生指定长度的密钥
+    pub fn derive_key_from_password(
+        password: &[u8],
+        salt: &[u8],
+        params: &KdfParams,
+        key_len: usize,
+    ) -> Result<Vec<u8>, CryptoError> {
+        params.validate()?;
+        if salt.is_empty() {
+            return Err(CryptoError::InvalidKdfParameters("盐不能为空".into()));
+        }
+        if key_len == 0 {
+            return Err(CryptoError::InvalidKdfParameters("密钥长度不能为0".into()));
+        }
+
+        let mut key = vec![0u8; key_len];
+        match *params {
+            KdfParams::Pbkdf2Sha256 { iterations } => pkcs5::pbkdf2_hmac(
+                password,
+                salt,
+                iterations as usize,
+                MessageDigest::sha256(),
+                &mut key,
+            )
+            .map_err(|e| CryptoError::KeyDerivationFailed(e.to_string()))?,
+            KdfParams::Scrypt { log_n, r, p } => {
+                // maxmem 固定为上限，不随密文头部中的参数放大
+                let n = 1u64 << log_n;
+                pkcs5::scrypt(
+                    password,
+                    salt,
+                    n,
+                    r as u64,
+                    p as u64,
+                    MAX_SCRYPT_MEMORY,
+                    &mut key,
+                )
+                .map_err(|e| CryptoError::KeyDerivationFailed(e.to_string()))?
+            }
+            KdfParams::Argon2id {
+                iterations,
+                memory_kib,
+                lanes,
+            } => derive_argon2id(password, salt, iterations, memory_kib, lanes, &mut key)?,
+        }
+
+        Ok(key)
+    }
+
+    #[cfg(not(target_os = "android"))]
+    fn derive_argon2id(
+        password: &[u8],
+        salt: &[u8],
+        iterations: u32,
+        memory_kib: u32,
+        lanes: u32,
+        key: &mut [u8],
+    ) -> Result<(), CryptoError> {
+        openssl::kdf::argon2id(
+            None, password, salt, None, None, iterations, lanes, memory_kib, key,
+        )
+        .map_err(|e| CryptoError::KeyDerivationFailed(e.to_string()))
+    }
+
+    // Android 使用系统预编译的 OpenSSL，版本可能低于 3.2，不保证提供 Argon2
+    #[cfg(target_os = "android")]
+    fn derive_argon2id(
+        _password: &[u8],
+        _salt: &[u8],
+        _iterations: u32,
+        _memory_kib: u32,
+        _lanes: u32,
+        _key: &mut [u8],
+    ) -> Result<(), CryptoError> {
+        Err(CryptoError::KeyDerivationFailed(
+            "当前平台的 OpenSSL 不支持 Argon2id".into(),
+        ))
+    }
+
+    /// 口令加密：派生 AES-256 密钥后使用 GCM 加密，派生参数和盐写入输出头部
+    pub fn encrypt_with_password(
+        password: &[u8],
+        plaintext: &[u8],
+        params: &KdfParams,
+    ) -> Result<Vec<u8>, CryptoError> {
+        let salt = generate_salt(DEFAULT_SALT_LEN)
+            .map_err(|e| CryptoError::KeyDerivationFailed(e.to_string()))?;
+        let key = Zeroizing::new(derive_key_from_password(
+            password,
+            &salt,
+            params,
+            AES_256_KEY_LEN,
+        )?);
+        let body = encrypt(&key, plaintext)?;
+
+        let mut result = Vec::with_capacity(PASSWORD_HEADER_LEN + salt.len() + body.len());
+        result.push(PASSWORD_FORMAT_VERSION);
+        result.push(params.algorithm().id());
+        for word in params.to_words() {
+            result.extend(word.to_be_bytes());
+        }
+        result.push(salt.len() as u8);
+        result.extend(&salt);
+        result.extend(&body);
+
+        Ok(result)
+    }
+
+    // 读取口令加密头部中的派生参数
+    fn read_password_params(data: &[u8]) -> Result<KdfParams, CryptoError> {
+        if data.len() < PASSWORD_HEADER_LEN {
+            return Err(CryptoError::InvalidCiphertextLength {
+                min_length: PASSWORD_HEADER_LEN,
+                actual: data.len(),
+            });
+        }
+        if data[0] != PASSWORD_FORMAT_VERSION {
+            return Err(CryptoError::InvalidCiphertextFormat);
+        }
+
+        let algorithm =
+            KdfAlgorithm::from_id(data[1]).ok_or(CryptoError::InvalidCiphertextFormat)?;
+        let mut words = [0u32; 3];
+        for (i, word) in words.iter_mut().enumerate() {
+            let offset = 2 + i * 4;
+            let mut buf = [0u8; 4];
+            buf.copy_from_slice(&data[offset..offset + 4]);
+            *word = u32::from_be_bytes(buf);
+        }
+        Ok(KdfParams::from_words(algorithm, words))
+    }
+
+    /// 口令解密：从头部读取派生参数和盐，重新派生密钥后解密
+    pub fn decrypt_with_password(password: &[u8], data: &[u8]) -> Result<Vec<u8>, CryptoError> {
+        let params = read_password_params(data)?;
+
+        let salt_len = data[PASSWORD_HEADER_LEN - 1] as usize;
+        let body_start = PASSWORD_HEADER_LEN + salt_len;
+        let min_length = body_start + DEFAULT_NONCE_LEN + DEFAULT_TAG_LEN;
+        if data.len() < min_length {
+            return Err(CryptoError::InvalidCiphertextLength {
+                min_length,
+                actual: data.len(),
+            });
+        }
+
+        let salt = &data[PASSWORD_HEADER_LEN..body_start];
+        let key = Zeroizing::new(derive_key_from_password(
+            password,
+            salt,
+            &params,
+            AES_256_KEY_LEN,
+        )?);
+        decrypt(&key, &data[body_start..])
+    }
+
+    pub fn encrypt_with_password_to_base64(
+        password: &[u8],
+        plaintext: &[u8],
+        params: &KdfParams,
+    ) -> Result<String, CryptoError> {
+        let ciphertext = encrypt_with_password(password, plaintext, params)?;
+        Ok(base64_encode(&ciphertext))
+    }
+
+    pub fn decrypt_with_password_from_base64(
+        password: &[u8],
+        ciphertext_base64: &str,
+    ) -> Result<Vec<u8>, CryptoError> {
+        let ciphertext = base64_decode(ciphertext_base64)?;
+        decrypt_with_password(password, &ciphertext)
+    }
+
+    impl From<KdfParams> for CKdfParams {
+        fn from(params: KdfParams) -> Self {
+            match params {
+                KdfParams::Pbkdf2Sha256 { iterations } => CKdfParams {
+                    algorithm: CKdfAlgorithm::Pbkdf2Sha256,
+                    iterations,
+                    memory_cost: 0,
+                    block_size: 0,
+                    parallelism: 0,
+                },
+                KdfParams::Scrypt { log_n, r, p } => CKdfParams {
+                    algorithm: CKdfAlgorithm::Scrypt,
+                    iterations: 0,
+                    memory_cost: log_n,
+                    block_size: r,
+                    parallelism: p,
+                },
+                KdfParams::Argon2id {
+                    iterations,
+                    memory_kib,
+                    lanes,
+                } => CKdfParams {
+                    algorithm: CKdfAlgorithm::Argon2id,
+                    iterations,
+                    memory_cost: memory_kib,
+                    block_size: 0,
+                    parallelism: lanes,
+                },
+            }
+        }
+    }
+
+    impl From<CKdfParams> for KdfParams {
+        fn from(params: CKdfParams) -> Self {
+            match params.algorithm {
+                CKdfAlgorithm::Pbkdf2Sha256 => KdfParams::Pbkdf2Sha256 {
+                    iterations: params.iterations,
+                },
+                CKdfAlgorithm::Scrypt => KdfParams::Scrypt {
+                    log_n: params.memory_cost,
+                    r: params.block_size,
+                    p: params.parallelism,
+                },
+                CKdfAlgorithm::Argon2id => KdfParams::Argon2id {
+                    iterations: params.iterations,
+                    memory_kib: params.memory_cost,
+                    lanes: params.parallelism,
+                },
+            }
+        }
+    }
+
+    // HKDF 密钥派生（RFC 5869）
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum HkdfHash {
+        Sha256,
+        Sha512,
+    }
+
+    impl HkdfHash {
+        fn md(self) -> &'static MdRef {
+            match self {
+                HkdfHash::Sha256 => Md::sha256(),
+                HkdfHash::Sha512 => Md::sha512(),
+            }
+        }
+
+        /// 哈希输出长度，也是 extract 得到的 PRK 长度
+        pub fn output_len(self) -> usize {
+            match self {
+                HkdfHash::Sha256 => 32,
+                HkdfHash::Sha512 => 64,
+            }
+        }
+    }
+
+    fn hkdf_run(
+        hash: HkdfHash,
+        mode: HkdfMode,
+        key: &[u8],
+        salt: &[u8],
+        info: &[u8],
+        out_len: usize,
+    ) -> Result<Vec<u8>, CryptoError> {
+        let derive = || -> Result<Vec<u8>, ErrorStack> {
+            let mut ctx = PkeyCtx::new_id(Id::HKDF)?;
+            ctx.derive_init()?;
+            ctx.set_hkdf_mode(mode)?;
+            ctx.set_hkdf_md(hash.md())?;
+            ctx.set_hkdf_key(key)?;
+            if !salt.is_empty() {
+                ctx.set_hkdf_salt(salt)?;
+            }
+            if !info.is_empty() {
+                ctx.add_hkdf_info(info)?;
+            }
+            let mut out = vec![0u8; out_len];
+            let len = ctx.derive(Some(&mut out))?;
+            out.truncate(len);
+            Ok(out)
+        };
+        derive().map_err(|e| CryptoError::KeyDerivationFailed(e.to_string()))
+    }
+
+    fn check_hkdf_output_len(hash: HkdfHash, len: usize) -> Result<(), CryptoError> {
+        let max = 255 * hash.output_len();
+        if len == 0 || len > max {
+            return Err(CryptoError::InvalidKdfParameters(format!(
+                "HKDF 输出长度必须在 1..={} 之间",
+                max
+            )));
+        }
+        Ok(())
+    }
+
+    /// HKDF-Extract：由输入密钥材料和盐得到伪随机密钥 PRK，盐为空时按 RFC 使用全零盐
+    pub fn hkdf_extract(hash: HkdfHash, salt: &[u8], ikm: &[u8]) -> Result<Vec<u8>, CryptoError> {
+        hkdf_run(
+            hash,
+            HkdfMode::EXTRACT_ONLY,
+            ikm,
+            salt,
+            &[],
+            hash.output_len(),
+        )
+    }
+
+    /// HKDF-Expand：由 PRK 和 info 扩展出指定长度的输出密钥材料
+    pub fn hkdf_expand(
+        hash: HkdfHash,
+        prk: &[u8],
+        info: &[u8],
+        len: usize,
+    ) -> Result<Vec<u8>, CryptoError> {
+        check_hkdf_output_len(hash, len)?;
+        if prk.len() < hash.output_len() {
+            return Err(CryptoError::InvalidKdfParameters(format!(
+                "PRK 长度至少需要 {} 字节",
+                hash.output_len()
+            )));
+        }
+        hkdf_run(hash, HkdfMode::EXPAND_ONLY, prk, &[], info, len)
+    }
+
+    /// 完整的 HKDF（extract + expand）
+    pub fn hkdf(
+        hash: HkdfHash,
+        salt: &[u8],
+        ikm: &[u8],
+        info: &[u8],
+        len: usize,
+    ) -> Result<Vec<u8>, CryptoError> {
+        check_hkdf_output_len(hash, len)?;
+        hkdf_run(hash, HkdfMode::EXTRACT_THEN_EXPAND, ikm, salt, info, len)
+    }
+
+    /// 基于 HKDF 的密钥层级：主密钥只做一次 extract，之后按标签和上下文派生子密钥
+    ///
+    /// 例如以主密钥为根、`HardwareInfo::get_device_id` 的结果作为上下文，
+    /// 可以为每台设备、每种用途得到互不相关的 AES 密钥。
+    pub struct KeyHierarchy {
+        hash: HkdfHash,
+        prk: Zeroizing<Vec<u8>>,
+    }
+
+    impl KeyHierarchy {
+        pub fn new(master_key: &[u8], salt: &[u8]) -> Result<Self, CryptoError> {
+            Self::with_hash(HkdfHash::Sha256, master_key, salt)
+        }
+
+        pub fn with_hash(
+            hash: HkdfHash,
+            master_key: &[u8],
+            salt: &[u8],
+        ) -> Result<Self, CryptoError> {
+            if master_key.is_empty() {
+                return Err(CryptoError::InvalidKdfParameters("主密钥不能为空".into()));
+            }
+            let prk = Zeroizing::new(hkdf_extract(hash, salt, master_key)?);
+            Ok(KeyHierarchy { hash, prk })
+        }
+
+        // info = 标签长度 | 标签 | 上下文长度 | 上下文 | 输出长度，避免不同输入拼接后产生歧义
+        fn encode_info(label: &str, context: &[u8], len: usize) -> Vec<u8> {
+            let mut info = Vec::with_capacity(label.len() + context.len() + 10);
+            info.extend((label.len() as u32).to_be_bytes());
+            info.extend(label.as_bytes());
+            info.extend((context.len() as u32).to_be_bytes());
+            info.extend(context);
+            info.extend((len as u16).to_be_bytes());
+            info
+        }
+
+        /// 派生任意长度的子密钥材料
+        pub fn derive_bytes(
+            &self,
+            label: &str,
+            context: &[u8],
+            len: usize,
+        ) -> Result<Vec<u8>, CryptoError> {
+            check_hkdf_output_len(self.hash, len)?;
+            let info = Self::encode_info(label, context, len);
+            hkdf_expand(self.hash, &self.prk, &info, len)
+        }
+
+        /// 派生 AES 密钥，长度限制与 `generate_key` 一致
+        pub fn derive_key<const N: usize>(
+            &self,
+            label: &str,
+            context: &[u8],
+        ) -> Result<[u8; N], CryptoError> {
+            if N != AES_128_KEY_LEN && N != AES_192_KEY_LEN && N != AES_256_KEY_LEN {
+                return Err(CryptoError::UnsupportedKeyLength { actual: N });
+            }
+
+            let bytes = Zeroizing::new(self.derive_bytes(label, context, N)?);
+            let mut key = [0u8; N];
+            key.copy_from_slice(&bytes);
+            Ok(key)
+        }
+
+        /// 派生下一级层级，子层级的密钥与父层级直接派生的密钥互不相同
+        pub fn child(&self, label: &str, context: &[u8]) -> Result<KeyHierarchy, CryptoError> {
+            let child_key =
+                Zeroizing::new(self.derive_bytes(label, context, self.hash.output_len())?);
+            Self::with_hash(self.hash, &child_key, b"FirmNetter key hierarchy")
+        }
+    }
+
+    // 数字签名（Ed25519 / ECDSA P-256）
+    const FILE_READ_CHUNK: usize = 64 * 1024;
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum SignatureAlgorithm {
+        Ed25519,
+        EcdsaP256,
+    }
+
+    /// 密钥编码格式
+    ///
+    /// PEM/DER 私钥为 PKCS#8，公钥为 SubjectPublicKeyInfo；
+    /// Raw 对 Ed25519 为 32 字节原始密钥，对 P-256 私钥为 32 字节标量、公钥为 65 字节未压缩点。
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum KeyFormat {
+        Pem,
+        Der,
+        Raw,
+    }
+
+    fn p256_group() -> Result<EcGroup, CryptoError> {
+        EcGroup::from_curve_name(Nid::X9_62_PRIME256V1)
+            .map_err(|e| CryptoError::InvalidKey(e.to_string()))
+    }
+
+    // EC 公钥与未压缩点编码之间的转换
+    fn ec_public_key_to_bytes<T: HasPublic>(pkey: &PKey<T>) -> Result<Vec<u8>, ErrorStack> {
+        let ec = pkey.ec_key()?;
+        let mut ctx = BigNumContext::new()?;
+        ec.public_key()
+            .to_bytes(ec.group(), PointConversionForm::UNCOMPRESSED, &mut ctx)
+    }
+
+    fn ec_public_key_from_bytes(group: &EcGroup, data: &[u8]) -> Result<PKey<Public>, ErrorStack> {
+        let mut ctx = BigNumContext::new()?;
+        let point = EcPoint::from_bytes(group, data, &mut ctx)?;
+        let ec = EcKey::from_public_key(group, &point)?;
+        ec.check_key()?;
+        PKey::from_ec_key(ec)
+    }
+
+    // 确认 PEM/DER 中读出的密钥与期望的签名算法一致
+    fn check_signature_key<T: HasPublic>(
+        algorithm: SignatureAlgorithm,
+        pkey: &PKey<T>,
+    ) -> Result<(), CryptoError> {
+        let matches = match algorithm {
+            SignatureAlgorithm::Ed25519 => pkey.id() == Id::ED25519,
+            SignatureAlgorithm::EcdsaP256 => {
+                pkey.id() == Id::EC
+                    && pkey
+                        .ec_key()
+                        .ok()
+                        .and_then(|k| k.group().curve_name())
+                        .is_some_and(|nid| nid == Nid::X9_62_PRIME256V1)
+            }
+        };
+        if matches {
+            Ok(())
+        } else {
+            Err(CryptoError::InvalidKey(format!(
+                "密钥类型与 {:?} 不匹配",
+                algorithm
+            )))
+        }
+    }
+
+    fn export_public_key<T: HasPublic>(
+        algorithm: SignatureAlgorithm,
+        pkey: &PKey<T>,
+        format: KeyFormat,
+    ) -> Result<Vec<u8>, CryptoError> {
+        let result = match format {
+            KeyFormat::Pem => pkey.public_key_to_pem(),
+            KeyFormat::Der => pkey.public_key_to_der(),
+            KeyFormat::Raw => match algorithm {
+                SignatureAlgorithm::Ed25519 => pkey.raw_public_key(),
+                SignatureAlgorithm::EcdsaP256 => ec_public_key_to_bytes(pkey),
+            },
+        };
+        result.map_err(|e| CryptoError::InvalidKey(e.to_string()))
+    }
+
+    /// 签名私钥
+    pub struct SigningKey {
+        algorithm: SignatureAlgorithm,
+        pkey: PKey<Private>,
+    }
+
+    impl SigningKey {
+        /// 生成新的密钥对
+        pub fn generate(algorithm: SignatureAlgorithm) -> Result<Self, CryptoError> {
+            let pkey = match algorithm {
+                SignatureAlgorithm::Ed25519 => PKey::generate_ed25519(),
+                SignatureAlgorithm::EcdsaP256 => {
+                    let group = p256_group()?;
+                    EcKey::generate(&group).and_then(PKey::from_ec_key)
+                }
+            }
+            .map_err(|e| CryptoError::KeyGenerationFailed(e.to_string()))?;
+            Ok(SigningKey { algorithm, pkey })
+        }
+
+        /// 从指定格式导入私钥
+        pub fn from_bytes(
+            algorithm: SignatureAlgorithm,
+            data: &[u8],
+            format: KeyFormat,
+        ) -> Result<Self, CryptoError> {
+            let pkey = match format {
+                KeyFormat::Pem => PKey::private_key_from_pem(data),
+                KeyFormat::Der => PKey::private_key_from_der(data),
+                KeyFormat::Raw => match algorithm {
+                    SignatureAlgorithm::Ed25519 => {
+                        PKey::private_key_from_raw_bytes(data, Id::ED25519)
+                    }
+                    SignatureAlgorithm::EcdsaP256 => {
+                        if data.len() != 32 {
+                            return Err(CryptoError::InvalidKey(
+                                "P-256 原始私钥必须为 32 字节".into(),
+                            ));
+                        }
+                        let group = p256_group()?;
+                        (|| {
+                            let ctx = BigNumContext::new()?;
+                            let scalar = BigNum::from_slice(data)?;
+                            let mut point = EcPoint::new(&group)?;
+                            point.mul_generator(&group, &scalar, &ctx)?;
+                            let ec = EcKey::from_private_components(&group, &scalar, &point)?;
+                            ec.check_key()?;
+                            PKey::from_ec_key(ec)
+                        })()
+                    }
+                },
+            }
+            .map_err(|e| CryptoError::InvalidKey(e.to_string()))?;
+
+            check_signature_key(algorithm, &pkey)?;
+            Ok(SigningKey { algorithm, pkey })
+        }
+
+        /// 按指定格式导出私钥
+        pub fn to_bytes(&self, format: KeyFormat) -> Result<Vec<u8>, CryptoError> {
+            let result = match format {
+                KeyFormat::Pem => self.pkey.private_key_to_pem_pkcs8(),
+                KeyFormat::Der => self.pkey.private_key_to_pkcs8(),
+                KeyFormat::Raw => match self.algorithm {
+                    SignatureAlgorithm::Ed25519 => self.pkey.raw_private_key(),
+                    SignatureAlgorithm::EcdsaP256 => self
+                        .pkey
+                        .ec_key()
+                        .and_then(|ec| ec.private_key().to_vec_padded(32)),
+                },
+            };
+            result.map_err(|e| CryptoError::InvalidKey(e.to_string()))
+        }
+
+        pub fn algorithm(&self) -> SignatureAlgorithm {
+            self.algorithm
+        }
+
+        /// 取出对应的公钥
+        pub fn verifying_key(&self) -> Result<VerifyingKey, CryptoError> {
+            let der = export_public_key(self.algorithm, &self.pkey, KeyFormat::Der)?;
+            VerifyingKey::from_bytes(self.algorithm, &der, KeyFormat::Der)
+        }
+
+        /// 对消息签名，ECDSA 使用 SHA-256 且输出 DER 编码的签名
+        pub fn sign(&self, message: &[u8]) -> Result<Vec<u8>, CryptoError> {
+            let result = match self.algorithm {
+                SignatureAlgorithm::Ed25519 => Signer::new_without_digest(&self.pkey)
+                    .and_then(|mut signer| signer.sign_oneshot_to_vec(message)),
+                SignatureAlgorithm::EcdsaP256 => Signer::new(MessageDigest::sha256(), &self.pkey)
+                    .and_then(|mut signer| {
+                        signer.update(message)?;
+                        signer.sign_to_vec()
+                    }),
+            };
+            result.map_err(|e| CryptoError::SignatureFailed(e.to_string()))
+        }
+
+        /// 对磁盘上的文件生成分离签名
+        pub fn sign_file<P: AsRef<Path>>(&self, path: P) -> Result<Vec<u8>, CryptoError> {
+            match self.algorithm {
+                // Ed25519 只支持一次性签名，通过 mmap 避免把整个文件读入堆内存
+                SignatureAlgorithm::Ed25519 => with_mapped_file(path, |data| self.sign(data)),
+                SignatureAlgorithm::EcdsaP256 => {
+                    let mut signer = Signer::new(MessageDigest::sha256(), &self.pkey)
+                        .map_err(|e| CryptoError::SignatureFailed(e.to_string()))?;
+                    read_file_chunks(path, |chunk| signer.update(chunk))?;
+                    signer
+                        .sign_to_vec()
+                        .map_err(|e| CryptoError::SignatureFailed(e.to_string()))
+                }
+            }
+        }
+    }
+
+    /// 签名公钥
+    pub struct VerifyingKey {
+        algorithm: SignatureAlgorithm,
+        pkey: PKey<Public>,
+    }
+
+    impl VerifyingKey {
+        /// 从指定格式导入公钥
+        pub fn from_bytes(
+            algorithm: SignatureAlgorithm,
+            data: &[u8],
+            format: KeyFormat,
+        ) -> Result<Self, CryptoError> {
+            let pkey = match format {
+                KeyFormat::Pem => PKey::public_key_from_pem(data),
+                KeyFormat::Der => PKey::public_key_from_der(data),
+                KeyFormat::Raw => match algorithm {
+                    SignatureAlgorithm::Ed25519 => {
+                        PKey::public_key_from_raw_bytes(data, Id::ED25519)
+                    }
+                    SignatureAlgorithm::EcdsaP256 => ec_public_key_from_bytes(&p256_group()?, data),
+                },
+            }
+            .map_err(|e| CryptoError::InvalidKey(e.to_string()))?;
+
+            check_signature_key(algorithm, &pkey)?;
+            Ok(VerifyingKey { algorithm, pkey })
+        }
+
+        /// 按指定格式导出公钥
+        pub fn to_bytes(&self, format: KeyFormat) -> Result<Vec<u8>, CryptoError> {
+            export_public_key(self.algorithm, &self.pkey, format)
+        }
+
+        pub fn algorithm(&self) -> SignatureAlgorithm {
+            self.algorithm
+        }
+
+        /// 验证签名，签名不匹配时返回 `SignatureVerificationFailed`
+        pub fn verify(&self, message: &[u8], signature: &[u8]) -> Result<(), CryptoError> {
+            let result = match self.algorithm {
+                SignatureAlgorithm::Ed25519 => Verifier::new_without_digest(&self.pkey)
+                    .and_then(|mut verifier| verifier.verify_oneshot(signature, message)),
+                SignatureAlgorithm::EcdsaP256 => Verifier::new(MessageDigest::sha256(), &self.pkey)
+                    .and_then(|mut verifier| {
+                        verifier.update(message)?;
+                        verifier.verify(signature)
+                    }),
+            };
+            match result {
+                Ok(true) => Ok(()),
+                // 格式错误的签名在 OpenSSL 中表现为错误栈，同样视为验证失败
+                Ok(false) | Err(_) => Err(CryptoError::SignatureVerificationFailed),
+            }
+        }
+
+        /// 验证磁盘文件的分离签名，不会把文件整体读入内存
+        pub fn verify_file<P: AsRef<Path>>(
+            &self,
+            path: P,
+            signature: &[u8],
+        ) -> Result<(), CryptoError> {
+            match self.algorithm {
+                SignatureAlgorithm::Ed25519 => {
+                    with_mapped_file(path, |data| self.verify(data, signature))
+                }
+                SignatureAlgorithm::EcdsaP256 => {
+                    let mut verifier = Verifier::new(MessageDigest::sha256(), &self.pkey)
+                        .map_err(|e| CryptoError::SignatureFailed(e.to_string()))?;
+                    read_file_chunks(path, |chunk| verifier.update(chunk))?;
+                    match verifier.verify(signature) {
+                        Ok(true) => Ok(()),
+                        Ok(false) | Err(_) => Err(CryptoError::SignatureVerificationFailed),
+                    }
+                }
+            }
+        }
+    }
+
+    // 以只读 mmap 方式打开文件，空文件直接传入空切片
+    fn with_mapped_file<P, R, F>(path: P, f: F) -> Result<R, CryptoError>
+    where
+        P: AsRef<Path>,
+        F: FnOnce(&[u8]) -> Result<R, CryptoError>,
+    {
+        let file = File::open(path).map_err(|e| CryptoError::IoError(e.to_string()))?;
+        let len = file
+            .metadata()
+            .map_err(|e| CryptoError::IoError(e.to_string()))?
+            .len();
+        if len == 0 {
+            return f(&[]);
+        }
+        let map = unsafe { Mmap::map(&file) }.map_err(|e| CryptoError::IoError(e.to_string()))?;
+        f(&map)
+    }
+
+    // 分块读取文件并交给回调处理
+    fn read_file_chunks<P, F>(path: P, mut f: F) -> Result<(), CryptoError>
+    where
+        P: AsRef<Path>,
+        F: FnMut(&[u8]) -> Result<(), ErrorStack>,
+    {
+        let mut file = File::open(path).map_err(|e| CryptoError::IoError(e.to_string()))?;
+        let mut buffer = vec![0u8; FILE_READ_CHUNK];
+        loop {
+            let read = file
+                .read(&mut buffer)
+                .map_err(|e| CryptoError::IoError(e.to_string()))?;
+            if read == 0 {
+                break;
+            }
+            f(&buffer[..read]).map_err(|e| CryptoError::SignatureFailed(e.to_string()))?;
+        }
+        Ok(())
+    }
+
+    // 混合公钥加密：临时密钥 ECDH + HKDF-SHA256 + AES-256-GCM
+    // 输出格式：版本(1) | 算法(1) | 临时公钥长度(1) | 临时公钥 | encrypt_with_options 的输出
+    const HYBRID_FORMAT_VERSION: u8 = 1;
+    const HYBRID_HKDF_INFO: &[u8] = b"FirmNetter hybrid encryption v1";
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum KeyAgreementAlgorithm {
+        X25519,
+        EcdhP256,
+    }
+
+    impl KeyAgreementAlgorithm {
+        fn id(self) -> u8 {
+            match self {
+                KeyAgreementAlgorithm::X25519 => 0,
+                KeyAgreementAlgorithm::EcdhP256 => 1,
+            }
+        }
+
+        fn from_id(id: u8) -> Option<Self> {
+            match id {
+                0 => Some(KeyAgreementAlgorithm::X25519),
+                1 => Some(KeyAgreementAlgorithm::EcdhP256),
+                _ => None,
+            }
+        }
+
+        // 根据 PEM 中的密钥类型识别算法
+        fn detect<T: HasPublic>(pkey: &PKey<T>) -> Result<Self, CryptoError> {
+            if pkey.id() == Id::X25519 {
+                return Ok(KeyAgreementAlgorithm::X25519);
+            }
+            check_signature_key(SignatureAlgorithm::EcdsaP256, pkey)
+                .map(|_| KeyAgreementAlgorithm::EcdhP256)
+                .map_err(|_| CryptoError::InvalidKey("仅支持 X25519 或 P-256 密钥".into()))
+        }
+
+        fn generate(self) -> Result<PKey<Private>, CryptoError> {
+            match self {
+                KeyAgreementAlgorithm::X25519 => PKey::generate_x25519(),
+                KeyAgreementAlgorithm::EcdhP256 => {
+                    let group = p256_group()?;
+                    EcKey::generate(&group).and_then(PKey::from_ec_key)
+                }
+            }
+            .map_err(|e| CryptoError::KeyGenerationFailed(e.to_string()))
+        }
+
+        fn public_key_to_bytes<T: HasPublic>(self, pkey: &PKey<T>) -> Result<Vec<u8>, CryptoError> {
+            match self {
+                KeyAgreementAlgorithm::X25519 => pkey.raw_public_key(),
+                KeyAgreementAlgorithm::EcdhP256 => ec_public_key_to_bytes(pkey),
+            }
+            .map_err(|e| CryptoError::InvalidKey(e.to_string()))
+        }
+
+        fn public_key_from_bytes(self, data: &[u8]) -> Result<PKey<Public>, CryptoError> {
+            match self {
+                KeyAgreementAlgorithm::X25519 => PKey::public_key_from_raw_bytes(data, Id::X25519),
+                KeyAgreementAlgorithm::EcdhP256 => ec_public_key_from_bytes(&p256_group()?, data),
+            }
+            .map_err(|e| CryptoError::InvalidKey(e.to_string()))
+        }
+    }
+
+    /// 混合加密的接收方私钥
+    pub struct HybridPrivateKey {
+        algorithm: KeyAgreementAlgorithm,
+        pkey: PKey<Private>,
+    }
+
+    impl HybridPrivateKey {
+        pub fn generate(algorithm: KeyAgreementAlgorithm) -> Result<Self, CryptoError> {
+            let pkey = algorithm.generate()?;
+            Ok(HybridPrivateKey { algorithm, pkey })
+        }
+
+        /// 从 PKCS#8 PEM 导入，算法由密钥类型决定
+        pub fn from_pem(pem: &[u8]) -> Result<Self, CryptoError> {
+            let pkey = PKey::private_key_from_pem(pem)
+                .map_err(|e| CryptoError::InvalidKey(e.to_string()))?;
+            let algorithm = KeyAgreementAlgorithm::detect(&pkey)?;
+            Ok(HybridPrivateKey { algorithm, pkey })
+        }
+
+        pub fn to_pem(&self) -> Result<Vec<u8>, CryptoError> {
+            self.pkey
+                .private_key_to_pem_pkcs8()
+                .map_err(|e| CryptoError::InvalidKey(e.to_string()))
+        }
+
+        pub fn algorithm(&self) -> KeyAgreementAlgorithm {
+            self.algorithm
+        }
+
+        pub fn public_key(&self) -> Result<HybridPublicKey, CryptoError> {
+            let der = self
+                .pkey
+                .public_key_to_der()
+                .map_err(|e| CryptoError::InvalidKey(e.to_string()))?;
+            let pkey = PKey::public_key_from_der(&der)
+                .map_err(|e| CryptoError::InvalidKey(e.to_string()))?;
+            Ok(HybridPublicKey {
+                algorithm: self.algorithm,
+                pkey,
+            })
+        }
+    }
+
+    /// 混合加密的接收方公钥
+    pub struct HybridPublicKey {
+        algorithm: KeyAgreementAlgorithm,
+        pkey: PKey<Public>,
+    }
+
+    impl HybridPublicKey {
+        /// 从 SubjectPublicKeyInfo PEM 导入，算法由密钥类型决定
+        pub fn from_pem(pem: &[u8]) -> Result<Self, CryptoError> {
+            let pkey = PKey::public_key_from_pem(pem)
+                .map_err(|e| CryptoError::InvalidKey(e.to_string()))?;
+            let algorithm = KeyAgreementAlgorithm::detect(&pkey)?;
+            Ok(HybridPublicKey { algorithm, pkey })
+        }
+
+        pub fn to_pem(&self) -> Result<Vec<u8>, CryptoError> {
+            self.pkey
+                .public_key_to_pem()
+                .map_err(|e| CryptoError::InvalidKey(e.to_string()))
+        }
+
+        pub fn algorithm(&self) -> KeyAgreementAlgorithm {
+            self.algorithm
+        }
+    }
+
+    // ECDH 共享秘密经 HKDF 派生为 AES-256 密钥，盐绑定双方公钥
+    fn hybrid_derive_key<T: HasPrivate, U: HasPublic>(
+        private: &PKey<T>,
+        peer: &PKey<U>,
+        ephemeral_public: &[u8],
+        recipient_public: &[u8],
+    ) -> Result<Zeroizing<Vec<u8>>, CryptoError> {
+        let shared = Deriver::new(private)
+            .and_then(|mut deriver| {
+                deriver.set_peer(peer)?;
+                deriver.derive_to_vec()
+            })
+            .map(Zeroizing::new)
+            .map_err(|e| CryptoError::KeyDerivationFailed(e.to_string()))?;
+
+        let mut salt = Vec::with_capacity(ephemeral_public.len() + recipient_public.len());
+        salt.extend(ephemeral_public);
+        salt.extend(recipient_public);
+        hkdf(
+            HkdfHash::Sha256,
+            &salt,
+            &shared,
+            HYBRID_HKDF_INFO,
+            AES_256_KEY_LEN,
+        )
+        .map(Zeroizing::new)
+    }
+
+    /// 使用接收方公钥加密，只有持有对应私钥的一方可以解密
+    pub fn hybrid_encrypt(
+        recipient: &HybridPublicKey,
+        plaintext: &[u8],
+    ) -> Result<Vec<u8>, CryptoError> {
+        let algorithm = recipient.algorithm;
+        let ephemeral = algorithm.generate()?;
+        let ephemeral_public = algorithm.public_key_to_bytes(&ephemeral)?;
+        let recipient_public = algorithm.public_key_to_bytes(&recipient.pkey)?;
+
+        let key = hybrid_derive_key(
+            &ephemeral,
+            &recipient.pkey,
+            &ephemeral_public,
+            &recipient_public,
+        )?;
+        let body = encrypt_with_options(&key, plaintext, &EncryptionOptions::default())?;
+
+        let mut result = Vec::with_capacity(3 + ephemeral_public.len() + body.len());
+        result.push(HYBRID_FORMAT_VERSION);
+        result.push(algorithm.id());
+        result.push(ephemeral_public.len() as u8);
+        result.extend(&ephemeral_public);
+        result.extend(&body);
+        Ok(result)
+    }
+
+    /// 使用接收方私钥解密 `hybrid_encrypt` 的输出
+    pub fn hybrid_decrypt(key: &HybridPrivateKey, data: &[u8]) -> Result<Vec<u8>, CryptoError> {
+        if data.len() < 3 {
+            return Err(CryptoError::InvalidCiphertextLength {
+                min_length: 3,
+                actual: data.len(),
+            });
+        }
+        if data[0] != HYBRID_FORMAT_VERSION {
+            return Err(CryptoError::InvalidCiphertextFormat);
+        }
+        let algorithm =
+            KeyAgreementAlgorithm::from_id(data[1]).ok_or(CryptoError::InvalidCiphertextFormat)?;
+        if algorithm != key.algorithm {
+            return Err(CryptoError::InvalidKey(
+                "密文使用的密钥算法与私钥不一致".into(),
+            ));
+        }
+
+        let public_end = 3 + data[2] as usize;
+        let min_length = public_end + DEFAULT_NONCE_LEN + DEFAULT_TAG_LEN;
+        if data.len() < min_length {
+            return Err(CryptoError::InvalidCiphertextLength {
+                min_length,
+                actual: data.len(),
+            });
+        }
+
+        let ephemeral_public = &data[3..public_end];
+        let ephemeral = algorithm.public_key_from_bytes(ephemeral_public)?;
+        let recipient_public = algorithm.public_key_to_bytes(&key.pkey)?;
+
+        let aes_key =
+            hybrid_derive_key(&key.pkey, &ephemeral, ephemeral_public, &recipient_public)?;
+        decrypt_with_options(&aes_key, &data[public_end..], &DecryptionOptions::default())
+    }
+
+    pub fn hybrid_encrypt_to_base64(
+        recipient: &HybridPublicKey,
+        plaintext: &[u8],
+    ) -> Result<String, CryptoError> {
+        let ciphertext = hybrid_encrypt(recipient, plaintext)?;
+        Ok(base64_encode(&ciphertext))
+    }
+
+    pub fn hybrid_decrypt_from_base64(
+        key: &HybridPrivateKey,
+        ciphertext_base64: &str,
+    ) -> Result<Vec<u8>, CryptoError> {
+        let ciphertext = base64_decode(ciphertext_base64)?;
+        hybrid_decrypt(key, &ciphertext)
+    }
+
+    // RSA 密钥与 OAEP / PSS 运算
+    pub const RSA_KEY_SIZES: [u32; 3] = [2048, 3072, 4096];
+
+    /// OAEP 与 PSS 使用的哈希算法（MGF1 使用相同的哈希）
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum RsaHash {
+        Sha1,
+        Sha256,
+        Sha384,
+        Sha512,
+    }
+
+    impl RsaHash {
+        fn md(self) -> &'static MdRef {
+            match self {
+                RsaHash::Sha1 => Md::sha1(),
+                RsaHash::Sha256 => Md::sha256(),
+                RsaHash::Sha384 => Md::sha384(),
+                RsaHash::Sha512 => Md::sha512(),
+            }
+        }
+
+        fn message_digest(self) -> MessageDigest {
+            match self {
+                RsaHash::Sha1 => MessageDigest::sha1(),
+                RsaHash::Sha256 => MessageDigest::sha256(),
+                RsaHash::Sha384 => MessageDigest::sha384(),
+                RsaHash::Sha512 => MessageDigest::sha512(),
+            }
+        }
+    }
+
+    fn check_rsa_key<T>(pkey: &PKey<T>) -> Result<(), CryptoError> {
+        if pkey.id() == Id::RSA {
+            Ok(())
+        } else {
+            Err(CryptoError::InvalidKey("不是 RSA 密钥".into()))
+        }
+    }
+
+    fn rsa_error(err: ErrorStack) -> CryptoError {
+        CryptoError::RsaOperationFailed(err.to_string())
+    }
+
+    /// RSA 私钥
+    pub struct RsaPrivateKey {
+        pkey: PKey<Private>,
+    }
+
+    impl RsaPrivateKey {
+        /// 生成 2048/3072/4096 位密钥
+        pub fn generate(bits: u32) -> Result<Self, CryptoError> {
+            if !RSA_KEY_SIZES.contains(&bits) {
+                return Err(CryptoError::UnsupportedRsaKeySize { bits });
+            }
+            let pkey = Rsa::generate(bits)
+                .and_then(PKey::from_rsa)
+                .map_err(|e| CryptoError::KeyGenerationFailed(e.to_string()))?;
+            Ok(RsaPrivateKey { pkey })
+        }
+
+        fn from_pkey(pkey: PKey<Private>) -> Result<Self, CryptoError> {
+            check_rsa_key(&pkey)?;
+            Ok(RsaPrivateKey { pkey })
+        }
+
+        /// 从 PEM 导入（PKCS#1 或 PKCS#8），加密的 PEM 需提供口令
+        pub fn from_pem(pem: &[u8], passphrase: Option<&[u8]>) -> Result<Self, CryptoError> {
+            let pkey = match passphrase {
+                Some(pass) => PKey::private_key_from_pem_passphrase(pem, pass)
+                    .map_err(|_| CryptoError::KeyDecryptionFailed)?,
+                None => PKey::private_key_from_pem(pem)
+                    .map_err(|e| CryptoError::InvalidKey(e.to_string()))?,
+            };
+            Self::from_pkey(pkey)
+        }
+
+        /// 从 DER 导入，加密的 PKCS#8 需提供口令
+        pub fn from_der(der: &[u8], passphrase: Option<&[u8]>) -> Result<Self, CryptoError> {
+            let pkey = match passphrase {
+                Some(pass) => PKey::private_key_from_pkcs8_passphrase(der, pass)
+                    .map_err(|_| CryptoError::KeyDecryptionFailed)?,
+                None => PKey::private_key_from_der(der)
+                    .map_err(|e| CryptoError::InvalidKey(e.to_string()))?,
+            };
+            Self::from_pkey(pkey)
+        }
+
+        /// 导出为 PKCS#8 PEM，提供口令时使用 AES-256-CBC 加密
+        pub fn to_pem(&self, passphrase: Option<&[u8]>) -> Result<Vec<u8>, CryptoError> {
+            match passphrase {
+                Some(pass) => self
+                    .pkey
+                    .private_key_to_pem_pkcs8_passphrase(Cipher::aes_256_cbc(), pass),
+                None => self.pkey.private_key_to_pem_pkcs8(),
+            }
+            .map_err(|e| CryptoError::InvalidKey(e.to_string()))
+        }
+
+        /// 导出为 PKCS#8 DER，提供口令时使用 AES-256-CBC 加密
+        pub fn to_der(&self, passphrase: Option<&[u8]>) -> Result<Vec<u8>, CryptoError> {
+            match passphrase {
+                Some(pass) => self
+                    .pkey
+                    .private_key_to_pkcs8_passphrase(Cipher::aes_256_cbc(), pass),
+                None => self.pkey.private_key_to_pkcs8(),
+            }
+            .map_err(|e| CryptoError::InvalidKey(e.to_string()))
+        }
+
+        pub fn bits(&self) -> u32 {
+            self.pkey.bits()
+        }
+
+        pub fn public_key(&self) -> Result<RsaPublicKey, CryptoError> {
+            let der = self
+                .pkey
+                .public_key_to_der()
+                .map_err(|e| CryptoError::InvalidKey(e.to_string()))?;
+            RsaPublicKey::from_der(&der)
+        }
+
+        /// RSA-OAEP 解密
+        pub fn decrypt_oaep(
+            &self,
+            ciphertext: &[u8],
+            hash: RsaHash,
+        ) -> Result<Vec<u8>, CryptoError> {
+            let mut ctx = PkeyCtx::new(&self.pkey).map_err(rsa_error)?;
+            ctx.decrypt_init().map_err(rsa_error)?;
+            ctx.set_rsa_padding(Padding::PKCS1_OAEP)
+                .map_err(rsa_error)?;
+            ctx.set_rsa_oaep_md(hash.md()).map_err(rsa_error)?;
+            ctx.set_rsa_mgf1_md(hash.md()).map_err(rsa_error)?;
+
+            let mut plaintext = Vec::new();
+            ctx.decrypt_to_vec(ciphertext, &mut plaintext)
+                .map_err(|e| CryptoError::DecryptionFailed(e.to_string()))?;
+            Ok(plaintext)
+        }
+
+        /// RSA-PSS 签名，盐长度等于哈希长度
+        pub fn sign_pss(&self, message: &[u8], hash: RsaHash) -> Result<Vec<u8>, CryptoError> {
+            Signer::new(hash.message_digest(), &self.pkey)
+                .and_then(|mut signer| {
+                    signer.set_rsa_padding(Padding::PKCS1_PSS)?;
+                    signer.set_rsa_pss_saltlen(RsaPssSaltlen::DIGEST_LENGTH)?;
+                    signer.set_rsa_mgf1_md(hash.message_digest())?;
+                    signer.update(message)?;
+                    signer.sign_to_vec()
+                })
+                .map_err(|e| CryptoError::SignatureFailed(e.to_string()))
+        }
+
+        /// RSASSA-PKCS1-v1_5 签名（JWT 的 RS256 等算法使用）
+        pub fn sign_pkcs1v15(&self, message: &[u8], hash: RsaHash) -> Result<Vec<u8>, CryptoError> {
+            Signer::new(hash.message_digest(), &self.pkey)
+                .and_then(|mut signer| {
+                    signer.set_rsa_padding(Padding::PKCS1)?;
+                    signer.update(message)?;
+                    signer.sign_to_vec()
+                })
+                .map_err(|e| CryptoError::SignatureFailed(e.to_string()))
+        }
+    }
+
+    /// RSA 公钥
+    pub struct RsaPublicKey {
+        pkey: PKey<Public>,
+    }
+
+    impl RsaPublicKey {
+        /// 从 PEM 导入，支持 SubjectPublicKeyInfo 和 PKCS#1（BEGIN RSA PUBLIC KEY）
+        pub fn from_pem(pem: &[u8]) -> Result<Self, CryptoError> {
+            let pkey = PKey::public_key_from_pem(pem)
+                .or_else(|_| Rsa::public_key_from_pem_pkcs1(pem).and_then(PKey::from_rsa))
+                .map_err(|e| CryptoError::InvalidKey(e.to_string()))?;
+            check_rsa_key(&pkey)?;
+            Ok(RsaPublicKey { pkey })
+        }
+
+        /// 从 DER 导入，支持 SubjectPublicKeyInfo 和 PKCS#1
+        pub fn from_der(der: &[u8]) -> Result<Self, CryptoError> {
+            let pkey = PKey::public_key_from_der(der)
+                .or_else(|_| Rsa::public_key_from_der_pkcs1(der).and_then(PKey::from_rsa))
+                .map_err(|e| CryptoError::InvalidKey(e.to_string()))?;
+            check_rsa_key(&pkey)?;
+            Ok(RsaPublicKey { pkey })
+        }
+
+        pub fn to_pem(&self) -> Result<Vec<u8>, CryptoError> {
+            self.pkey
+                .public_key_to_pem()
+                .map_err(|e| CryptoError::InvalidKey(e.to_string()))
+        }
+
+        pub fn to_der(&self) -> Result<Vec<u8>, CryptoError> {
+            self.pkey
+                .public_key_to_der()
+                .map_err(|e| CryptoError::InvalidKey(e.to_string()))
+        }
+
+        pub fn bits(&self) -> u32 {
+            self.pkey.bits()
+        }
+
+        /// OAEP 单次可加密的最大明文长度：k - 2*hLen - 2
+        pub fn max_oaep_plaintext_len(&self, hash: RsaHash) -> usize {
+            let digest_len = hash.message_digest().size();
+            self.pkey.size().saturating_sub(2 * digest_len + 2)
+        }
+
+        /// RSA-OAEP 加密，适合包装会话密钥等短数据
+        pub fn encrypt_oaep(
+            &self,
+            plaintext: &[u8],
+            hash: RsaHash,
+        ) -> Result<Vec<u8>, CryptoError> {
+            let max = self.max_oaep_plaintext_len(hash);
+            if plaintext.len() > max {
+                return Err(CryptoError::RsaPlaintextTooLong {
+                    max,
+                    actual: plaintext.len(),
+                });
+            }
+
+            let mut ctx = PkeyCtx::new(&self.pkey).map_err(rsa_error)?;
+            ctx.encrypt_init().map_err(rsa_error)?;
+            ctx.set_rsa_padding(Padding::PKCS1_OAEP)
+                .map_err(rsa_error)?;
+            ctx.set_rsa_oaep_md(hash.md()).map_err(rsa_error)?;
+            ctx.set_rsa_mgf1_md(hash.md()).map_err(rsa_error)?;
+
+            let mut ciphertext = Vec::new();
+            ctx.encrypt_to_vec(plaintext, &mut ciphertext)
+                .map_err(|e| CryptoError::EncryptionFailed(e.to_string()))?;
+            Ok(ciphertext)
+        }
+
+        /// RSA-PSS 验签，签名不匹配时返回 `SignatureVerificationFailed`
+        pub fn verify_pss(
+            &self,
+            message: &[u8],
+            signature: &[u8],
+            hash: RsaHash,
+        ) -> Result<(), CryptoError> {
+            let result =
+                Verifier::new(hash.message_digest(), &self.pkey).and_then(|mut verifier| {
+                    verifier.set_rsa_padding(Padding::PKCS1_PSS)?;
+                    verifier.set_rsa_pss_saltlen(RsaPssSaltlen::DIGEST_LENGTH)?;
+                    verifier.set_rsa_mgf1_md(hash.message_digest())?;
+                    verifier.update(message)?;
+                    verifier.verify(signature)
+                });
+            match result {
+                Ok(true) => Ok(()),
+                Ok(false) | Err(_) => Err(CryptoError::SignatureVerificationFailed),
+            }
+        }
+
+        /// 验证 RSASSA-PKCS1-v1_5 签名
+        pub fn verify_pkcs1v15(
+            &self,
+            message: &[u8],
+            signature: &[u8],
+            hash: RsaHash,
+        ) -> Result<(), CryptoError> {
+            let result =
+                Verifier::new(hash.message_digest(), &self.pkey).and_then(|mut verifier| {
+                    verifier.set_rsa_padding(Padding::PKCS1)?;
+                    verifier.update(message)?;
+                    verifier.verify(signature)
+                });
+            match result {
+                Ok(true) => Ok(()),
+                Ok(false) | Err(_) => Err(CryptoError::SignatureVerificationFailed),
+            }
+        }
+    }
+
+    // 加密密钥库：按名称和版本管理多把 AES 密钥，整个文件由主密钥或口令加密
+    // 文件格式：魔数 "FNKS" | 版本(1) | 保护方式(1) | 加密后的 JSON
+    // 密文格式：版本(1) | 名称长度(1) | 名称 | 密钥版本(u32, 大端) | GCM密文
+    // GCM 以密文头部（含密钥 ID）为附加认证数据，改写密钥 ID 会导致解密失败
+    const KEYSTORE_MAGIC: &[u8; 4] = b"FNKS";
+    const KEYSTORE_FILE_VERSION: u8 = 1;
+    const KEYSTORE_CIPHERTEXT_VERSION: u8 = 2;
+    const KEYSTORE_MAX_NAME_LEN: usize = 255;
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+    #[serde(rename_all = "snake_case")]
+    pub enum KeyStatus {
+        /// 用于加密和解密
+        Active,
+        /// 已被轮换，只能解密旧数据
+        DecryptOnly,
+        /// 已吊销，不能再使用
+        Revoked,
+    }
+
+    /// 密钥标识：名称 + 版本，显示为 `name/v3`
+    #[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+    pub struct KeyId {
+        pub name: String,
+        pub version: u32,
+    }
+
+    impl std::fmt::Display for KeyId {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            write!(f, "{}/v{}", self.name, self.version)
+        }
+    }
+
+    /// 供列表展示的密钥信息，不包含密钥材料
+    #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+    pub struct KeyInfo {
+        pub id: KeyId,
+        pub status: KeyStatus,
+        /// 创建时间（Unix 秒）
+        pub created_at: u64,
+        pub key_len: usize,
+    }
+
+    struct KeyEntry {
+        info: KeyInfo,
+        key: SecretKey,
+    }
+
+    // 序列化到文件的结构，密钥以 Base64 保存，用完即清零
+    #[derive(Serialize, Deserialize)]
+    struct StoredKey {
+        name: String,
+        version: u32,
+        status: KeyStatus,
+        created_at: u64,
+        key: String,
+    }
+
+    impl Drop for StoredKey {
+        fn drop(&mut self) {
+            self.key.zeroize();
+        }
+    }
+
+    enum KeystoreProtection {
+        MasterKey(SecretKey),
+        Password {
+            password: Zeroizing<Vec<u8>>,
+            params: KdfParams,
+        },
+    }
+
+    impl KeystoreProtection {
+        fn id(&self) -> u8 {
+            match self {
+                KeystoreProtection::MasterKey(_) => 0,
+                KeystoreProtection::Password { .. } => 1,
+            }
+        }
+    }
+
+    pub struct Keystore {
+        protection: KeystoreProtection,
+        entries: Vec<KeyEntry>,
+    }
+
+    impl std::fmt::Debug for Keystore {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            f.debug_struct("Keystore")
+                .field("keys", &self.list())
+                .finish_non_exhaustive()
+        }
+    }
+
+    fn unix_now() -> u64 {
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0)
+    }
+
+    impl Keystore {
+        /// 创建由主密钥保护的空密钥库
+        pub fn with_master_key(master_key: &[u8]) -> Result<Self, CryptoError> {
+            select_cipher(master_key)?;
+            Ok(Keystore {
+                protection: KeystoreProtection::MasterKey(SecretKey::from_slice(master_key)),
+                entries: Vec::new(),
+            })
+        }
+
+        /// 创建由口令保护的空密钥库，每次保存都会重新生成盐
+        pub fn with_password(password: &[u8], params: KdfParams) -> Result<Self, CryptoError> {
+            params.validate()?;
+            Ok(Keystore {
+                protection: KeystoreProtection::Password {
+                    password: Zeroizing::new(password.to_vec()),
+                    params,
+                },
+                entries: Vec::new(),
+            })
+        }
+
+        pub fn open_with_master_key<P: AsRef<Path>>(
+            path: P,
+            master_key: &[u8],
+        ) -> Result<Self, CryptoError> {
+            let data = std::fs::read(path).map_err(|e| CryptoError::IoError(e.to_string()))?;
+            let payload = Self::check_header(&data, 0)?;
+            let json = Zeroizing::new(decrypt(master_key, payload)?);
+            let mut keystore = Self::with_master_key(master_key)?;
+            keystore.load_entries(&json)?;
+            Ok(keystore)
+        }
+
+        pub fn open_with_password<P: AsRef<Path>>(
+            path: P,
+            password: &[u8],
+        ) -> Result<Self, CryptoError> {
+            let data = std::fs::read(path).map_err(|e| CryptoError::IoError(e.to_string()))?;
+            let payload = Self::check_header(&data, 1)?;
+            let params = read_password_params(payload)?;
+            let json = Zeroizing::new(decrypt_with_password(password, payload)?);
+            let mut keystore = Self::with_password(password, params)?;
+            keystore.load_entries(&json)?;
+            Ok(keystore)
+        }
+
+        fn check_header(data: &[u8], protection: u8) -> Result<&[u8], CryptoError> {
+            let header_len = KEYSTORE_MAGIC.len() + 2;
+            if data.len() < header_len
+                || &data[..KEYSTORE_MAGIC.len()] != KEYSTORE_MAGIC
+                || data[KEYSTORE_MAGIC.len()] != KEYSTORE_FILE_VERSION
+            {
+                return Err(CryptoError::InvalidKeystore("文件头无效".into()));
+            }
+            if data[KEYSTORE_MAGIC.len() + 1] != protection {
+                return Err(CryptoError::InvalidKeystore(
+                    "保护方式不匹配（主密钥 / 口令）".into(),
+                ));
+            }
+            Ok(&data[header_len..])
+        }
+
+        fn load_entries(&mut self, json: &[u8]) -> Result<(), CryptoError> {
+            let stored: Vec<StoredKey> = serde_json::from_slice(json)
+                .map_err(|e| CryptoError::InvalidKeystore(e.to_string()))?;
+            for item in &stored {
+                let id = KeyId {
+                    name: item.name.clone(),
+                    version: item.version,
+                };
+                if self.entries.iter().any(|e| e.info.id == id) {
+                    return Err(CryptoError::InvalidKeystore(format!(
+                        "重复的密钥 ID: {}",
+                        id
+                    )));
+                }
+                let key = SecretKey::from_base64(&item.key)?;
+                select_cipher(key.expose_secret())?;
+                self.entries.push(KeyEntry {
+                    info: KeyInfo {
+                        id,
+                        status: item.status,
+                        created_at: item.created_at,
+                        key_len: key.len(),
+                    },
+                    key,
+                });
+            }
+            Ok(())
+        }
+
+        /// 加密并写入文件，先写临时文件再替换，避免写到一半损坏原文件
+        pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), CryptoError> {
+            let stored: Vec<StoredKey> = self
+                .entries
+                .iter()
+                .map(|entry| StoredKey {
+                    name: entry.info.id.name.clone(),
+                    version: entry.info.id.version,
+                    status: entry.info.status,
+                    created_at: entry.info.created_at,
+                    key: base64_encode(entry.key.expose_secret()),
+                })
+                .collect();
+            let json = Zeroizing::new(
+                serde_json::to_vec(&stored)
+                    .map_err(|e| CryptoError::InvalidKeystore(e.to_string()))?,
+            );
+
+            let payload = match &self.protection {
+                KeystoreProtection::MasterKey(key) => encrypt(key.expose_secret(), &json)?,
+                KeystoreProtection::Password { password, params } => {
+                    encrypt_with_password(password, &json, params)?
+                }
+            };
+
+            let mut data = Vec::with_capacity(KEYSTORE_MAGIC.len() + 2 + payload.len());
+            data.extend(KEYSTORE_MAGIC);
+            data.push(KEYSTORE_FILE_VERSION);
+            data.push(self.protection.id());
+            data.extend(payload);
+
+            let path = path.as_ref();
+            let mut tmp = path.as_os_str().to_owned();
+            tmp.push(".tmp");
+            std::fs::write(&tmp, &data).map_err(|e| CryptoError::IoError(e.to_string()))?;
+            std::fs::rename(&tmp, path).map_err(|e| CryptoError::IoError(e.to_string()))
+        }
+
+        fn check_name(name: &str) -> Result<(), CryptoError> {
+            if name.is_empty() || name.len() > KEYSTORE_MAX_NAME_LEN {
+                return Err(CryptoError::InvalidKeystore(format!(
+                    "密钥名称长度必须在 1 到 {} 字节之间",
+                    KEYSTORE_MAX_NAME_LEN
+                )));
+            }
+            Ok(())
+        }
+
+        fn push_key(&mut self, name: &str, version: u32, key: SecretKey) -> KeyId {
+            let id = KeyId {
+                name: name.to_string(),
+                version,
+            };
+            self.entries.push(KeyEntry {
+                info: KeyInfo {
+                    id: id.clone(),
+                    status: KeyStatus::Active,
+                    created_at: unix_now(),
+                    key_len: key.len(),
+                },
+                key,
+            });
+            id
+        }
+
+        /// 生成新的命名密钥，版本从 1 开始
+        pub fn add_key(&mut self, name: &str, key_len: usize) -> Result<KeyId, CryptoError> {
+            let key = SecretKey::generate(key_len)
+                .map_err(|_| CryptoError::UnsupportedKeyLength { actual: key_len })?;
+            self.import_key(name, key.expose_secret())
+        }
+
+        /// 导入已有密钥（例如配置文件中 `generate_key_base64` 生成的字符串）
+        pub fn import_key(&mut self, name: &str, key: &[u8]) -> Result<KeyId, CryptoError> {
+            Self::check_name(name)?;
+            select_cipher(key)?;
+            if self.entries.iter().any(|e| e.info.id.name == name) {
+                return Err(CryptoError::KeyAlreadyExists(name.to_string()));
+            }
+            Ok(self.push_key(name, 1, SecretKey::from_slice(key)))
+        }
+
+        /// 轮换密钥：生成同长度的新版本，原活动版本改为仅解密
+        pub fn rotate_key(&mut self, name: &str) -> Result<KeyId, CryptoError> {
+            let (key_len, latest) = {
+                let versions: Vec<&KeyInfo> = self
+                    .entries
+                    .iter()
+                    .map(|e| &e.info)
+                    .filter(|info| info.id.name == name)
+                    .collect();
+                let latest = versions
+                    .iter()
+                    .map(|info| info.id.version)
+                    .max()
+                    .ok_or_else(|| CryptoError::KeyNotFound(name.to_string()))?;
+                let key_len = versions
+                    .iter()
+                    .find(|info| info.status == KeyStatus::Active)
+                    .or_else(|| versions.iter().find(|info| info.id.version == latest))
+                    .map(|info| info.key_len)
+                    .unwrap_or(AES_256_KEY_LEN);
+                (key_len, latest)
+            };
+
+            let key = SecretKey::generate(key_len)
+                .map_err(|e| CryptoError::KeyGenerationFailed(e.to_string()))?;
+            for entry in self.entries.iter_mut() {
+                if entry.info.id.name == name && entry.info.status == KeyStatus::Active {
+                    entry.info.status = KeyStatus::DecryptOnly;
+                }
+            }
+            Ok(self.push_key(name, latest + 1, key))
+        }
+
+        /// 吊销指定版本，吊销后该版本的密文无法再解密
+        pub fn revoke_key(&mut self, id: &KeyId) -> Result<(), CryptoError> {
+            let entry = self
+                .entries
+                .iter_mut()
+                .find(|e| e.info.id == *id)
+                .ok_or_else(|| CryptoError::KeyNotFound(id.to_string()))?;
+            entry.info.status = KeyStatus::Revoked;
+            Ok(())
+        }
+
+        /// 列出所有密钥（按名称、版本排序）
+        pub fn list(&self) -> Vec<KeyInfo> {
+            let mut infos: Vec<KeyInfo> = self.entries.iter().map(|e| e.info.clone()).collect();
+            infos.sort_by(|a, b| {
+                a.id.name
+                    .cmp(&b.id.name)
+                    .then(a.id.version.cmp(&b.id.version))
+            });
+            infos
+        }
+
+        /// 取得某名称当前的活动密钥
+        pub fn active_key(&self, name: &str) -> Result<(&KeyId, &SecretKey), CryptoError> {
+            self.entries
+                .iter()
+                .find(|e| e.info.id.name == name && e.info.status == KeyStatus::Active)
+                .map(|e| (&e.info.id, &e.key))
+                .ok_or_else(|| CryptoError::KeyNotFound(name.to_string()))
+        }
+
+        /// 用活动密钥加密，输出带有密钥 ID
+        pub fn encrypt(&self, name: &str, plaintext: &[u8]) -> Result<Vec<u8>, CryptoError> {
+            let (id, key) = self.active_key(name)?;
+
+            let mut result = Vec::with_capacity(2 + id.name.len() + 4 + plaintext.len() + 28);
+            result.push(KEYSTORE_CIPHERTEXT_VERSION);
+            result.push(id.name.len() as u8);
+            result.extend(id.name.as_bytes());
+            result.extend(id.version.to_be_bytes());
+            let body = gcm_encrypt(
+                key.expose_secret(),
+                plaintext,
+                &result,
+                &EncryptionOptions::default(),
+            )?;
+            result.extend(body);
+            Ok(result)
+        }
+
+        /// 读取密文中的密钥 ID，不做解密
+        pub fn ciphertext_key_id(data: &[u8]) -> Result<KeyId, CryptoError> {
+            Self::split_ciphertext(data).map(|(id, _)| id)
+        }
+
+        fn split_ciphertext(data: &[u8]) -> Result<(KeyId, &[u8]), CryptoError> {
+            if data.len() < 2 || data[0] != KEYSTORE_CIPHERTEXT_VERSION {
+                return Err(CryptoError::InvalidCiphertextFormat);
+            }
+            let name_end = 2 + data[1] as usize;
+            let body_start = name_end + 4;
+            if data.len() < body_start {
+                return Err(CryptoError::InvalidCiphertextLength {
+                    min_length: body_start,
+                    actual: data.len(),
+                });
+            }
+            let name = std::str::from_utf8(&data[2..name_end])
+                .map_err(|_| CryptoError::InvalidCiphertextFormat)?;
+            let mut version = [0u8; 4];
+            version.copy_from_slice(&data[name_end..body_start]);
+            let id = KeyId {
+                name: name.to_string(),
+                version: u32::from_be_bytes(version),
+            };
+            Ok((id, &data[body_start..]))
+        }
+
+        /// 按密文中的密钥 ID 选择密钥解密，活动和仅解密状态的密钥均可使用
+        pub fn decrypt(&self, data: &[u8]) -> Result<Vec<u8>, CryptoError> {
+            let (id, body) = Self::split_ciphertext(data)?;
+            let entry = self
+                .entries
+                .iter()
+                .find(|e| e.info.id == id)
+                .ok_or_else(|| CryptoError::KeyNotFound(id.to_string()))?;
+            if entry.info.status == KeyStatus::Revoked {
+                return Err(CryptoError::KeyRevoked(id.to_string()));
+            }
+            let header = &data[..data.len() - body.len()];
+            gcm_decrypt(
+                entry.key.expose_secret(),
+                body,
+                header,
+                &DecryptionOptions::default(),
+            )
+        }
+
+        pub fn encrypt_to_base64(
+            &self,
+            name: &str,
+            plaintext: &[u8],
+        ) -> Result<String, CryptoError> {
+            self.encrypt(name, plaintext)
+                .map(|data| base64_encode(&data))
+        }
+
+        pub fn decrypt_from_base64(&self, ciphertext_base64: &str) -> Result<Vec<u8>, CryptoError> {
+            self.decrypt(&base64_decode(ciphertext_base64)?)
+        }
+    }
+
+    // JWT / JWS 紧凑序列化（RFC 7515 / 7519）
+    // 验证时由调用方指定期望的算法，头部中的 alg 必须与之一致，避免算法混淆攻击
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum JwsAlgorithm {
+        HS256,
+        HS384,
+        HS512,
+        RS256,
+        ES256,
+        EdDSA,
+    }
+
+    impl JwsAlgorithm {
+        pub fn name(self) -> &'static str {
+            match self {
+                JwsAlgorithm::HS256 => "HS256",
+                JwsAlgorithm::HS384 => "HS384",
+                JwsAlgorithm::HS512 => "HS512",
+                JwsAlgorithm::RS256 => "RS256",
+                JwsAlgorithm::ES256 => "ES256",
+                JwsAlgorithm::EdDSA => "EdDSA",
+            }
+        }
+
+        fn hmac_hash(self) -> Option<HashAlgorithm> {
+            match self {
+                JwsAlgorithm::HS256 => Some(HashAlgorithm::Sha256),
+                JwsAlgorithm::HS384 => Some(HashAlgorithm::Sha384),
+                JwsAlgorithm::HS512 => Some(HashAlgorithm::Sha512),
+                _ => None,
+            }
+        }
+
+        fn signature_algorithm(self) -> Option<SignatureAlgorithm> {
+            match self {
+                JwsAlgorithm::ES256 => Some(SignatureAlgorithm::EcdsaP256),
+                JwsAlgorithm::EdDSA => Some(SignatureAlgorithm::Ed25519),
+                _ => None,
+            }
+        }
+    }
+
+    /// 签发 JWT 使用的密钥
+    pub enum JwtSigner<'a> {
+        /// HS256 / HS384 / HS512 的共享密钥
+        Hmac(&'a [u8]),
+        /// RS256
+        Rsa(&'a RsaPrivateKey),
+        /// ES256（P-256）或 EdDSA（Ed25519）
+        Key(&'a SigningKey),
+    }
+
+    /// 验证 JWT 使用的密钥
+    pub enum JwtVerifier<'a> {
+        Hmac(&'a [u8]),
+        Rsa(&'a RsaPublicKey),
+        Key(&'a VerifyingKey),
+    }
+
+    /// 声明校验选项
+    #[derive(Debug, Clone)]
+    pub struct JwtValidation {
+        /// 时间类声明允许的时钟偏差（秒）
+        pub leeway: u64,
+        /// 期望的 aud，设置后令牌必须包含该值
+        pub audience: Option<String>,
+        /// 期望的 iss，设置后必须完全相等
+        pub issuer: Option<String>,
+        /// 是否要求存在 exp
+        pub require_exp: bool,
+        /// 校验使用的当前时间（Unix 秒），为空时取系统时间
+        pub now: Option<u64>,
+    }
+
+    impl Default for JwtValidation {
+        fn default() -> Self {
+            Self {
+                leeway: 60,
+                audience: None,
+                issuer: None,
+                require_exp: true,
+                now: None,
+            }
+        }
+    }
+
+    fn jwt_sign_input(
+        alg: JwsAlgorithm,
+        signer: &JwtSigner,
+        input: &[u8],
+    ) -> Result<Vec<u8>, CryptoError> {
+        match (signer, alg.hmac_hash(), alg.signature_algorithm()) {
+            (JwtSigner::Hmac(key), Some(hash), _) => {
+                if key.is_empty() {
+                    return Err(CryptoError::InvalidKey("HMAC 密钥不能为空".into()));
+                }
+                hmac(hash, key, input).map(|d| d.into_bytes())
+            }
+            (JwtSigner::Rsa(key), _, _) if alg == JwsAlgorithm::RS256 => {
+                key.sign_pkcs1v15(input, RsaHash::Sha256)
+            }
+            (JwtSigner::Key(key), _, Some(expected)) if key.algorithm() == expected => {
+                let signature = key.sign(input)?;
+                if expected == SignatureAlgorithm::EcdsaP256 {
+                    // JWS 使用 r || s 定长编码而不是 DER
+                    EcdsaSig::from_der(&signature)
+                        .and_then(|sig| {
+                            let mut raw = sig.r().to_vec_padded(32)?;
+                            raw.extend(sig.s().to_vec_padded(32)?);
+                            Ok(raw)
+                        })
+                        .map_err(|e| CryptoError::SignatureFailed(e.to_string()))
+                } else {
+                    Ok(signature)
+                }
+            }
+            _ => Err(CryptoError::InvalidKey(format!(
+                "密钥类型与算法 {} 不匹配",
+                alg.name()
+            ))),
+        }
+    }
+
+    fn jwt_verify_input(
+        alg: JwsAlgorithm,
+        verifier: &JwtVerifier,
+        input: &[u8],
+        signature: &[u8],
+    ) -> Result<(), CryptoError> {
+        match (verifier, alg.hmac_hash(), alg.signature_algorithm()) {
+            (JwtVerifier::Hmac(key), Some(hash), _) => hmac_verify(hash, key, input, signature)
+                .map_err(|_| CryptoError::SignatureVerificationFailed),
+            (JwtVerifier::Rsa(key), _, _) if alg == JwsAlgorithm::RS256 => {
+                key.verify_pkcs1v15(input, signature, RsaHash::Sha256)
+            }
+            (JwtVerifier::Key(key), _, Some(expected)) if key.algorithm() == expected => {
+                if expected == SignatureAlgorithm::EcdsaP256 {
+                    if signature.len() != 64 {
+                        return Err(CryptoError::SignatureVerificationFailed);
+                    }
+                    let der = BigNum::from_slice(&signature[..32])
+                        .and_then(|r| {
+                            let s = BigNum::from_slice(&signature[32..])?;
+                            EcdsaSig::from_private_components(r, s)?.to_der()
+                        })
+                        .map_err(|_| CryptoError::SignatureVerificationFailed)?;
+                    key.verify(input, &der)
+                } else {
+                    key.verify(input, signature)
+                }
+            }
+            _ => Err(CryptoError::InvalidKey(format!(
+                "密钥类型与算法 {} 不匹配",
+                alg.name()
+            ))),
+        }
+    }
+
+    /// 签发 JWT，claims 可以是任意可序列化为 JSON 对象的值
+    pub fn jwt_sign<T: Serialize>(
+        alg: JwsAlgorithm,
+        signer: &JwtSigner,
+        claims: &T,
+    ) -> Result<String, CryptoError> {
+        let claims =
+            serde_json::to_value(claims).map_err(|e| CryptoError::InvalidToken(e.to_string()))?;
+        if !claims.is_object() {
+            return Err(CryptoError::InvalidToken("claims 必须是 JSON 对象".into()));
+        }
+        let header = serde_json::json!({ "alg": alg.name(), "typ": "JWT" });
+
+        let mut token = encode(Encoding::Base64UrlNoPad, header.to_string().as_bytes());
+        token.push('.');
+        token.push_str(&encode(
+            Encoding::Base64UrlNoPad,
+            claims.to_string().as_bytes(),
+        ));
+        let signature = jwt_sign_input(alg, signer, token.as_bytes())?;
+        token.push('.');
+        token.push_str(&encode(Encoding::Base64UrlNoPad, &signature));
+        Ok(token)
+    }
+
+    // 拆分紧凑序列化并解析头部和声明
+    fn jwt_parts(
+        token: &str,
+    ) -> Result<(&str, serde_json::Value, serde_json::Value, Vec<u8>), CryptoError> {
+        let parts: Vec<&str> = token.trim().split('.').collect();
+        if parts.len() != 3 {
+            return Err(CryptoError::InvalidToken("必须由三段组成".into()));
+        }
+        let parse = |part: &str| -> Result<serde_json::Value, CryptoError> {
+            let bytes = decode(Encoding::Base64UrlNoPad, part)
+                .map_err(|e| CryptoError::InvalidToken(e.to_string()))?;
+            serde_json::from_slice(&bytes).map_err(|e| CryptoError::InvalidToken(e.to_string()))
+        };
+        let header = parse(parts[0])?;
+        let claims = parse(parts[1])?;
+        if !header.is_object() || !claims.is_object() {
+            return Err(CryptoError::InvalidToken(
+                "头部和 claims 必须是 JSON 对象".into(),
+            ));
+        }
+        let signature = decode(Encoding::Base64UrlNoPad, parts[2])
+            .map_err(|e| CryptoError::InvalidToken(e.to_string()))?;
+        let signed_len = parts[0].len() + 1 + parts[1].len();
+        Ok((&token.trim()[..signed_len], header, claims, signature))
+    }
+
+    /// 不验证签名，只解析头部和声明（例如根据 kid 选择密钥），结果不可信
+    pub fn jwt_decode_unverified(
+        token: &str,
+    ) -> Result<(serde_json::Value, serde_json::Value), CryptoError> {
+        jwt_parts(token).map(|(_, header, claims, _)| (header, claims))
+    }
+
+    fn jwt_time_claim(claims: &serde_json::Value, name: &str) -> Result<Option<u64>, CryptoError> {
+        match claims.get(name) {
+            None => Ok(None),
+            Some(value) => value
+                .as_u64()
+                .or_else(|| value.as_f64().filter(|v| *v >= 0.0).map(|v| v as u64))
+                .map(Some)
+                .ok_or_else(|| CryptoError::InvalidClaim(format!("{} 必须是数字时间戳", name))),
+        }
+    }
+
+    fn jwt_check_claims(
+        claims: &serde_json::Value,
+        validation: &JwtValidation,
+    ) -> Result<(), CryptoError> {
+        let now = validation.now.unwrap_or_else(unix_now);
+
+        match jwt_time_claim(claims, "exp")? {
+            Some(exp) if now >= exp.saturating_add(validation.leeway) => {
+                return Err(CryptoError::TokenExpired);
+            }
+            None if validation.require_exp => {
+                return Err(CryptoError::InvalidClaim("缺少 exp".into()));
+            }
+            _ => {}
+        }
+        if let Some(nbf) = jwt_time_claim(claims, "nbf")?
+            && now.saturating_add(validation.leeway) < nbf
+        {
+            return Err(CryptoError::TokenNotYetValid);
+        }
+        if let Some(iat) = jwt_time_claim(claims, "iat")?
+            && iat > now.saturating_add(validation.leeway)
+        {
+            return Err(CryptoError::InvalidClaim("iat 晚于当前时间".into()));
+        }
+
+        if let Some(expected) = &validation.issuer
+            && claims.get("iss").and_then(|v| v.as_str()) != Some(expected.as_str())
+        {
+            return Err(CryptoError::InvalidClaim("iss 不匹配".into()));
+        }
+        if let Some(expected) = &validation.audience {
+            // aud 可以是单个字符串或字符串数组
+            let matched = match claims.get("aud") {
+                Some(serde_json::Value::String(aud)) => aud == expected,
+                Some(serde_json::Value::Array(list)) => {
+                    list.iter().any(|v| v.as_str() == Some(expected.as_str()))
+                }
+                _ => false,
+            };
+            if !matched {
+                return Err(CryptoError::InvalidClaim("aud 不匹配".into()));
+            }
+        }
+        Ok(())
+    }
+
+    /// 验证签名和声明，返回 claims
+    pub fn jwt_verify(
+        token: &str,
+        alg: JwsAlgorithm,
+        verifier: &JwtVerifier,
+        validation: &JwtValidation,
+    ) -> Result<serde_json::Value, CryptoError> {
+        let (signed, header, claims, signature) = jwt_parts(token)?;
+        if header.get("alg").and_then(|v| v.as_str()) != Some(alg.name()) {
+            return Err(CryptoError::InvalidToken(format!(
+                "alg 必须为 {}",
+                alg.name()
+            )));
+        }
+        jwt_verify_input(alg, verifier, signed.as_bytes(), &signature)?;
+        jwt_check_claims(&claims, validation)?;
+        Ok(claims)
+    }
+
+    /// 验证后把 claims 反序列化为指定类型
+    pub fn jwt_verify_as<T: serde::de::DeserializeOwned>(
+        token: &str,
+        alg: JwsAlgorithm,
+        verifier: &JwtVerifier,
+        validation: &JwtValidation,
+    ) -> Result<T, CryptoError> {
+        let claims = jwt_verify(token, alg, verifier, validation)?;
+        serde_json::from_value(claims).map_err(|e| CryptoError::InvalidClaim(e.to_string()))
+    }
+
+    // X.509 证书与 CSR
+    /// 主题备用名称
+    #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+    #[serde(tag = "type", content = "value", rename_all = "snake_case")]
+    pub enum SubjectAltName {
+        Dns(String),
+        Ip(std::net::IpAddr),
+        Email(String),
+        Uri(String),
+    }
+
+    /// 证书的可读信息，可直接序列化为 JSON
+    #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+    pub struct CertificateInfo {
+        /// 形如 `CN=device-1, O=FirmNetter`
+        pub subject: String,
+        pub issuer: String,
+        pub subject_alt_names: Vec<SubjectAltName>,
+        /// 有效期起止时间（Unix 秒）
+        pub not_before: i64,
+        pub not_after: i64,
+        /// 序列号，大写十六进制
+        pub serial_number: String,
+        /// 整张证书 DER 的 SHA-256，小写十六进制
+        pub fingerprint_sha256: String,
+        /// SubjectPublicKeyInfo 的 SHA-256，小写十六进制（密钥不变时证书续期后保持不变）
+        pub spki_sha256: String,
+    }
+
+    /// 证书固定（pinning）：匹配证书指纹或公钥指纹
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    pub enum CertificatePin {
+        CertificateSha256([u8; 32]),
+        SpkiSha256([u8; 32]),
+    }
+
+    impl CertificatePin {
+        /// 解析 `sha256/<Base64>`（HPKP 风格的公钥指纹）或 64 位十六进制证书指纹（可带冒号）
+        pub fn parse(pin: &str) -> Result<Self, CryptoError> {
+            let pin = pin.trim();
+            let (bytes, spki) = match pin.strip_prefix("sha256/") {
+                Some(encoded) => (decode(Encoding::Base64, encoded)?, true),
+                None => (hex_decode(&pin.replace(':', ""))?, false),
+            };
+            let digest: [u8; 32] = bytes.try_into().map_err(|_| {
+                CryptoError::CertificateError("指纹必须为 32 字节的 SHA-256".into())
+            })?;
+            Ok(if spki {
+                CertificatePin::SpkiSha256(digest)
+            } else {
+                CertificatePin::CertificateSha256(digest)
+            })
+        }
+
+        pub fn matches(&self, certificate: &Certificate) -> bool {
+            let actual = match self {
+                CertificatePin::CertificateSha256(_) => certificate.fingerprint_sha256(),
+                CertificatePin::SpkiSha256(_) => certificate.spki_sha256(),
+            };
+            let expected = match self {
+                CertificatePin::CertificateSha256(d) | CertificatePin::SpkiSha256(d) => d,
+            };
+            actual.is_ok_and(|a| memcmp::eq(&a, expected))
+        }
+    }
+
+    fn x509_error(err: ErrorStack) -> CryptoError {
+        CryptoError::CertificateError(err.to_string())
+    }
+
+    fn asn1_time_to_unix(time: &Asn1TimeRef) -> Result<i64, CryptoError> {
+        let epoch = Asn1Time::from_unix(0).map_err(x509_error)?;
+        let diff = epoch.diff(time).map_err(x509_error)?;
+        Ok(diff.days as i64 * 86400 + diff.secs as i64)
+    }
+
+    fn x509_name_to_string(name: &X509NameRef) -> String {
+        name.entries()
+            .map(|entry| {
+                let key = entry.object().nid().short_name().unwrap_or("?");
+                let value = entry
+                    .data()
+                    .as_utf8()
+                    .map(|s| s.to_string())
+                    .unwrap_or_default();
+                format!("{}={}", key, value)
+            })
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    /// X.509 证书
+    #[derive(Clone)]
+    pub struct Certificate {
+        x509: X509,
+    }
+
+    impl std::fmt::Debug for Certificate {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            f.debug_struct("Certificate")
+                .field("subject", &self.subject())
+                .field("issuer", &self.issuer())
+                .finish()
+        }
+    }
+
+    impl Certificate {
+        pub fn from_pem(pem: &[u8]) -> Result<Self, CryptoError> {
+            X509::from_pem(pem)
+                .map(|x509| Certificate { x509 })
+                .map_err(x509_error)
+        }
+
+        pub fn from_der(der: &[u8]) -> Result<Self, CryptoError> {
+            X509::from_der(der)
+                .map(|x509| Certificate { x509 })
+                .map_err(x509_error)
+        }
+
+        /// 读取 PEM 证书包（例如 CA bundle）中的全部证书
+        pub fn bundle_from_pem(pem: &[u8]) -> Result<Vec<Self>, CryptoError> {
+            let certs = X509::stack_from_pem(pem).map_err(x509_error)?;
+            if certs.is_empty() {
+                return Err(CryptoError::CertificateError("未找到证书".into()));
+            }
+            Ok(certs.into_iter().map(|x509| Certificate { x509 }).collect())
+        }
+
+        /// 自动识别 PEM 或 DER
+        pub fn from_bytes(data: &[u8]) -> Result<Self, CryptoError> {
+            if data.starts_with(b"-----") || data.trim_ascii_start().starts_with(b"-----") {
+                Self::from_pem(data)
+            } else {
+                Self::from_der(data)
+            }
+        }
+
+        pub fn to_pem(&self) -> Result<Vec<u8>, CryptoError> {
+            self.x509.to_pem().map_err(x509_error)
+        }
+
+        pub fn to_der(&self) -> Result<Vec<u8>, CryptoError> {
+            self.x509.to_der().map_err(x509_error)
+        }
+
+        pub fn subject(&self) -> String {
+            x509_name_to_string(self.x509.subject_name())
+        }
+
+        pub fn issuer(&self) -> String {
+            x509_name_to_string(self.x509.issuer_name())
+        }
+
+        pub fn common_name(&self) -> Option<String> {
+            self.x509
+                .subject_name()
+                .entries_by_nid(Nid::COMMONNAME)
+                .next()
+                .and_then(|entry| entry.data().as_utf8().ok())
+                .map(|s| s.to_string())
+        }
+
+        pub fn subject_alt_names(&self) -> Vec<SubjectAltName> {
+            let Some(names) = self.x509.subject_alt_names() else {
+                return Vec::new();
+            };
+            names
+                .iter()
+                .filter_map(|name| {
+                    if let Some(dns) = name.dnsname() {
+                        Some(SubjectAltName::Dns(dns.to_string()))
+                    } else if let Some(ip) = name.ipaddress() {
+                        match ip.len() {
+                            4 => <[u8; 4]>::try_from(ip).ok().map(std::net::IpAddr::from),
+                            16 => <[u8; 16]>::try_from(ip).ok().map(std::net::IpAddr::from),
+                            _ => None,
+                        }
+                        .map(SubjectAltName::Ip)
+                    } else if let Some(email) = name.email() {
+                        Some(SubjectAltName::Email(email.to_string()))
+                    } else {
+                        name.uri().map(|uri| SubjectAltName::Uri(uri.to_string()))
+                    }
+                })
+                .collect()
+        }
+
+        pub fn not_before(&self) -> Result<i64, CryptoError> {
+            asn1_time_to_unix(self.x509.not_before())
+        }
+
+        pub fn not_after(&self) -> Result<i64, CryptoError> {
+            asn1_time_to_unix(self.x509.not_after())
+        }
+
+        /// 判断给定时间（Unix 秒）是否在有效期内
+        pub fn is_valid_at(&self, time: i64) -> Result<bool, CryptoError> {
+            Ok(self.not_before()? <= time && time <= self.not_after()?)
+        }
+
+        pub fn serial_number(&self) -> Result<String, CryptoError> {
+            self.x509
+                .serial_number()
+                .to_bn()
+                .and_then(|bn| bn.to_hex_str().map(|s| s.to_string()))
+                .map_err(x509_error)
+        }
+
+        pub fn fingerprint_sha256(&self) -> Result<[u8; 32], CryptoError> {
+            let digest = self
+                .x509
+                .digest(MessageDigest::sha256())
+                .map_err(x509_error)?;
+            let mut out = [0u8; 32];
+            out.copy_from_slice(&digest);
+            Ok(out)
+        }
+
+        pub fn spki_sha256(&self) -> Result<[u8; 32], CryptoError> {
+            let spki = self
+                .x509
+                .public_key()
+                .and_then(|key| key.public_key_to_der())
+                .map_err(x509_error)?;
+            let digest = crate::hash::hash::hash(HashAlgorithm::Sha256, &spki)?;
+            let mut out = [0u8; 32];
+            out.copy_from_slice(digest.as_bytes());
+            Ok(out)
+        }
+
+        /// 证书公钥（PEM）
+        pub fn public_key_pem(&self) -> Result<Vec<u8>, CryptoError> {
+            self.x509
+                .public_key()
+                .and_then(|key| key.public_key_to_pem())
+                .map_err(x509_error)
+        }
+
+        /// 汇总证书信息
+        pub fn info(&self) -> Result<CertificateInfo, CryptoError> {
+            Ok(CertificateInfo {
+                subject: self.subject(),
+                issuer: self.issuer(),
+                subject_alt_names: self.subject_alt_names(),
+                not_before: self.not_before()?,
+                not_after: self.not_after()?,
+                serial_number: self.serial_number()?,
+                fingerprint_sha256: hex_encode(&self.fingerprint_sha256()?),
+                spki_sha256: hex_encode(&self.spki_sha256()?),
+            })
+        }
+    }
+
+    /// 离线验证证书链
+    ///
+    /// `intermediates` 为中间证书，`roots` 为受信任的 CA；`at_time` 为验证时间（Unix 秒），
+    /// 为空时使用当前时间。不检查吊销状态。
+    pub fn verify_certificate_chain(
+        leaf: &Certificate,
+        intermediates: &[Certificate],
+        roots: &[Certificate],
+        at_time: Option<i64>,
+    ) -> Result<(), CryptoError> {
+        if roots.is_empty() {
+            return Err(CryptoError::CertificateError(
+                "至少需要一个受信任的 CA".into(),
+            ));
+        }
+        let mut store = X509StoreBuilder::new().map_err(x509_error)?;
+        for root in roots {
+            store.add_cert(root.x509.clone()).map_err(x509_error)?;
+        }
+        if let Some(time) = at_time {
+            let mut param = X509VerifyParam::new().map_err(x509_error)?;
+            param.set_time(time as _);
+            store.set_param(&param).map_err(x509_error)?;
+        }
+        let store = store.build();
+
+        let mut chain = Stack::new().map_err(x509_error)?;
+        for cert in intermediates {
+            chain.push(cert.x509.clone()).map_err(x509_error)?;
+        }
+
+        let mut ctx = X509StoreContext::new().map_err(x509_error)?;
+        let result = ctx
+            .init(&store, &leaf.x509, &chain, |c| {
+                let ok = c.verify_cert()?;
+                Ok(if ok {
+                    Ok(())
+                } else {
+                    Err(format!(
+                        "{}（深度 {}）",
+                        c.error().error_string(),
+                        c.error_depth()
+                    ))
+                })
+            })
+            .map_err(x509_error)?;
+        result.map_err(CryptoError::CertificateVerificationFailed)
+    }
+
+    /// CSR 的主题和备用名称
+    #[derive(Debug, Clone, Default)]
+    pub struct CsrRequest {
+        /// 主题字段，按顺序写入，例如 `("CN", "device-1")`、`("O", "FirmNetter")`
+        pub subject: Vec<(String, String)>,
+        pub dns_names: Vec<String>,
+        pub ip_addresses: Vec<std::net::IpAddr>,
+    }
+
+    impl CsrRequest {
+        pub fn new(common_name: &str) -> Self {
+            CsrRequest {
+                subject: vec![("CN".to_string(), common_name.to_string())],
+                ..Default::default()
+            }
+        }
+    }
+
+    /// 生成 CSR 使用的私钥
+    pub enum CsrKey<'a> {
+        /// Ed25519 或 ECDSA P-256
+        Signing(&'a SigningKey),
+        Rsa(&'a RsaPrivateKey),
+    }
+
+    fn build_csr(pkey: &PKeyRef<Private>, request: &CsrRequest) -> Result<Vec<u8>, CryptoError> {
+        if request.subject.is_empty() {
+            return Err(CryptoError::CertificateError("CSR 主题不能为空".into()));
+        }
+        let mut name = X509NameBuilder::new().map_err(x509_error)?;
+        for (field, value) in &request.subject {
+            name.append_entry_by_text(field, value)
+                .map_err(|e| CryptoError::CertificateError(format!("{}: {}", field, e)))?;
+        }
+        let name = name.build();
+
+        let mut builder = X509ReqBuilder::new().map_err(x509_error)?;
+        builder.set_version(0).map_err(x509_error)?;
+        builder.set_subject_name(&name).map_err(x509_error)?;
+        builder.set_pubkey(pkey).map_err(x509_error)?;
+
+        if !request.dns_names.is_empty() || !request.ip_addresses.is_empty() {
+            let mut san = SubjectAlternativeName::new();
+            for dns in &request.dns_names {
+                san.dns(dns);
+            }
+            for ip in &request.ip_addresses {
+                san.ip(&ip.to_string());
+            }
+            let extension = san
+                .build(&builder.x509v3_context(None))
+                .map_err(x509_error)?;
+            let mut extensions = Stack::new().map_err(x509_error)?;
+            extensions.push(extension).map_err(x509_error)?;
+            builder.add_extensions(&extensions).map_err(x509_error)?;
+        }
+
+        // Ed25519 自带哈希，不能再指定摘要算法
+        let digest = if pkey.id() == Id::ED25519 {
+            MessageDigest::null()
+        } else {
+            MessageDigest::sha256()
+        };
+        builder.sign(pkey, digest).map_err(x509_error)?;
+        builder.build().to_pem().map_err(x509_error)
+    }
+
+    /// 用密钥对生成 PEM 格式的 CSR（RSA / ECDSA 使用 SHA-256 签名）
+    pub fn create_csr(key: &CsrKey, request: &CsrRequest) -> Result<Vec<u8>, CryptoError> {
+        match key {
+            CsrKey::Signing(key) => build_csr(&key.pkey, request),
+            CsrKey::Rsa(key) => build_csr(&key.pkey, request),
+        }
+    }
+
+    // 口令哈希存储（PHC 字符串格式）
+    // Argon2id：$argon2id$v=19$m=<KiB>,t=<迭代>,p=<lanes>$<盐>$<哈希>
+    // scrypt：  $scrypt$ln=<log2 N>,r=<r>,p=<p>$<盐>$<哈希>
+    // 盐和哈希使用无填充的标准 Base64
+    const PASSWORD_HASH_LEN: usize = 32;
+    const ARGON2_VERSION: u32 = 19;
+
+    /// 常量时间比较两段字节，长度不同时直接返回 false（长度本身不保密）
+    pub fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+        a.len() == b.len() && memcmp::eq(a, b)
+    }
+
+    /// 口令校验结果
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum PasswordVerification {
+        Valid,
+        /// 口令正确，但存储的哈希参数弱于当前策略，应重新计算并保存
+        ValidNeedsRehash,
+    }
+
+    struct PhcHash {
+        params: KdfParams,
+        salt: Vec<u8>,
+        hash: Vec<u8>,
+    }
+
+    fn parse_phc_params(fields: &str) -> Result<Vec<(&str, u32)>, CryptoError> {
+        fields
+            .split(',')
+            .map(|field| {
+                let (key, value) = field.split_once('=').ok_or_else(|| {
+                    CryptoError::InvalidPasswordHash(format!("参数格式错误: {}", field))
+                })?;
+                let value = value.parse::<u32>().map_err(|_| {
+                    CryptoError::InvalidPasswordHash(format!("参数值无效: {}", field))
+                })?;
+                Ok((key, value))
+            })
+            .collect()
+    }
+
+    fn phc_param(params: &[(&str, u32)], name: &str) -> Result<u32, CryptoError> {
+        params
+            .iter()
+            .find(|(key, _)| *key == name)
+            .map(|(_, value)| *value)
+            .ok_or_else(|| CryptoError::InvalidPasswordHash(format!("缺少参数 {}", name)))
+    }
+
+    fn parse_phc(phc: &str) -> Result<PhcHash, CryptoError> {
+        let parts: Vec<&str> = phc.trim().split('$').collect();
+        // 开头的 '$' 会产生一个空段
+        let (algorithm, version, params, salt, hash) = match parts.as_slice() {
+            ["", alg, version, params, salt, hash] if version.starts_with("v=") => {
+                (*alg, Some(*version), *params, *salt, *hash)
+            }
+            ["", alg, params, salt, hash] => (*alg, None, *params, *salt, *hash),
+            _ => return Err(CryptoError::InvalidPasswordHash("不是 PHC 格式".into())),
+        };
+
+        let fields = parse_phc_params(params)?;
+        let params = match algorithm {
+            "argon2id" => {
+                if version != Some("v=19") {
+                    return Err(CryptoError::InvalidPasswordHash(format!(
+                        "仅支持 Argon2 版本 {}",
+                        ARGON2_VERSION
+                    )));
+                }
+                KdfParams::Argon2id {
+                    iterations: phc_param(&fields, "t")?,
+                    memory_kib: phc_param(&fields, "m")?,
+                    lanes: phc_param(&fields, "p")?,
+                }
+            }
+            "scrypt" => KdfParams::Scrypt {
+                log_n: phc_param(&fields, "ln")?,
+                r: phc_param(&fields, "r")?,
+                p: phc_param(&fields, "p")?,
+            },
+            other => {
+                return Err(CryptoError::InvalidPasswordHash(format!(
+                    "不支持的算法: {}",
+                    other
+                )));
+            }
+        };
+        // 拒绝超出上限的参数，避免恶意哈希串耗尽资源
+        params
+            .validate()
+            .map_err(|e| CryptoError::InvalidPasswordHash(e.to_string()))?;
+
+        let salt = decode(Encoding::Base64NoPad, salt)
+            .map_err(|e| CryptoError::InvalidPasswordHash(e.to_string()))?;
+        let hash = decode(Encoding::Base64NoPad, hash)
+            .map_err(|e| CryptoError::InvalidPasswordHash(e.to_string()))?;
+        if salt.is_empty() || hash.is_empty() {
+            return Err(CryptoError::InvalidPasswordHash("盐和哈希不能为空".into()));
+        }
+        Ok(PhcHash { params, salt, hash })
+    }
+
+    /// 计算口令哈希并编码为 PHC 字符串，仅支持 Argon2id 和 scrypt
+    pub fn hash_password(password: &[u8], params: &KdfParams) -> Result<String, CryptoError> {
+        let header = match *params {
+            KdfParams::Argon2id {
+                iterations,
+                memory_kib,
+                lanes,
+            } => format!(
+                "$argon2id$v={}$m={},t={},p={}",
+                ARGON2_VERSION, memory_kib, iterations, lanes
+            ),
+            KdfParams::Scrypt { log_n, r, p } => format!("$scrypt$ln={},r={},p={}", log_n, r, p),
+            KdfParams::Pbkdf2Sha256 { .. } => {
+                return Err(CryptoError::InvalidKdfParameters(
+                    "口令存储只支持 Argon2id 和 scrypt".into(),
+                ));
+            }
+        };
+
+        let salt = generate_salt(DEFAULT_SALT_LEN)
+            .map_err(|e| CryptoError::KeyDerivationFailed(e.to_string()))?;
+        let hash = Zeroizing::new(derive_key_from_password(
+            password,
+            &salt,
+            params,
+            PASSWORD_HASH_LEN,
+        )?);
+        Ok(format!(
+            "{}${}${}",
+            header,
+            encode(Encoding::Base64NoPad, &salt),
+            encode(Encoding::Base64NoPad, &hash)
+        ))
+    }
+
+    /// 使用默认参数（Argon2id）计算口令哈希
+    pub fn hash_password_default(password: &[u8]) -> Result<String, CryptoError> {
+        hash_password(password, &KdfParams::default())
+    }
+
+    /// 判断存储的哈希是否需要按当前参数重新计算
+    pub fn password_needs_rehash(phc: &str, current: &KdfParams) -> Result<bool, CryptoError> {
+        let parsed = parse_phc(phc)?;
+        Ok(parsed.params != *current
+            || parsed.salt.len() < DEFAULT_SALT_LEN
+            || parsed.hash.len() < PASSWORD_HASH_LEN)
+    }
+
+    /// 校验口令，比较为常量时间；口令错误时返回 `PasswordMismatch`
+    ///
+    /// `current` 为当前的哈希策略，存储的参数与之不同时返回 `ValidNeedsRehash`。
+    pub fn verify_password(
+        password: &[u8],
+        phc: &str,
+        current: &KdfParams,
+    ) -> Result<PasswordVerification, CryptoError> {
+        let parsed = parse_phc(phc)?;
+        let computed = Zeroizing::new(derive_key_from_password(
+            password,
+            &parsed.salt,
+            &parsed.params,
+            parsed.hash.len(),
+        )?);
+        if !constant_time_eq(&computed, &parsed.hash) {
+            return Err(CryptoError::PasswordMismatch);
+        }
+
+        if password_needs_rehash(phc, current)? {
+            Ok(PasswordVerification::ValidNeedsRehash)
+        } else {
+            Ok(PasswordVerification::Valid)
+        }
+    }
+
+    // C接口辅助函数：错误转换
+    pub(crate) fn crypto_error_to_code(err: &CryptoError) -> CryptoErrorCode {
+        match err {
+            CryptoError::EncryptionFailed(_) => CryptoErrorCode::EncryptionFailed,
+            CryptoError::DecryptionFailed(_) => CryptoErrorCode::DecryptionFailed,
+            CryptoError::UnsupportedKeyLength { .. } => CryptoErrorCode::UnsupportedKeyLength,
+            CryptoError::InvalidCiphertextLength { .. } => CryptoErrorCode::InvalidCiphertextLength,
+            CryptoError::TagVerificationFailed => CryptoErrorCode::TagVerificationFailed,
+            CryptoError::InvalidNonceLength => CryptoErrorCode::InvalidNonceLength,
+            CryptoError::InvalidTagLength => CryptoErrorCode::InvalidTagLength,
+            CryptoError::Base64EncodeError(_) => CryptoErrorCode::Base64EncodeError,
+            CryptoError::Base64DecodeError(_) => CryptoErrorCode::Base64DecodeError,
+            CryptoError::InvalidCiphertextFormat => CryptoErrorCode::InvalidCiphertextFormat,
+            CryptoError::Utf8DecodingFailed(_) => CryptoErrorCode::Utf8DecodingFailed,
+            CryptoError::InvalidKdfParameters(_) => CryptoErrorCode::InvalidKdfParameters,
+            CryptoError::KeyDerivationFailed(_) => CryptoErrorCode::KeyDerivationFailed,
+            CryptoError::KeyGenerationFailed(_) => CryptoErrorCode::KeyGenerationFailed,
+            CryptoError::InvalidKey(_) => CryptoErrorCode::InvalidKey,
+            CryptoError::SignatureFailed(_) => CryptoErrorCode::SignatureFailed,
+            CryptoError::SignatureVerificationFailed => {
+                CryptoErrorCode::SignatureVerificationFailed
+            }
+            CryptoError::IoError(_) => CryptoErrorCode::IoError,
+            CryptoError::UnsupportedRsaKeySize { .. } => CryptoErrorCode::UnsupportedRsaKeySize,
+            CryptoError::RsaPlaintextTooLong { .. } => CryptoErrorCode::RsaPlaintextTooLong,
+            CryptoError::KeyDecryptionFailed => CryptoErrorCode::KeyDecryptionFailed,
+            CryptoError::RsaOperationFailed(_) => CryptoErrorCode::RsaOperationFailed,
+            CryptoError::FixedIvEncryptionDisabled => CryptoErrorCode::FixedIvEncryptionDisabled,
+            CryptoError::MemoryLockFailed(_) => CryptoErrorCode::MemoryLockFailed,
+            CryptoError::KeyNotFound(_) => CryptoErrorCode::KeyNotFound,
+            CryptoError::KeyAlreadyExists(_) => CryptoErrorCode::KeyAlreadyExists,
+            CryptoError::KeyRevoked(_) => CryptoErrorCode::KeyRevoked,
+            CryptoError::InvalidKeystore(_) => CryptoErrorCode::InvalidKeystore,
+            CryptoError::HashFailed(_) => CryptoErrorCode::HashFailed,
+            CryptoError::MacVerificationFailed => CryptoErrorCode::MacVerificationFailed,
+            CryptoError::InvalidEncoding(_) => CryptoErrorCode::InvalidEncoding,
+            CryptoError::InvalidToken(_) => CryptoErrorCode::InvalidToken,
+            CryptoError::TokenExpired => CryptoErrorCode::TokenExpired,
+            CryptoError::TokenNotYetValid => CryptoErrorCode::TokenNotYetValid,
+            CryptoError::InvalidClaim(_) => CryptoErrorCode::InvalidClaim,
+            CryptoError::RandomFailed(_) => CryptoErrorCode::RandomFailed,
+            CryptoError::InvalidRandomParameters(_) => CryptoErrorCode::InvalidRandomParameters,
+            CryptoError::CertificateError(_) => CryptoErrorCode::CertificateError,
+            CryptoError::CertificateVerificationFailed(_) => {
+                CryptoErrorCode::CertificateVerificationFailed
+            }
+            CryptoError::PasswordMismatch => CryptoErrorCode::PasswordMismatch,
+            CryptoError::InvalidPasswordHash(_) => CryptoErrorCode::InvalidPasswordHash,
+        }
+    }
+
+    // C接口：生成AES密钥（128位）
+    #[unsafe(no_mangle)]
+    pub extern "C" fn generate_aes128_key(
+        key_buf: *mut u8,
+        key_len: *mut usize,
+    ) -> CryptoErrorCode {
+        if key_buf.is_null() || key_len.is_null() {
+            return CryptoErrorCode::NullPointerError;
+        }
+
+        // 临时密钥放在 SecretKey 中，复制给调用方后即清零
+        let key = match SecretKey::generate(AES_128_KEY_LEN) {
+            Ok(k) => k,
+            Err(_) => return CryptoErrorCode::KeyGenerationFailed,
+        };
+
+        unsafe {
+            *key_len = AES_128_KEY_LEN;
+            let dest = std::slice::from_raw_parts_mut(key_buf, AES_128_KEY_LEN);
+            dest.copy_from_slice(key.expose_secret());
+        }
+        CryptoErrorCode::Success
+    }
+
+    // C接口：生成AES密钥（192位）
+    #[unsafe(no_mangle)]
+    pub extern "C" fn generate_aes192_key(
+        key_buf: *mut u8,
+        key_len: *mut usize,
+    ) -> CryptoErrorCode {
+        if key_buf.is_null() || key_len.is_null() {
+            return CryptoErrorCode::NullPointerError;
+        }
+
+        // 临时密钥放在 SecretKey 中，复制给调用方后即清零
+        let key = match SecretKey::generate(AES_192_KEY_LEN) {
+            Ok(k) => k,
+            Err(_) => return CryptoErrorCode::KeyGenerationFailed,
+        };
+
+        unsafe {
+            *key_len = AES_192_KEY_LEN;
+            let dest = std::slice::from_raw_parts_mut(key_buf, AES_192_KEY_LEN);
+            dest.copy_from_slice(key.expose_secret());
+        }
+        CryptoErrorCode::Success
+    }
+
+    // C接口：生成受保护的随机密钥（16/24/32 字节），需用 free_secret_buffer 释放
+    #[unsafe(no_mangle)]
+    pub extern "C" fn generate_secret_key(
+        key_len: usize,
+        out_key: *mut *mut u8,
+        out_len: *mut usize,
+    ) -> CryptoErrorCode {
+        if out_key.is_null() || out_len.is_null() {
+            return CryptoErrorCode::NullPointerError;
+        }
+
+        match SecretKey::generate(key_len) {
+            Ok(key) => {
+                write_byte_buffer(key.expose_secret().to_vec(), out_key, out_len);
+                CryptoErrorCode::Success
+            }
+            Err(KeyError::InvalidKeyLength) => CryptoErrorCode::UnsupportedKeyLength,
+            Err(_) => CryptoErrorCode::KeyGenerationFailed,
+        }
+    }
+
+    // C接口：生成AES密钥（256位，Base64编码）
+    #[unsafe(no_mangle)]
+    pub extern "C" fn generate_aes256_key_base64(out_key: *mut *mut c_char) -> CryptoErrorCode {
+        if out_key.is_null() {
+            return CryptoErrorCode::NullPointerError;
+        }
+
+        let key_str = match generate_key_base64::<AES_256_KEY_LEN>() {
+            Ok(s) => s,
+            Err(_) => return CryptoErrorCode::KeyGenerationFailed,
+        };
+
+        let c_str = match CString::new(key_str) {
+            Ok(s) => s,
+            Err(_) => return CryptoErrorCode::Base64EncodeError,
+        };
+
+        unsafe {
+            *out_key = c_str.into_raw();
+        }
+        CryptoErrorCode::Success
+    }
+
+    // C接口：AES-GCM加密（Base64输出）
+    #[unsafe(no_mangle)]
+    pub extern "C" fn aes_gcm_encrypt_base64(
+        key: *const u8,
+        key_len: usize,
+        plaintext: *const c_char,
+        ciphertext_out: *mut *mut c_char,
+    ) -> CryptoErrorCode {
+        if key.is_null() || plaintext.is_null() || ciphertext_out.is_null() {
+            return CryptoErrorCode::NullPointerError;
+        }
+
+        let key_slice = unsafe { std::slice::from_raw_parts(key, key_len) };
+        let plaintext_str = unsafe { CStr::from_ptr(plaintext).to_string_lossy().into_owned() };
+
+        let result = encrypt_to_base64(key_slice, plaintext_str.as_bytes());
+        match result {
+            Ok(ciphertext) => {
+                let c_str = match CString::new(ciphertext) {
+                    Ok(s) => s,
+                    Err(_) => return CryptoErrorCode::Base64EncodeError,
+                };
+                unsafe { *ciphertext_out = c_str.into_raw() };
+                CryptoErrorCode::Success
+            }
+            Err(e) => crypto_error_to_code(&e),
+        }
+    }
+
+    // C接口：AES-GCM解密（Base64输入）
+    #[unsafe(no_mangle)]
+    pub extern "C" fn aes_gcm_decrypt_base64(
+        key: *const u8,
+        key_len: usize,
+        ciphertext: *const c_char,
+        plaintext_out: *mut *mut c_char,
+    ) -> CryptoErrorCode {
+        if key.is_null() || ciphertext.is_null() || plaintext_out.is_null() {
+            return CryptoErrorCode::NullPointerError;
+        }
+
+        let key_slice = unsafe { std::slice::from_raw_parts(key, key_len) };
+        let ciphertext_str = unsafe { CStr::from_ptr(ciphertext).to_string_lossy().into_owned() };
+
+        let result = decrypt_from_base64(key_slice, &ciphertext_str);
+        match result {
+            Ok(plaintext_bytes) => {
+                let plaintext_str = match String::from_utf8(plaintext_bytes) {
+                    Ok(s) => s,
+                    Err(_e) => return CryptoErrorCode::Utf8DecodingFailed,
+                };
+                let c_str = match CString::new(plaintext_str) {
+                    Ok(s) => s,
+                    Err(_) => return CryptoErrorCode::Base64DecodeError,
+                };
+                unsafe { *plaintext_out = c_str.into_raw() };
+                CryptoErrorCode::Success
+            }
+            Err(e) => crypto_error_to_code(&e),
+        }
+    }
+
+    // C接口：AES-CBC-192加密
+    #[unsafe(no_mangle)]
+    pub extern "C" fn aes_cbc192_encrypt(
+        key: *const u8,
+        key_len: usize,
+        plaintext: *const c_char,
+        mode: CAesCbcMode,
+        ciphertext_out: *mut *mut c_char,
+    ) -> CryptoErrorCode {
+        if key.is_null() || plaintext.is_null() || ciphertext_out.is_null() {
+            return CryptoErrorCode::NullPointerError;
+        }
+
+        if key_len != AES_192_KEY_LEN {
+            return CryptoErrorCode::UnsupportedKeyLength;
+        }
+
+        let key_slice = unsafe { std::slice::from_raw_parts(key, key_len) };
+        let plaintext_str = unsafe { CStr::from_ptr(plaintext).to_string_lossy().into_owned() };
+        let rust_mode = match mode {
+            #[allow(deprecated)]
+            CAesCbcMode::FixedIv => AesCbcMode::FixedIv,
+            CAesCbcMode::RandomIv => AesCbcMode::RandomIv,
+        };
+
+        let result = encrypt_cbc_192(key_slice, &plaintext_str, rust_mode);
+        match result {
+            Ok(ciphertext) => {
+                let c_str = match CString::new(ciphertext) {
+                    Ok(s) => s,
+                    Err(_) => return CryptoErrorCode::Base64EncodeError,
+                };
+                unsafe { *ciphertext_out = c_str.into_raw() };
+                CryptoErrorCode::Success
+            }
+            Err(e) => crypto_error_to_code(&e),
+        }
+    }
+
+    // C接口：AES-CBC-192解密
+    #[unsafe(no_mangle)]
+    pub extern "C" fn aes_cbc192_decrypt(
+        key: *const u8,
+        key_len: usize,
+        ciphertext: *const c_char,
+        plaintext_out: *mut *mut c_char,
+    ) -> CryptoErrorCode {
+        if key.is_null() || ciphertext.is_null() || plaintext_out.is_null() {
+            return CryptoErrorCode::NullPointerError;
+        }
+
+        if key_len != AES_192_KEY_LEN {
+            return CryptoErrorCode::UnsupportedKeyLength;
+        }
+
+        let key_slice = unsafe { std::slice::from_raw_parts(key, key_len) };
+        let ciphertext_str = unsafe { CStr::from_ptr(ciphertext).to_string_lossy().into_owned() };
+
+        let result = decrypt_cbc_192(key_slice, &ciphertext_str);
+        match result {
+            Ok(plaintext) => {
+                let c_str = match CString::new(plaintext) {
+                    Ok(s) => s,
+                    Err(e) => {
+                        return crypto_error_to_code(&CryptoError::Utf8DecodingFailed(
+                            e.to_string(),
+                        ));
+                    }
+                };
+                unsafe { *plaintext_out = c_str.into_raw() };
+                CryptoErrorCode::Success
+            }
+            Err(e) => crypto_error_to_code(&e),
+        }
+    }
+
+    // C接口：Base64编码
+    #[unsafe(no_mangle)]
+    pub extern "C" fn base64_encode_c(
+        data: *const u8,
+        data_len: usize,
+        out_str: *mut *mut c_char,
+    ) -> CryptoErrorCode {
+        if data.is_null() || out_str.is_null() {
+            return CryptoErrorCode::NullPointerError;
+        }
+
+        let data_slice = unsafe { std::slice::from_raw_parts(data, data_len) };
+        let encoded = base64_encode(data_slice);
+        let c_str = match CString::new(encoded) {
+            Ok(s) => s,
+            Err(_) => return CryptoErrorCode::Base64EncodeError,
+        };
+
+        unsafe { *out_str = c_str.into_raw() };
+        CryptoErrorCode::Success
+    }
+
+    // C接口：Base64解码
+    #[unsafe(no_mangle)]
+    pub extern "C" fn base64_decode_c(
+        data_str: *const c_char,
+        out_data: *mut *mut u8,
+        out_len: *mut usize,
+    ) -> CryptoErrorCode {
+        if data_str.is_null() || out_data.is_null() || out_len.is_null() {
+            return CryptoErrorCode::NullPointerError;
+        }
+
+        let data = unsafe { CStr::from_ptr(data_str).to_string_lossy().into_owned() };
+        let decoded = match base64_decode(&data) {
+            Ok(d) => d,
+            Err(e) => return crypto_error_to_code(&e),
+        };
+
+        unsafe {
+            *out_len = decoded.len();
+            let mut buf = vec![0u8; decoded.len()].into_boxed_slice();
+            buf.copy_from_slice(&decoded);
+            *out_data = buf.as_mut_ptr();
+            std::mem::forget(buf); // 转移所有权给C端
+        }
+        CryptoErrorCode::Success
+    }
+
+    fn c_encoding(encoding: CEncoding) -> Encoding {
+        match encoding {
+            CEncoding::Base64 => Encoding::Base64,
+            CEncoding::Base64NoPad => Encoding::Base64NoPad,
+            CEncoding::Base64Url => Encoding::Base64Url,
+            CEncoding::Base64UrlNoPad => Encoding::Base64UrlNoPad,
+            CEncoding::Base64Mime => Encoding::Base64Mime,
+            CEncoding::Hex => Encoding::Hex,
+            CEncoding::Base32 => Encoding::Base32,
+        }
+    }
+
+    // C接口辅助函数：编码并写成C字符串
+    fn codec_encode_to_c(
+        encoding: Encoding,
+        data: *const u8,
+        data_len: usize,
+        out_str: *mut *mut c_char,
+    ) -> CryptoErrorCode {
+        if out_str.is_null() {
+            return CryptoErrorCode::NullPointerError;
+        }
+        let Some(data_slice) = optional_slice(data, data_len) else {
+            return CryptoErrorCode::NullPointerError;
+        };
+
+        let c_str = match CString::new(encode(encoding, data_slice)) {
+            Ok(s) => s,
+            Err(_) => return CryptoErrorCode::Base64EncodeError,
+        };
+        unsafe { *out_str = c_str.into_raw() };
+        CryptoErrorCode::Success
+    }
+
+    // C接口辅助函数：解码C字符串并写入字节缓冲区
+    fn codec_decode_from_c(
+        encoding: Encoding,
+        data_str: *const c_char,
+        out_data: *mut *mut u8,
+        out_len: *mut usize,
+    ) -> CryptoErrorCode {
+        if data_str.is_null() || out_data.is_null() || out_len.is_null() {
+            return CryptoErrorCode::NullPointerError;
+        }
+
+        let data = unsafe { CStr::from_ptr(data_str).to_string_lossy() };
+        match decode(encoding, &data) {
+            Ok(decoded) => {
+                write_byte_buffer(decoded, out_data, out_len);
+                CryptoErrorCode::Success
+            }
+            Err(e) => crypto_error_to_code(&e),
+        }
+    }
+
+    // C接口：按指定编码编码，结果需用 free_c_string 释放
+    #[unsafe(no_mangle)]
+    pub extern "C" fn codec_encode_c(
+        encoding: CEncoding,
+        data: *const u8,
+        data_len: usize,
+        out_str: *mut *mut c_char,
+    ) -> CryptoErrorCode {
+        codec_encode_to_c(c_encoding(encoding), data, data_len, out_str)
+    }
+
+    // C接口：按指定编码解码，结果需用 free_byte_buffer 释放
+    #[unsafe(no_mangle)]
+    pub extern "C" fn codec_decode_c(
+        encoding: CEncoding,
+        data_str: *const c_char,
+        out_data: *mut *mut u8,
+        out_len: *mut usize,
+    ) -> CryptoErrorCode {
+        codec_decode_from_c(c_encoding(encoding), data_str, out_data, out_len)
+    }
+
+    // C接口：URL 安全 Base64 编码（无填充）
+    #[unsafe(no_mangle)]
+    pub extern "C" fn base64url_encode_c(
+        data: *const u8,
+        data_len: usize,
+        out_str: *mut *mut c_char,
+    ) -> CryptoErrorCode {
+        codec_encode_to_c(Encoding::Base64UrlNoPad, data, data_len, out_str)
+    }
+
+    // C接口：URL 安全 Base64 解码（无填充）
+    #[unsafe(no_mangle)]
+    pub extern "C" fn base64url_decode_c(
+        data_str: *const c_char,
+        out_data: *mut *mut u8,
+        out_len: *mut usize,
+    ) -> CryptoErrorCode {
+        codec_decode_from_c(Encoding::Base64UrlNoPad, data_str, out_data, out_len)
+    }
+
+    // C接口：十六进制编码
+    #[unsafe(no_mangle)]
+    pub extern "C" fn hex_encode_c(
+        data: *const u8,
+        data_len: usize,
+        out_str: *mut *mut c_char,
+    ) -> CryptoErrorCode {
+        codec_encode_to_c(Encoding::Hex, data, data_len, out_str)
+    }
+
+    // C接口：十六进制解码
+    #[unsafe(no_mangle)]
+    pub extern "C" fn hex_decode_c(
+        data_str: *const c_char,
+        out_data: *mut *mut u8,
+        out_len: *mut usize,
+    ) -> CryptoErrorCode {
+        codec_decode_from_c(Encoding::Hex, data_str, out_data, out_len)
+    }
+
+    // C接口：Base32 编码
+    #[unsafe(no_mangle)]
+    pub extern "C" fn base32_encode_c(
+        data: *const u8,
+        data_len: usize,
+        out_str: *mut *mut c_char,
+    ) -> CryptoErrorCode {
+        codec_encode_to_c(Encoding::Base32, data, data_len, out_str)
+    }
+
+    // C接口：Base32 解码
+    #[unsafe(no_mangle)]
+    pub extern "C" fn base32_decode_c(
+        data_str: *const c_char,
+        out_data: *mut *mut u8,
+        out_len: *mut usize,
+    ) -> CryptoErrorCode {
+        codec_decode_from_c(Encoding::Base32, data_str, out_data, out_len)
+    }
+
+    // C接口：流式编码文件，out_written 可为空
+    #[unsafe(no_mangle)]
+    pub extern "C" fn codec_encode_file_c(
+        encoding: CEncoding,
+        input_path: *const c_char,
+        output_path: *const c_char,
+        out_written: *mut u64,
+    ) -> CryptoErrorCode {
+        if input_path.is_null() || output_path.is_null() {
+            return CryptoErrorCode::NullPointerError;
+        }
+
+        let input = unsafe { CStr::from_ptr(input_path).to_string_lossy() };
+        let output = unsafe { CStr::from_ptr(output_path).to_string_lossy() };
+        match encode_file(c_encoding(encoding), input.as_ref(), output.as_ref()) {
+            Ok(written) => {
+                if !out_written.is_null() {
+                    unsafe { *out_written = written };
+                }
+                CryptoErrorCode::Success
+            }
+            Err(e) => crypto_error_to_code(&e),
+        }
+    }
+
+    // C接口：流式解码文件，out_written 可为空
+    #[unsafe(no_mangle)]
+    pub extern "C" fn codec_decode_file_c(
+        encoding: CEncoding,
+        input_path: *const c_char,
+        output_path: *const c_char,
+        out_written: *mut u64,
+    ) -> CryptoErrorCode {
+        if input_path.is_null() || output_path.is_null() {
+            return CryptoErrorCode::NullPointerError;
+        }
+
+        let input = unsafe { CStr::from_ptr(input_path).to_string_lossy() };
+        let output = unsafe { CStr::from_ptr(output_path).to_string_lossy() };
+        match decode_file(c_encoding(encoding), input.as_ref(), output.as_ref()) {
+            Ok(written) => {
+                if !out_written.is_null() {
+                    unsafe { *out_written = written };
+                }
+                CryptoErrorCode::Success
+            }
+            Err(e) => crypto_error_to_code(&e),
+        }
+    }
+
+    // C接口：获取口令派生算法的默认参数
+    #[unsafe(no_mangle)]
+    pub extern "C" fn kdf_default_params(
+        algorithm: CKdfAlgorithm,
+        out_params: *mut CKdfParams,
+    ) -> CryptoErrorCode {
+        if out_params.is_null() {
+            return CryptoErrorCode::NullPointerError;
+        }
+
+        let rust_algorithm = match algorithm {
+            CKdfAlgorithm::Pbkdf2Sha256 => KdfAlgorithm::Pbkdf2Sha256,
+            CKdfAlgorithm::Scrypt => KdfAlgorithm::Scrypt,
+            CKdfAlgorithm::Argon2id => KdfAlgorithm::Argon2id,
+        };
+
+        unsafe { *out_params = KdfParams::default_for(rust_algorithm).into() };
+        CryptoErrorCode::Success
+    }
+
+    // C接口：生成随机盐
+    #[unsafe(no_mangle)]
+    pub extern "C" fn generate_salt_c(salt_buf: *mut u8, salt_len: usize) -> CryptoErrorCode {
+        if salt_buf.is_null() {
+            return CryptoErrorCode::NullPointerError;
+        }
+
+        let salt = match generate_salt(salt_len) {
+            Ok(s) => s,
+            Err(_) => return CryptoErrorCode::KeyGenerationFailed,
+        };
+
+        unsafe {
+            let dest = std::slice::from_raw_parts_mut(salt_buf, salt_len);
+            dest.copy_from_slice(&salt);
+        }
+        CryptoErrorCode::Success
+    }
+
+    // C接口：口令派生密钥，结果写入调用方提供的 key_buf（长度 key_len）
+    #[unsafe(no_mangle)]
+    pub extern "C" fn derive_key_from_password_c(
+        password: *const u8,
+        password_len: usize,
+        salt: *const u8,
+        salt_len: usize,
+        params: *const CKdfParams,
+        key_buf: *mut u8,
+        key_len: usize,
+    ) -> CryptoErrorCode {
+        if password.is_null() || salt.is_null() || params.is_null() || key_buf.is_null() {
+            return CryptoErrorCode::NullPointerError;
+        }
+
+        let password_slice = unsafe { std::slice::from_raw_parts(password, password_len) };
+        let salt_slice = unsafe { std::slice::from_raw_parts(salt, salt_len) };
+        let rust_params: KdfParams = unsafe { *params }.into();
+
+        match derive_key_from_password(password_slice, salt_slice, &rust_params, key_len)
+            .map(Zeroizing::new)
+        {
+            Ok(key) => {
+                unsafe {
+                    let dest = std::slice::from_raw_parts_mut(key_buf, key_len);
+                    dest.copy_from_slice(&key);
+                }
+                CryptoErrorCode::Success
+            }
+            Err(e) => crypto_error_to_code(&e),
+        }
+    }
+
+    // C接口：口令加密（Base64输出），params 为空时使用 Argon2id 默认参数
+    #[unsafe(no_mangle)]
+    pub extern "C" fn password_encrypt_base64(
+        password: *const c_char,
+        plaintext: *const c_char,
+        params: *const CKdfParams,
+        ciphertext_out: *mut *mut c_char,
+    ) -> CryptoErrorCode {
+        if password.is_null() || plaintext.is_null() || ciphertext_out.is_null() {
+            return CryptoErrorCode::NullPointerError;
+        }
+
+        let password_bytes = unsafe { CStr::from_ptr(password).to_bytes() };
+        let plaintext_bytes = unsafe { CStr::from_ptr(plaintext).to_bytes() };
+        let rust_params = if params.is_null() {
+            KdfParams::default()
+        } else {
+            unsafe { *params }.into()
+        };
+
+        match encrypt_with_password_to_base64(password_bytes, plaintext_bytes, &rust_params) {
+            Ok(ciphertext) => {
+                let c_str = match CString::new(ciphertext) {
+                    Ok(s) => s,
+                    Err(_) => return CryptoErrorCode::Base64EncodeError,
+                };
+                unsafe { *ciphertext_out = c_str.into_raw() };
+                CryptoErrorCode::Success
+            }
+            Err(e) => crypto_error_to_code(&e),
+        }
+    }
+
+    // C接口：口令解密（Base64输入）
+    #[unsafe(no_mangle)]
+    pub extern "C" fn password_decrypt_base64(
+        password: *const c_char,
+        ciphertext: *const c_char,
+        plaintext_out: *mut *mut c_char,
+    ) -> CryptoErrorCode {
+        if password.is_null() || ciphertext.is_null() || plaintext_out.is_null() {
+            return CryptoErrorCode::NullPointerError;
+        }
+
+        let password_bytes = unsafe { CStr::from_ptr(password).to_bytes() };
+        let ciphertext_str = unsafe { CStr::from_ptr(ciphertext).to_string_lossy().into_owned() };
+
+        match decrypt_with_password_from_base64(password_bytes, &ciphertext_str) {
+            Ok(plaintext_bytes) => {
+                let plaintext_str = match String::from_utf8(plaintext_bytes) {
+                    Ok(s) => s,
+                    Err(_) => return CryptoErrorCode::Utf8DecodingFailed,
+                };
+                let c_str = match CString::new(plaintext_str) {
+                    Ok(s) => s,
+                    Err(_) => return CryptoErrorCode::Utf8DecodingFailed,
+                };
+                unsafe { *plaintext_out = c_str.into_raw() };
+                CryptoErrorCode::Success
+            }
+            Err(e) => crypto_error_to_code(&e),
+        }
+    }
+
+    // C接口辅助函数：允许长度为0时传入空指针
+    fn optional_slice<'a>(ptr: *const u8, len: usize) -> Option<&'a [u8]> {
+        if len == 0 {
+            Some(&[])
+        } else if ptr.is_null() {
+            None
+        } else {
+            Some(unsafe { std::slice::from_raw_parts(ptr, len) })
+        }
+    }
+
+    fn c_hkdf_hash(hash: CHkdfHash) -> HkdfHash {
+        match hash {
+            CHkdfHash::Sha256 => HkdfHash::Sha256,
+            CHkdfHash::Sha512 => HkdfHash::Sha512,
+        }
+    }
+
+    // C接口：HKDF-Extract，prk_buf 至少需要哈希输出长度（SHA-256 为32，SHA-512 为64）
+    #[unsafe(no_mangle)]
+    pub extern "C" fn hkdf_extract_c(
+        hash: CHkdfHash,
+        salt: *const u8,
+        salt_len: usize,
+        ikm: *const u8,
+        ikm_len: usize,
+        prk_buf: *mut u8,
+        prk_len: *mut usize,
+    ) -> CryptoErrorCode {
+        if prk_buf.is_null() || prk_len.is_null() {
+            return CryptoErrorCode::NullPointerError;
+        }
+        let (Some(salt_slice), Some(ikm_slice)) =
+            (optional_slice(salt, salt_len), optional_slice(ikm, ikm_len))
+        else {
+            return CryptoErrorCode::NullPointerError;
+        };
+
+        match hkdf_extract(c_hkdf_hash(hash), salt_slice, ikm_slice).map(Zeroizing::new) {
+            Ok(prk) => {
+                unsafe {
+                    *prk_len = prk.len();
+                    let dest = std::slice::from_raw_parts_mut(prk_buf, prk.len());
+                    dest.copy_from_slice(&prk);
+                }
+                CryptoErrorCode::Success
+            }
+            Err(e) => crypto_error_to_code(&e),
+        }
+    }
+
+    // C接口：HKDF-Expand，结果写入 okm_buf（长度 okm_len）
+    #[unsafe(no_mangle)]
+    pub extern "C" fn hkdf_expand_c(
+        hash: CHkdfHash,
+        prk: *const u8,
+        prk_len: usize,
+        info: *const u8,
+        info_len: usize,
+        okm_buf: *mut u8,
+        okm_len: usize,
+    ) -> CryptoErrorCode {
+        if prk.is_null() || okm_buf.is_null() {
+            return CryptoErrorCode::NullPointerError;
+        }
+        let Some(info_slice) = optional_slice(info, info_len) else {
+            return CryptoErrorCode::NullPointerError;
+        };
+        let prk_slice = unsafe { std::slice::from_raw_parts(prk, prk_len) };
+
+        match hkdf_expand(c_hkdf_hash(hash), prk_slice, info_slice, okm_len).map(Zeroizing::new) {
+            Ok(okm) => {
+                unsafe {
+                    let dest = std::slice::from_raw_parts_mut(okm_buf, okm_len);
+                    dest.copy_from_slice(&okm);
+                }
+                CryptoErrorCode::Success
+            }
+            Err(e) => crypto_error_to_code(&e),
+        }
+    }
+
+    // C接口：完整 HKDF（extract + expand）
+    #[unsafe(no_mangle)]
+    pub extern "C" fn hkdf_derive_c(
+        hash: CHkdfHash,
+        salt: *const u8,
+        salt_len: usize,
+        ikm: *const u8,
+        ikm_len: usize,
+        info: *const u8,
+        info_len: usize,
+        okm_buf: *mut u8,
+        okm_len: usize,
+    ) -> CryptoErrorCode {
+        if okm_buf.is_null() {
+            return CryptoErrorCode::NullPointerError;
+        }
+        let (Some(salt_slice), Some(ikm_slice), Some(info_slice)) = (
+            optional_slice(salt, salt_len),
+            optional_slice(ikm, ikm_len),
+            optional_slice(info, info_len),
+        ) else {
+            return CryptoErrorCode::NullPointerError;
+        };
+
+        match hkdf(
+            c_hkdf_hash(hash),
+            salt_slice,
+            ikm_slice,
+            info_slice,
+            okm_len,
+        )
+        .map(Zeroizing::new)
+        {
+            Ok(okm) => {
+                unsafe {
+                    let dest = std::slice::from_raw_parts_mut(okm_buf, okm_len);
+                    dest.copy_from_slice(&okm);
+                }
+                CryptoErrorCode::Success
+            }
+            Err(e) => crypto_error_to_code(&e),
+        }
+    }
+
+    // C接口：由主密钥按标签和上下文派生 AES 子密钥（HKDF-SHA256），key_len 必须为 16/24/32
+    #[unsafe(no_mangle)]
+    pub extern "C" fn derive_subkey(
+        master_key: *const u8,
+        master_key_len: usize,
+        label: *const c_char,
+        context: *const u8,
+        context_len: usize,
+        key_buf: *mut u8,
+        key_len: usize,
+    ) -> CryptoErrorCode {
+        if master_key.is_null() || label.is_null() || key_buf.is_null() {
+            return CryptoErrorCode::NullPointerError;
+        }
+        if key_len != AES_128_KEY_LEN && key_len != AES_192_KEY_LEN && key_len != AES_256_KEY_LEN {
+            return CryptoErrorCode::UnsupportedKeyLength;
+        }
+        let Some(context_slice) = optional_slice(context, context_len) else {
+            return CryptoErrorCode::NullPointerError;
+        };
+
+        let master_slice = unsafe { std::slice::from_raw_parts(master_key, master_key_len) };
+        let label_str = unsafe { CStr::from_ptr(label).to_string_lossy().into_owned() };
+
+        let result = KeyHierarchy::new(master_slice, &[])
+            .and_then(|h| h.derive_bytes(&label_str, context_slice, key_len))
+            .map(Zeroizing::new);
+        match result {
+            Ok(key) => {
+                unsafe {
+                    let dest = std::slice::from_raw_parts_mut(key_buf, key_len);
+                    dest.copy_from_slice(&key);
+                }
+                CryptoErrorCode::Success
+            }
+            Err(e) => crypto_error_to_code(&e),
+        }
+    }
+
+    // C接口辅助函数：把字节数据交给C端，之后需用 free_byte_buffer 释放
+    fn write_byte_buffer(data: Vec<u8>, out_data: *mut *mut u8, out_len: *mut usize) {
+        let boxed = data.into_boxed_slice();
+        unsafe {
+            *out_len = boxed.len();
+            *out_data = Box::into_raw(boxed) as *mut u8;
+        }
+    }
+
+    fn c_signature_algorithm(algorithm: CSignatureAlgorithm) -> SignatureAlgorithm {
+        match algorithm {
+            CSignatureAlgorithm::Ed25519 => SignatureAlgorithm::Ed25519,
+            CSignatureAlgorithm::EcdsaP256 => SignatureAlgorithm::EcdsaP256,
+        }
+    }
+
+    fn c_key_format(format: CKeyFormat) -> KeyFormat {
+        match format {
+            CKeyFormat::Pem => KeyFormat::Pem,
+            CKeyFormat::Der => KeyFormat::Der,
+            CKeyFormat::Raw => KeyFormat::Raw,
+        }
+    }
+
+    // C接口：生成签名密钥对，私钥和公钥按 format 编码，需用 free_byte_buffer 释放
+    #[unsafe(no_mangle)]
+    pub extern "C" fn signature_generate_keypair(
+        algorithm: CSignatureAlgorithm,
+        format: CKeyFormat,
+        private_out: *mut *mut u8,
+        private_len: *mut usize,
+        public_out: *mut *mut u8,
+        public_len: *mut usize,
+    ) -> CryptoErrorCode {
+        if private_out.is_null()
+            || private_len.is_null()
+            || public_out.is_null()
+            || public_len.is_null()
+        {
+            return CryptoErrorCode::NullPointerError;
+        }
+
+        let format = c_key_format(format);
+        let result = SigningKey::generate(c_signature_algorithm(algorithm)).and_then(|key| {
+            let private_key = key.to_bytes(format)?;
+            let public_key = key.verifying_key()?.to_bytes(format)?;
+            Ok((private_key, public_key))
+        });
+
+        match result {
+            Ok((private_key, public_key)) => {
+                write_byte_buffer(private_key, private_out, private_len);
+                write_byte_buffer(public_key, public_out, public_len);
+                CryptoErrorCode::Success
+            }
+            Err(e) => crypto_error_to_code(&e),
+        }
+    }
+
+    // C接口：由私钥导出公钥
+    #[unsafe(no_mangle)]
+    pub extern "C" fn signature_public_key_from_private(
+        algorithm: CSignatureAlgorithm,
+        private_key: *const u8,
+        private_len: usize,
+        private_format: CKeyFormat,
+        public_format: CKeyFormat,
+        public_out: *mut *mut u8,
+        public_len: *mut usize,
+    ) -> CryptoErrorCode {
+        if private_key.is_null() || public_out.is_null() || public_len.is_null() {
+            return CryptoErrorCode::NullPointerError;
+        }
+
+        let key_slice = unsafe { std::slice::from_raw_parts(private_key, private_len) };
+        let result = SigningKey::from_bytes(
+            c_signature_algorithm(algorithm),
+            key_slice,
+            c_key_format(private_format),
+        )
+        .and_then(|key| key.verifying_key())
+        .and_then(|key| key.to_bytes(c_key_format(public_format)));
+
+        match result {
+            Ok(public_key) => {
+                write_byte_buffer(public_key, public_out, public_len);
+                CryptoErrorCode::Success
+            }
+            Err(e) => crypto_error_to_code(&e),
+        }
+    }
+
+    // C接口：签名，签名结果需用 free_byte_buffer 释放
+    #[unsafe(no_mangle)]
+    pub extern "C" fn signature_sign(
+        algorithm: CSignatureAlgorithm,
+        private_key: *const u8,
+        private_len: usize,
+        key_format: CKeyFormat,
+        data: *const u8,
+        data_len: usize,
+        signature_out: *mut *mut u8,
+        signature_len: *mut usize,
+    ) -> CryptoErrorCode {
+        if private_key.is_null() || signature_out.is_null() || signature_len.is_null() {
+            return CryptoErrorCode::NullPointerError;
+        }
+        let Some(data_slice) = optional_slice(data, data_len) else {
+            return CryptoErrorCode::NullPointerError;
+        };
+
+        let key_slice = unsafe { std::slice::from_raw_parts(private_key, private_len) };
+        let result = SigningKey::from_bytes(
+            c_signature_algorithm(algorithm),
+            key_slice,
+            c_key_format(key_format),
+        )
+        .and_then(|key| key.sign(data_slice));
+
+        match result {
+            Ok(signature) => {
+                write_byte_buffer(signature, signature_out, signature_len);
+                CryptoErrorCode::Success
+            }
+            Err(e) => crypto_error_to_code(&e),
+        }
+    }
+
+    // C接口：验证签名，签名有效时返回 Success
+    #[unsafe(no_mangle)]
+    pub extern "C" fn signature_verify(
+        algorithm: CSignatureAlgorithm,
+        public_key: *const u8,
+        public_len: usize,
+        key_format: CKeyFormat,
+        data: *const u8,
+        data_len: usize,
+        signature: *const u8,
+        signature_len: usize,
+    ) -> CryptoErrorCode {
+        if public_key.is_null() || signature.is_null() {
+            return CryptoErrorCode::NullPointerError;
+        }
+        let Some(data_slice) = optional_slice(data, data_len) else {
+            return CryptoErrorCode::NullPointerError;
+        };
+
+        let key_slice = unsafe { std::slice::from_raw_parts(public_key, public_len) };
+        let signature_slice = unsafe { std::slice::from_raw_parts(signature, signature_len) };
+        let result = VerifyingKey::from_bytes(
+            c_signature_algorithm(algorithm),
+            key_slice,
+            c_key_format(key_format),
+        )
+        .and_then(|key| key.verify(data_slice, signature_slice));
+
+        match result {
+            Ok(()) => CryptoErrorCode::Success,
+            Err(e) => crypto_error_to_code(&e),
+        }
+    }
+
+    // C接口：验证文件的分离签名（例如 c_download_file 下载的固件包）
+    #[unsafe(no_mangle)]
+    pub extern "C" fn signature_verify_file(
+        algorithm: CSignatureAlgorithm,
+        public_key: *const u8,
+        public_len: usize,
+        key_format: CKeyFormat,
+        file_path: *const c_char,
+        signature: *const u8,
+        signature_len: usize,
+    ) -> CryptoErrorCode {
+        if public_key.is_null() || file_path.is_null() || signature.is_null() {
+            return CryptoErrorCode::NullPointerError;
+        }
+
+        let key_slice = unsafe { std::slice::from_raw_parts(public_key, public_len) };
+        let signature_slice = unsafe { std::slice::from_raw_parts(signature, signature_len) };
+        let path = unsafe { CStr::from_ptr(file_path).to_string_lossy().into_owned() };
+        let result = VerifyingKey::from_bytes(
+            c_signature_algorithm(algorithm),
+            key_slice,
+            c_key_format(key_format),
+        )
+        .and_then(|key| key.verify_file(&path, signature_slice));
+
+        match result {
+            Ok(()) => CryptoErrorCode::Success,
+            Err(e) => crypto_error_to_code(&e),
+        }
+    }
+
+    // C接口辅助函数：把 PEM 字节转换为C字符串
+    fn write_pem_string(pem: Vec<u8>, out: *mut *mut c_char) -> CryptoErrorCode {
+        match CString::new(pem) {
+            Ok(s) => {
+                unsafe { *out = s.into_raw() };
+                CryptoErrorCode::Success
+            }
+            Err(_) => CryptoErrorCode::InvalidKey,
+        }
+    }
+
+    // C接口辅助函数：写出一对 PEM，两段都转换成功后才写出指针，失败时调用方无需释放任何内容
+    fn write_pem_pair(
+        private_pem: Vec<u8>,
+        public_pem: Vec<u8>,
+        private_pem_out: *mut *mut c_char,
+        public_pem_out: *mut *mut c_char,
+    ) -> CryptoErrorCode {
+        let (Ok(private_pem), Ok(public_pem)) =
+            (CString::new(private_pem), CString::new(public_pem))
+        else {
+            return CryptoErrorCode::InvalidKey;
+        };
+        unsafe {
+            *private_pem_out = private_pem.into_raw();
+            *public_pem_out = public_pem.into_raw();
+        }
+        CryptoErrorCode::Success
+    }
+
+    // C接口：生成混合加密密钥对（PEM），需用 free_c_string 释放
+    #[unsafe(no_mangle)]
+    pub extern "C" fn hybrid_generate_keypair(
+        algorithm: CKeyAgreementAlgorithm,
+        private_pem_out: *mut *mut c_char,
+        public_pem_out: *mut *mut c_char,
+    ) -> CryptoErrorCode {
+        if private_pem_out.is_null() || public_pem_out.is_null() {
+            return CryptoErrorCode::NullPointerError;
+        }
+
+        let rust_algorithm = match algorithm {
+            CKeyAgreementAlgorithm::X25519 => KeyAgreementAlgorithm::X25519,
+            CKeyAgreementAlgorithm::EcdhP256 => KeyAgreementAlgorithm::EcdhP256,
+        };
+        let result = HybridPrivateKey::generate(rust_algorithm).and_then(|key| {
+            let private_pem = key.to_pem()?;
+            let public_pem = key.public_key()?.to_pem()?;
+            Ok((private_pem, public_pem))
+        });
+
+        match result {
+            Ok((private_pem, public_pem)) => {
+                write_pem_pair(private_pem, public_pem, private_pem_out, public_pem_out)
+            }
+            Err(e) => crypto_error_to_code(&e),
+        }
+    }
+
+    // C接口：由混合加密私钥 PEM 导出公钥 PEM
+    #[unsafe(no_mangle)]
+    pub extern "C" fn hybrid_public_key_from_private(
+        private_pem: *const c_char,
+        public_pem_out: *mut *mut c_char,
+    ) -> CryptoErrorCode {
+        if private_pem.is_null() || public_pem_out.is_null() {
+            return CryptoErrorCode::NullPointerError;
+        }
+
+        let pem = unsafe { CStr::from_ptr(private_pem).to_bytes() };
+        let result = HybridPrivateKey::from_pem(pem)
+            .and_then(|key| key.public_key())
+            .and_then(|key| key.to_pem());
+
+        match result {
+            Ok(public_pem) => write_pem_string(public_pem, public_pem_out),
+            Err(e) => crypto_error_to_code(&e),
+        }
+    }
+
+    // C接口：混合加密（Base64输出）
+    #[unsafe(no_mangle)]
+    pub extern "C" fn hybrid_encrypt_base64(
+        public_pem: *const c_char,
+        plaintext: *const c_char,
+        ciphertext_out: *mut *mut c_char,
+    ) -> CryptoErrorCode {
+        if public_pem.is_null() || plaintext.is_null() || ciphertext_out.is_null() {
+            return CryptoErrorCode::NullPointerError;
+        }
+
+        let pem = unsafe { CStr::from_ptr(public_pem).to_bytes() };
+        let plaintext_bytes = unsafe { CStr::from_ptr(plaintext).to_bytes() };
+        let result = HybridPublicKey::from_pem(pem)
+            .and_then(|key| hybrid_encrypt_to_base64(&key, plaintext_bytes));
+
+        match result {
+            Ok(ciphertext) => {
+                let c_str = match CString::new(ciphertext) {
+                    Ok(s) => s,
+                    Err(_) => return CryptoErrorCode::Base64EncodeError,
+                };
+                unsafe { *ciphertext_out = c_str.into_raw() };
+                CryptoErrorCode::Success
+            }
+            Err(e) => crypto_error_to_code(&e),
+        }
+    }
+
+    // C接口：混合解密（Base64输入）
+    #[unsafe(no_mangle)]
+    pub extern "C" fn hybrid_decrypt_base64(
+        private_pem: *const c_char,
+        ciphertext: *const c_char,
+        plaintext_out: *mut *mut c_char,
+    ) -> CryptoErrorCode {
+        if private_pem.is_null() || ciphertext.is_null() || plaintext_out.is_null() {
+            return CryptoErrorCode::NullPointerError;
+        }
+
+        let pem = unsafe { CStr::from_ptr(private_pem).to_bytes() };
+        let ciphertext_str = unsafe { CStr::from_ptr(ciphertext).to_string_lossy().into_owned() };
+        let result = HybridPrivateKey::from_pem(pem)
+            .and_then(|key| hybrid_decrypt_from_base64(&key, &ciphertext_str));
+
+        match result {
+            Ok(plaintext_bytes) => {
+                let plaintext_str = match String::from_utf8(plaintext_bytes) {
+                    Ok(s) => s,
+                    Err(_) => return CryptoErrorCode::Utf8DecodingFailed,
+                };
+                let c_str = match CString::new(plaintext_str) {
+                    Ok(s) => s,
+                    Err(_) => return CryptoErrorCode::Utf8DecodingFailed,
+                };
+                unsafe { *plaintext_out = c_str.into_raw() };
+                CryptoErrorCode::Success
+            }
+            Err(e) => crypto_error_to_code(&e),
+        }
+    }
+
+    // C接口辅助函数：可为空的C字符串口令
+    fn optional_passphrase<'a>(passphrase: *const c_char) -> Option<&'a [u8]> {
+        if passphrase.is_null() {
+            None
+        } else {
+            Some(unsafe { CStr::from_ptr(passphrase).to_bytes() })
+        }
+    }
+
+    fn c_rsa_hash(hash: CRsaHash) -> RsaHash {
+        match hash {
+            CRsaHash::Sha1 => RsaHash::Sha1,
+            CRsaHash::Sha256 => RsaHash::Sha256,
+            CRsaHash::Sha384 => RsaHash::Sha384,
+            CRsaHash::Sha512 => RsaHash::Sha512,
+        }
+    }
+
+    // C接口：生成 RSA 密钥对（PEM），passphrase 不为空时私钥加密保存
+    #[unsafe(no_mangle)]
+    pub extern "C" fn rsa_generate_keypair(
+        bits: u32,
+        passphrase: *const c_char,
+        private_pem_out: *mut *mut c_char,
+        public_pem_out: *mut *mut c_char,
+    ) -> CryptoErrorCode {
+        if private_pem_out.is_null() || public_pem_out.is_null() {
+            return CryptoErrorCode::NullPointerError;
+        }
+
+        let pass = optional_passphrase(passphrase);
+        let result = RsaPrivateKey::generate(bits).and_then(|key| {
+            let private_pem = key.to_pem(pass)?;
+            let public_pem = key.public_key()?.to_pem()?;
+            Ok((private_pem, public_pem))
+        });
+
+        match result {
+            Ok((private_pem, public_pem)) => {
+                write_pem_pair(private_pem, public_pem, private_pem_out, public_pem_out)
+            }
+            Err(e) => crypto_error_to_code(&e),
+        }
+    }
+
+    // C接口：RSA-OAEP 加密，密文需用 free_byte_buffer 释放
+    #[unsafe(no_mangle)]
+    pub extern "C" fn rsa_oaep_encrypt(
+        public_pem: *const c_char,
+        hash: CRsaHash,
+        data: *const u8,
+        data_len: usize,
+        ciphertext_out: *mut *mut u8,
+        ciphertext_len: *mut usize,
+    ) -> CryptoErrorCode {
+        if public_pem.is_null() || ciphertext_out.is_null() || ciphertext_len.is_null() {
+            return CryptoErrorCode::NullPointerError;
+        }
+        let Some(data_slice) = optional_slice(data, data_len) else {
+            return CryptoErrorCode::NullPointerError;
+        };
+
+        let pem = unsafe { CStr::from_ptr(public_pem).to_bytes() };
+        let result = RsaPublicKey::from_pem(pem)
+            .and_then(|key| key.encrypt_oaep(data_slice, c_rsa_hash(hash)));
+
+        match result {
+            Ok(ciphertext) => {
+                write_byte_buffer(ciphertext, ciphertext_out, ciphertext_len);
+                CryptoErrorCode::Success
+            }
+            Err(e) => crypto_error_to_code(&e),
+        }
+    }
+
+    // C接口：RSA-OAEP 解密，明文需用 free_byte_buffer 释放
+    #[unsafe(no_mangle)]
+    pub extern "C" fn rsa_oaep_decrypt(
+        private_pem: *const c_char,
+        passphrase: *const c_char,
+        hash: CRsaHash,
+        ciphertext: *const u8,
+        ciphertext_len: usize,
+        plaintext_out: *mut *mut u8,
+        plaintext_len: *mut usize,
+    ) -> CryptoErrorCode {
+        if private_pem.is_null()
+            || ciphertext.is_null()
+            || plaintext_out.is_null()
+            || plaintext_len.is_null()
+        {
+            return CryptoErrorCode::NullPointerError;
+        }
+
+        let pem = unsafe { CStr::from_ptr(private_pem).to_bytes() };
+        let ciphertext_slice = unsafe { std::slice::from_raw_parts(ciphertext, ciphertext_len) };
+        let result = RsaPrivateKey::from_pem(pem, optional_passphrase(passphrase))
+            .and_then(|key| key.decrypt_oaep(ciphertext_slice, c_rsa_hash(hash)));
+
+        match result {
+            Ok(plaintext) => {
+                write_byte_buffer(plaintext, plaintext_out, plaintext_len);
+                CryptoErrorCode::Success
+            }
+            Err(e) => crypto_error_to_code(&e),
+        }
+    }
+
+    // C接口：RSA-PSS 签名，签名需用 free_byte_buffer 释放
+    #[unsafe(no_mangle)]
+    pub extern "C" fn rsa_pss_sign(
+        private_pem: *const c_char,
+        passphrase: *const c_char,
+        hash: CRsaHash,
+        data: *const u8,
+        data_len: usize,
+        signature_out: *mut *mut u8,
+        signature_len: *mut usize,
+    ) -> CryptoErrorCode {
+        if private_pem.is_null() || signature_out.is_null() || signature_len.is_null() {
+            return CryptoErrorCode::NullPointerError;
+        }
+        let Some(data_slice) = optional_slice(data, data_len) else {
+            return CryptoErrorCode::NullPointerError;
+        };
+
+        let pem = unsafe { CStr::from_ptr(private_pem).to_bytes() };
+        let result = RsaPrivateKey::from_pem(pem, optional_passphrase(passphrase))
+            .and_then(|key| key.sign_pss(data_slice, c_rsa_hash(hash)));
+
+        match result {
+            Ok(signature) => {
+                write_byte_buffer(signature, signature_out, signature_len);
+                CryptoErrorCode::Success
+            }
+            Err(e) => crypto_error_to_code(&e),
+        }
+    }
+
+    // C接口：RSA-PSS 验签，签名有效时返回 Success
+    #[unsafe(no_mangle)]
+    pub extern "C" fn rsa_pss_verify(
+        public_pem: *const c_char,
+        hash: CRsaHash,
+        data: *const u8,
+        data_len: usize,
+        signature: *const u8,
+        signature_len: usize,
+    ) -> CryptoErrorCode {
+        if public_pem.is_null() || signature.is_null() {
+            return CryptoErrorCode::NullPointerError;
+        }
+        let Some(data_slice) = optional_slice(data, data_len) else {
+            return CryptoErrorCode::NullPointerError;
+        };
+
+        let pem = unsafe { CStr::from_ptr(public_pem).to_bytes() };
+        let signature_slice = unsafe { std::slice::from_raw_parts(signature, signature_len) };
+        let result = RsaPublicKey::from_pem(pem)
+            .and_then(|key| key.verify_pss(data_slice, signature_slice, c_rsa_hash(hash)));
+
+        match result {
+            Ok(()) => CryptoErrorCode::Success,
+            Err(e) => crypto_error_to_code(&e),
+        }
+    }
+
+    fn c_cbc_mode(mode: CAesCbcMode) -> AesCbcMode {
+        match mode {
+            #[allow(deprecated)]
+            CAesCbcMode::FixedIv => AesCbcMode::FixedIv,
+            CAesCbcMode::RandomIv => AesCbcMode::RandomIv,
+        }
+    }
+
+    // C接口：AES-CBC加密任意字节（128/192/256位密钥），输出需用 free_byte_buffer 释放
+    #[unsafe(no_mangle)]
+    pub extern "C" fn aes_cbc_encrypt(
+        key: *const u8,
+        key_len: usize,
+        data: *const u8,
+        data_len: usize,
+        mode: CAesCbcMode,
+        out_data: *mut *mut u8,
+        out_len: *mut usize,
+    ) -> CryptoErrorCode {
+        if key.is_null() || out_data.is_null() || out_len.is_null() {
+            return CryptoErrorCode::NullPointerError;
+        }
+        let Some(data_slice) = optional_slice(data, data_len) else {
+            return CryptoErrorCode::NullPointerError;
+        };
+
+        let key_slice = unsafe { std::slice::from_raw_parts(key, key_len) };
+        match encrypt_cbc(key_slice, data_slice, c_cbc_mode(mode)) {
+            Ok(ciphertext) => {
+                write_byte_buffer(ciphertext, out_data, out_len);
+                CryptoErrorCode::Success
+            }
+            Err(e) => crypto_error_to_code(&e),
+        }
+    }
+
+    // C接口：AES-CBC解密任意字节
+    #[unsafe(no_mangle)]
+    pub extern "C" fn aes_cbc_decrypt(
+        key: *const u8,
+        key_len: usize,
+        data: *const u8,
+        data_len: usize,
+        mode: CAesCbcMode,
+        out_data: *mut *mut u8,
+        out_len: *mut usize,
+    ) -> CryptoErrorCode {
+        if key.is_null() || data.is_null() || out_data.is_null() || out_len.is_null() {
+            return CryptoErrorCode::NullPointerError;
+        }
+
+        let key_slice = unsafe { std::slice::from_raw_parts(key, key_len) };
+        let data_slice = unsafe { std::slice::from_raw_parts(data, data_len) };
+        match decrypt_cbc(key_slice, data_slice, c_cbc_mode(mode)) {
+            Ok(plaintext) => {
+                write_byte_buffer(plaintext, out_data, out_len);
+                CryptoErrorCode::Success
+            }
+            Err(e) => crypto_error_to_code(&e),
+        }
+    }
+
+    // C接口：AES-CBC + HMAC-SHA256 加密，输出需用 free_byte_buffer 释放
+    #[unsafe(no_mangle)]
+    pub extern "C" fn aes_cbc_hmac_encrypt(
+        key: *const u8,
+        key_len: usize,
+        mac_key: *const u8,
+        mac_key_len: usize,
+        data: *const u8,
+        data_len: usize,
+        out_data: *mut *mut u8,
+        out_len: *mut usize,
+    ) -> CryptoErrorCode {
+        if key.is_null() || mac_key.is_null() || out_data.is_null() || out_len.is_null() {
+            return CryptoErrorCode::NullPointerError;
+        }
+        let Some(data_slice) = optional_slice(data, data_len) else {
+            return CryptoErrorCode::NullPointerError;
+        };
+
+        let key_slice = unsafe { std::slice::from_raw_parts(key, key_len) };
+        let mac_key_slice = unsafe { std::slice::from_raw_parts(mac_key, mac_key_len) };
+        match encrypt_cbc_hmac(key_slice, mac_key_slice, data_slice) {
+            Ok(ciphertext) => {
+                write_byte_buffer(ciphertext, out_data, out_len);
+                CryptoErrorCode::Success
+            }
+            Err(e) => crypto_error_to_code(&e),
+        }
+    }
+
+    // C接口：AES-CBC + HMAC-SHA256 解密，标签不匹配时返回 TagVerificationFailed
+    #[unsafe(no_mangle)]
+    pub extern "C" fn aes_cbc_hmac_decrypt(
+        key: *const u8,
+        key_len: usize,
+        mac_key: *const u8,
+        mac_key_len: usize,
+        data: *const u8,
+        data_len: usize,
+        out_data: *mut *mut u8,
+        out_len: *mut usize,
+    ) -> CryptoErrorCode {
+        if key.is_null()
+            || mac_key.is_null()
+            || data.is_null()
+            || out_data.is_null()
+            || out_len.is_null()
+        {
+            return CryptoErrorCode::NullPointerError;
+        }
+
+        let key_slice = unsafe { std::slice::from_raw_parts(key, key_len) };
+        let mac_key_slice = unsafe { std::slice::from_raw_parts(mac_key, mac_key_len) };
+        let data_slice = unsafe { std::slice::from_raw_parts(data, data_len) };
+        match decrypt_cbc_hmac(key_slice, mac_key_slice, data_slice) {
+            Ok(plaintext) => {
+                write_byte_buffer(plaintext, out_data, out_len);
+                CryptoErrorCode::Success
+            }
+            Err(e) => crypto_error_to_code(&e),
+        }
+    }
+
+    // C接口：显式使用全零 IV 的旧格式 CBC 加密，仅用于与旧系统互通
+    #[unsafe(no_mangle)]
+    pub extern "C" fn aes_cbc_encrypt_legacy_fixed_iv(
+        key: *const u8,
+        key_len: usize,
+        data: *const u8,
+        data_len: usize,
+        out_data: *mut *mut u8,
+        out_len: *mut usize,
+    ) -> CryptoErrorCode {
+        if key.is_null() || out_data.is_null() || out_len.is_null() {
+            return CryptoErrorCode::NullPointerError;
+        }
+        let Some(data_slice) = optional_slice(data, data_len) else {
+            return CryptoErrorCode::NullPointerError;
+        };
+
+        let key_slice = unsafe { std::slice::from_raw_parts(key, key_len) };
+        match encrypt_cbc_legacy_fixed_iv(key_slice, data_slice) {
+            Ok(ciphertext) => {
+                write_byte_buffer(ciphertext, out_data, out_len);
+                CryptoErrorCode::Success
+            }
+            Err(e) => crypto_error_to_code(&e),
+        }
+    }
+
+    // C接口：把一条旧 CBC 密文迁移为 AES-GCM（Base64），new_key 为空时沿用旧密钥
+    #[unsafe(no_mangle)]
+    pub extern "C" fn migrate_legacy_cbc(
+        old_key: *const u8,
+        old_key_len: usize,
+        new_key: *const u8,
+        new_key_len: usize,
+        legacy_ciphertext: *const c_char,
+        ciphertext_out: *mut *mut c_char,
+    ) -> CryptoErrorCode {
+        if old_key.is_null() || legacy_ciphertext.is_null() || ciphertext_out.is_null() {
+            return CryptoErrorCode::NullPointerError;
+        }
+
+        let old_key_slice = unsafe { std::slice::from_raw_parts(old_key, old_key_len) };
+        let new_key_slice = if new_key.is_null() {
+            None
+        } else {
+            Some(unsafe { std::slice::from_raw_parts(new_key, new_key_len) })
+        };
+        let legacy = unsafe { CStr::from_ptr(legacy_ciphertext).to_string_lossy() };
+
+        match migrate_legacy_ciphertext(old_key_slice, new_key_slice, &legacy) {
+            Ok(ciphertext) => {
+                let c_str = match CString::new(ciphertext) {
+                    Ok(s) => s,
+                    Err(_) => return CryptoErrorCode::Base64EncodeError,
+                };
+                unsafe { *ciphertext_out = c_str.into_raw() };
+                CryptoErrorCode::Success
+            }
+            Err(e) => crypto_error_to_code(&e),
+        }
+    }
+
+    // C接口：批量迁移旧 CBC 密文
+    // results_out 和 codes_out 均为调用方分配的 count 长度数组；
+    // 成功的条目写入新密文（需用 free_c_string 释放），失败的条目写入空指针并在 codes_out 中记录错误码
+    #[unsafe(no_mangle)]
+    pub extern "C" fn migrate_legacy_cbc_batch(
+        old_key: *const u8,
+        old_key_len: usize,
+        new_key: *const u8,
+        new_key_len: usize,
+        items: *const *const c_char,
+        count: usize,
+        results_out: *mut *mut c_char,
+        codes_out: *mut CryptoErrorCode,
+        failed_count: *mut usize,
+    ) -> CryptoErrorCode {
+        if old_key.is_null()
+            || items.is_null()
+            || results_out.is_null()
+            || codes_out.is_null()
+            || failed_count.is_null()
+        {
+            return CryptoErrorCode::NullPointerError;
+        }
+
+        let old_key_slice = unsafe { std::slice::from_raw_parts(old_key, old_key_len) };
+        let new_key_slice = if new_key.is_null() {
+            None
+        } else {
+            Some(unsafe { std::slice::from_raw_parts(new_key, new_key_len) })
+        };
+        let item_ptrs = unsafe { std::slice::from_raw_parts(items, count) };
+        let results = unsafe { std::slice::from_raw_parts_mut(results_out, count) };
+        let codes = unsafe { std::slice::from_raw_parts_mut(codes_out, count) };
+
+        let legacy: Vec<String> = item_ptrs
+            .iter()
+            .map(|&p| {
+                if p.is_null() {
+                    String::new()
+                } else {
+                    unsafe { CStr::from_ptr(p).to_string_lossy().into_owned() }
+                }
+            })
+            .collect();
+
+        let mut failed = 0;
+        for (index, result) in migrate_legacy_iter(old_key_slice, new_key_slice, &legacy) {
+            let converted = result.and_then(|ciphertext| {
+                CString::new(ciphertext).map_err(|e| CryptoError::Base64EncodeError(e.to_string()))
+            });
+            match converted {
+                Ok(c_str) => {
+                    results[index] = c_str.into_raw();
+                    codes[index] = CryptoErrorCode::Success;
+                }
+                Err(e) => {
+                    results[index] = ptr::null_mut();
+                    codes[index] = if item_ptrs[index].is_null() {
+                        CryptoErrorCode::NullPointerError
+                    } else {
+                        crypto_error_to_code(&e)
+                    };
+                    failed += 1;
+                }
+            }
         }
+
+        unsafe { *failed_count = failed };
+        CryptoErrorCode::Success
     }
 
-    // C接口辅助函数：错误转换
-    fn crypto_error_to_code(err: &CryptoError) -> CryptoErrorCode {
-        match err {
-            CryptoError::EncryptionFailed(_) => CryptoErrorCode::EncryptionFailed,
-            CryptoError::DecryptionFailed(_) => CryptoErrorCode::DecryptionFailed,
-            CryptoError::UnsupportedKeyLength { .. } => CryptoErrorCode::UnsupportedKeyLength,
-            CryptoError::InvalidCiphertextLength { .. } => CryptoErrorCode::InvalidCiphertextLength,
-            CryptoError::TagVerificationFailed => CryptoErrorCode::TagVerificationFailed,
-            CryptoError::InvalidNonceLength => CryptoErrorCode::InvalidNonceLength,
-            CryptoError::InvalidTagLength => CryptoErrorCode::InvalidTagLength,
-            CryptoError::Base64EncodeError(_) => CryptoErrorCode::Base64EncodeError,
-            CryptoError::Base64DecodeError(_) => CryptoErrorCode::Base64DecodeError,
-            CryptoError::InvalidCiphertextFormat => CryptoErrorCode::InvalidCiphertextFormat,
-            CryptoError::Utf8DecodingFailed(_) => CryptoErrorCode::Utf8DecodingFailed,
+    // C接口：密钥库句柄操作
+    // 句柄由 keystore_create* / keystore_open* 创建，使用完毕后需调用 keystore_free
+
+    fn c_keystore<'a>(handle: *mut Keystore) -> Option<&'a mut Keystore> {
+        unsafe { handle.as_mut() }
+    }
+
+    fn write_keystore_handle(keystore: Keystore, out_handle: *mut *mut Keystore) {
+        unsafe { *out_handle = Box::into_raw(Box::new(keystore)) };
+    }
+
+    // C接口：创建由主密钥保护的空密钥库
+    #[unsafe(no_mangle)]
+    pub extern "C" fn keystore_create(
+        master_key: *const u8,
+        master_key_len: usize,
+        out_handle: *mut *mut Keystore,
+    ) -> CryptoErrorCode {
+        if master_key.is_null() || out_handle.is_null() {
+            return CryptoErrorCode::NullPointerError;
+        }
+
+        let key_slice = unsafe { std::slice::from_raw_parts(master_key, master_key_len) };
+        match Keystore::with_master_key(key_slice) {
+            Ok(keystore) => {
+                write_keystore_handle(keystore, out_handle);
+                CryptoErrorCode::Success
+            }
+            Err(e) => crypto_error_to_code(&e),
         }
     }
 
-    // C接口：生成AES密钥（128位）
+    // C接口：创建由口令保护的空密钥库（params 为空时使用默认参数）
     #[unsafe(no_mangle)]
-    pub extern "C" fn generate_aes128_key(
-        key_buf: *mut u8,
-        key_len: *mut usize,
+    pub extern "C" fn keystore_create_with_password(
+        password: *const u8,
+        password_len: usize,
+        params: *const CKdfParams,
+        out_handle: *mut *mut Keystore,
     ) -> CryptoErrorCode {
-        if key_buf.is_null() || key_len.is_null() {
+        if password.is_null() || out_handle.is_null() {
             return CryptoErrorCode::NullPointerError;
         }
 
-        let key = match generate_key::<AES_128_KEY_LEN>() {
-            Ok(k) => k,
-            Err(_) => return CryptoErrorCode::KeyGenerationFailed,
+        let password_slice = unsafe { std::slice::from_raw_parts(password, password_len) };
+        let params = if params.is_null() {
+            KdfParams::default()
+        } else {
+            unsafe { *params }.into()
         };
+        match Keystore::with_password(password_slice, params) {
+            Ok(keystore) => {
+                write_keystore_handle(keystore, out_handle);
+                CryptoErrorCode::Success
+            }
+            Err(e) => crypto_error_to_code(&e),
+        }
+    }
 
-        unsafe {
-            *key_len = AES_128_KEY_LEN;
-            let dest = std::slice::from_raw_parts_mut(key_buf, AES_128_KEY_LEN);
-            dest.copy_from_slice(&key);
+    // C接口：打开由主密钥保护的密钥库文件
+    #[unsafe(no_mangle)]
+    pub extern "C" fn keystore_open(
+        path: *const c_char,
+        master_key: *const u8,
+        master_key_len: usize,
+        out_handle: *mut *mut Keystore,
+    ) -> CryptoErrorCode {
+        if path.is_null() || master_key.is_null() || out_handle.is_null() {
+            return CryptoErrorCode::NullPointerError;
+        }
+
+        let path_str = unsafe { CStr::from_ptr(path).to_string_lossy() };
+        let key_slice = unsafe { std::slice::from_raw_parts(master_key, master_key_len) };
+        match Keystore::open_with_master_key(path_str.as_ref(), key_slice) {
+            Ok(keystore) => {
+                write_keystore_handle(keystore, out_handle);
+                CryptoErrorCode::Success
+            }
+            Err(e) => crypto_error_to_code(&e),
         }
-        CryptoErrorCode::Success
     }
 
-    // C接口：生成AES密钥（192位）
+    // C接口：打开由口令保护的密钥库文件
     #[unsafe(no_mangle)]
-    pub extern "C" fn generate_aes192_key(
-        key_buf: *mut u8,
-        key_len: *mut usize,
+    pub extern "C" fn keystore_open_with_password(
+        path: *const c_char,
+        password: *const u8,
+        password_len: usize,
+        out_handle: *mut *mut Keystore,
     ) -> CryptoErrorCode {
-        if key_buf.is_null() || key_len.is_null() {
+        if path.is_null() || password.is_null() || out_handle.is_null() {
             return CryptoErrorCode::NullPointerError;
         }
 
-        let key = match generate_key::<AES_192_KEY_LEN>() {
-            Ok(k) => k,
-            Err(_) => return CryptoErrorCode::KeyGenerationFailed,
+        let path_str = unsafe { CStr::from_ptr(path).to_string_lossy() };
+        let password_slice = unsafe { std::slice::from_raw_parts(password, password_len) };
+        match Keystore::open_with_password(path_str.as_ref(), password_slice) {
+            Ok(keystore) => {
+                write_keystore_handle(keystore, out_handle);
+                CryptoErrorCode::Success
+            }
+            Err(e) => crypto_error_to_code(&e),
+        }
+    }
+
+    // C接口：保存密钥库到文件
+    #[unsafe(no_mangle)]
+    pub extern "C" fn keystore_save(handle: *mut Keystore, path: *const c_char) -> CryptoErrorCode {
+        let Some(keystore) = c_keystore(handle) else {
+            return CryptoErrorCode::NullPointerError;
         };
+        if path.is_null() {
+            return CryptoErrorCode::NullPointerError;
+        }
 
-        unsafe {
-            *key_len = AES_192_KEY_LEN;
-            let dest = std::slice::from_raw_parts_mut(key_buf, AES_192_KEY_LEN);
-            dest.copy_from_slice(&key);
+        let path_str = unsafe { CStr::from_ptr(path).to_string_lossy() };
+        match keystore.save(path_str.as_ref()) {
+            Ok(()) => CryptoErrorCode::Success,
+            Err(e) => crypto_error_to_code(&e),
         }
-        CryptoErrorCode::Success
     }
 
-    // C接口：生成AES密钥（256位，Base64编码）
+    // C接口：新增命名密钥，out_version 可为空
     #[unsafe(no_mangle)]
-    pub extern "C" fn generate_aes256_key_base64(out_key: *mut *mut c_char) -> CryptoErrorCode {
-        if out_key.is_null() {
+    pub extern "C" fn keystore_add_key(
+        handle: *mut Keystore,
+        name: *const c_char,
+        key_len: usize,
+        out_version: *mut u32,
+    ) -> CryptoErrorCode {
+        let Some(keystore) = c_keystore(handle) else {
+            return CryptoErrorCode::NullPointerError;
+        };
+        if name.is_null() {
             return CryptoErrorCode::NullPointerError;
         }
 
-        let key_str = match generate_key_base64::<AES_256_KEY_LEN>() {
-            Ok(s) => s,
-            Err(_) => return CryptoErrorCode::KeyGenerationFailed,
+        let name_str = unsafe { CStr::from_ptr(name).to_string_lossy() };
+        match keystore.add_key(&name_str, key_len) {
+            Ok(id) => {
+                if !out_version.is_null() {
+                    unsafe { *out_version = id.version };
+                }
+                CryptoErrorCode::Success
+            }
+            Err(e) => crypto_error_to_code(&e),
+        }
+    }
+
+    // C接口：轮换命名密钥，out_version 返回新版本号，可为空
+    #[unsafe(no_mangle)]
+    pub extern "C" fn keystore_rotate_key(
+        handle: *mut Keystore,
+        name: *const c_char,
+        out_version: *mut u32,
+    ) -> CryptoErrorCode {
+        let Some(keystore) = c_keystore(handle) else {
+            return CryptoErrorCode::NullPointerError;
         };
+        if name.is_null() {
+            return CryptoErrorCode::NullPointerError;
+        }
 
-        let c_str = match CString::new(key_str) {
-            Ok(s) => s,
-            Err(_) => return CryptoErrorCode::Base64EncodeError,
+        let name_str = unsafe { CStr::from_ptr(name).to_string_lossy() };
+        match keystore.rotate_key(&name_str) {
+            Ok(id) => {
+                if !out_version.is_null() {
+                    unsafe { *out_version = id.version };
+                }
+                CryptoErrorCode::Success
+            }
+            Err(e) => crypto_error_to_code(&e),
+        }
+    }
+
+    // C接口：吊销指定版本的密钥
+    #[unsafe(no_mangle)]
+    pub extern "C" fn keystore_revoke_key(
+        handle: *mut Keystore,
+        name: *const c_char,
+        version: u32,
+    ) -> CryptoErrorCode {
+        let Some(keystore) = c_keystore(handle) else {
+            return CryptoErrorCode::NullPointerError;
         };
+        if name.is_null() {
+            return CryptoErrorCode::NullPointerError;
+        }
 
-        unsafe {
-            *out_key = c_str.into_raw();
+        let id = KeyId {
+            name: unsafe { CStr::from_ptr(name).to_string_lossy().into_owned() },
+            version,
+        };
+        match keystore.revoke_key(&id) {
+            Ok(()) => CryptoErrorCode::Success,
+            Err(e) => crypto_error_to_code(&e),
         }
-        CryptoErrorCode::Success
     }
 
-    // C接口：AES-GCM加密（Base64输出）
+    // C接口：以 JSON 数组列出密钥信息（不含密钥材料），需用 free_c_string 释放
     #[unsafe(no_mangle)]
-    pub extern "C" fn aes_gcm_encrypt_base64(
-        key: *const u8,
-        key_len: usize,
-        plaintext: *const c_char,
+    pub extern "C" fn keystore_list_json(
+        handle: *mut Keystore,
+        out_json: *mut *mut c_char,
+    ) -> CryptoErrorCode {
+        let Some(keystore) = c_keystore(handle) else {
+            return CryptoErrorCode::NullPointerError;
+        };
+        if out_json.is_null() {
+            return CryptoErrorCode::NullPointerError;
+        }
+
+        let json = match serde_json::to_string(&keystore.list()) {
+            Ok(j) => j,
+            Err(e) => return crypto_error_to_code(&CryptoError::InvalidKeystore(e.to_string())),
+        };
+        match CString::new(json) {
+            Ok(c_str) => {
+                unsafe { *out_json = c_str.into_raw() };
+                CryptoErrorCode::Success
+            }
+            Err(_) => CryptoErrorCode::InvalidKeystore,
+        }
+    }
+
+    // C接口：用活动密钥加密（Base64输出，带密钥 ID），需用 free_c_string 释放
+    #[unsafe(no_mangle)]
+    pub extern "C" fn keystore_encrypt_base64(
+        handle: *mut Keystore,
+        name: *const c_char,
+        data: *const u8,
+        data_len: usize,
         ciphertext_out: *mut *mut c_char,
     ) -> CryptoErrorCode {
-        if key.is_null() || plaintext.is_null() || ciphertext_out.is_null() {
+        let Some(keystore) = c_keystore(handle) else {
+            return CryptoErrorCode::NullPointerError;
+        };
+        if name.is_null() || ciphertext_out.is_null() {
             return CryptoErrorCode::NullPointerError;
         }
+        let Some(data_slice) = optional_slice(data, data_len) else {
+            return CryptoErrorCode::NullPointerError;
+        };
 
-        let key_slice = unsafe { std::slice::from_raw_parts(key, key_len) };
-        let plaintext_str = unsafe { CStr::from_ptr(plaintext).to_string_lossy().into_owned() };
+        let name_str = unsafe { CStr::from_ptr(name).to_string_lossy() };
+        match keystore.encrypt_to_base64(&name_str, data_slice) {
+            Ok(ciphertext) => match CString::new(ciphertext) {
+                Ok(c_str) => {
+                    unsafe { *ciphertext_out = c_str.into_raw() };
+                    CryptoErrorCode::Success
+                }
+                Err(_) => CryptoErrorCode::Base64EncodeError,
+            },
+            Err(e) => crypto_error_to_code(&e),
+        }
+    }
 
-        let result = encrypt_to_base64(key_slice, plaintext_str.as_bytes());
-        match result {
-            Ok(ciphertext) => {
-                let c_str = match CString::new(ciphertext) {
-                    Ok(s) => s,
-                    Err(_) => return CryptoErrorCode::Base64EncodeError,
-                };
-                unsafe { *ciphertext_out = c_str.into_raw() };
+    // C接口：按密文中的密钥 ID 解密，明文需用 free_secret_buffer 释放
+    #[unsafe(no_mangle)]
+    pub extern "C" fn keystore_decrypt_base64(
+        handle: *mut Keystore,
+        ciphertext: *const c_char,
+        out_data: *mut *mut u8,
+        out_len: *mut usize,
+    ) -> CryptoErrorCode {
+        let Some(keystore) = c_keystore(handle) else {
+            return CryptoErrorCode::NullPointerError;
+        };
+        if ciphertext.is_null() || out_data.is_null() || out_len.is_null() {
+            return CryptoErrorCode::NullPointerError;
+        }
+
+        let ciphertext_str = unsafe { CStr::from_ptr(ciphertext).to_string_lossy() };
+        match keystore.decrypt_from_base64(&ciphertext_str) {
+            Ok(plaintext) => {
+                write_byte_buffer(plaintext, out_data, out_len);
                 CryptoErrorCode::Success
             }
             Err(e) => crypto_error_to_code(&e),
         }
     }
 
-    // C接口：AES-GCM解密（Base64输入）
+    // C接口：释放密钥库句柄，密钥材料随之清零
     #[unsafe(no_mangle)]
-    pub extern "C" fn aes_gcm_decrypt_base64(
+    pub extern "C" fn keystore_free(handle: *mut Keystore) {
+        if !handle.is_null() {
+            unsafe {
+                let _ = Box::from_raw(handle);
+            }
+        }
+    }
+
+    fn c_jws_algorithm(alg: CJwsAlgorithm) -> JwsAlgorithm {
+        match alg {
+            CJwsAlgorithm::HS256 => JwsAlgorithm::HS256,
+            CJwsAlgorithm::HS384 => JwsAlgorithm::HS384,
+            CJwsAlgorithm::HS512 => JwsAlgorithm::HS512,
+            CJwsAlgorithm::RS256 => JwsAlgorithm::RS256,
+            CJwsAlgorithm::ES256 => JwsAlgorithm::ES256,
+            CJwsAlgorithm::EdDSA => JwsAlgorithm::EdDSA,
+        }
+    }
+
+    fn optional_c_string(s: *const c_char) -> Option<String> {
+        if s.is_null() {
+            None
+        } else {
+            Some(unsafe { CStr::from_ptr(s).to_string_lossy().into_owned() })
+        }
+    }
+
+    // C接口：签发 JWT，结果需用 free_c_string 释放
+    // HS* 的 key 为共享密钥原始字节；RS256 / ES256 / EdDSA 的 key 为 PEM 私钥
+    #[unsafe(no_mangle)]
+    pub extern "C" fn jwt_sign_c(
+        alg: CJwsAlgorithm,
         key: *const u8,
         key_len: usize,
-        ciphertext: *const c_char,
-        plaintext_out: *mut *mut c_char,
+        claims_json: *const c_char,
+        out_token: *mut *mut c_char,
     ) -> CryptoErrorCode {
-        if key.is_null() || ciphertext.is_null() || plaintext_out.is_null() {
+        if key.is_null() || claims_json.is_null() || out_token.is_null() {
             return CryptoErrorCode::NullPointerError;
         }
 
+        let alg = c_jws_algorithm(alg);
         let key_slice = unsafe { std::slice::from_raw_parts(key, key_len) };
-        let ciphertext_str = unsafe { CStr::from_ptr(ciphertext).to_string_lossy().into_owned() };
+        let claims_str = unsafe { CStr::from_ptr(claims_json).to_string_lossy() };
+        let result = serde_json::from_str::<serde_json::Value>(&claims_str)
+            .map_err(|e| CryptoError::InvalidToken(e.to_string()))
+            .and_then(|claims| match alg {
+                JwsAlgorithm::RS256 => {
+                    let rsa = RsaPrivateKey::from_pem(key_slice, None)?;
+                    jwt_sign(alg, &JwtSigner::Rsa(&rsa), &claims)
+                }
+                JwsAlgorithm::ES256 | JwsAlgorithm::EdDSA => {
+                    let algorithm = alg
+                        .signature_algorithm()
+                        .unwrap_or(SignatureAlgorithm::Ed25519);
+                    let signing = SigningKey::from_bytes(algorithm, key_slice, KeyFormat::Pem)?;
+                    jwt_sign(alg, &JwtSigner::Key(&signing), &claims)
+                }
+                _ => jwt_sign(alg, &JwtSigner::Hmac(key_slice), &claims),
+            });
 
-        let result = decrypt_from_base64(key_slice, &ciphertext_str);
         match result {
-            Ok(plaintext_bytes) => {
-                let plaintext_str = match String::from_utf8(plaintext_bytes) {
-                    Ok(s) => s,
-                    Err(_e) => return CryptoErrorCode::Utf8DecodingFailed,
-                };
-                let c_str = match CString::new(plaintext_str) {
-                    Ok(s) => s,
-                    Err(_) => return CryptoErrorCode::Base64DecodeError,
-                };
-                unsafe { *plaintext_out = c_str.into_raw() };
-                CryptoErrorCode::Success
-            }
+            Ok(token) => match CString::new(token) {
+                Ok(c_str) => {
+                    unsafe { *out_token = c_str.into_raw() };
+                    CryptoErrorCode::Success
+                }
+                Err(_) => CryptoErrorCode::Base64EncodeError,
+            },
             Err(e) => crypto_error_to_code(&e),
         }
     }
 
-    // C接口：AES-CBC-192加密
+    // C接口：验证 JWT 并以 JSON 字符串返回 claims，结果需用 free_c_string 释放
+    // HS* 的 key 为共享密钥原始字节；RS256 / ES256 / EdDSA 的 key 为 PEM 公钥；
+    // validation 为空指针时使用默认选项（60 秒偏差，要求 exp）
     #[unsafe(no_mangle)]
-    pub extern "C" fn aes_cbc192_encrypt(
+    pub extern "C" fn jwt_verify_c(
+        token: *const c_char,
+        alg: CJwsAlgorithm,
         key: *const u8,
         key_len: usize,
-        plaintext: *const c_char,
-        mode: CAesCbcMode,
-        ciphertext_out: *mut *mut c_char,
+        validation: *const CJwtValidation,
+        out_claims_json: *mut *mut c_char,
     ) -> CryptoErrorCode {
-        if key.is_null() || plaintext.is_null() || ciphertext_out.is_null() {
+        if token.is_null() || key.is_null() || out_claims_json.is_null() {
             return CryptoErrorCode::NullPointerError;
         }
 
-        if key_len != AES_192_KEY_LEN {
-            return CryptoErrorCode::UnsupportedKeyLength;
-        }
-
+        let alg = c_jws_algorithm(alg);
         let key_slice = unsafe { std::slice::from_raw_parts(key, key_len) };
-        let plaintext_str = unsafe { CStr::from_ptr(plaintext).to_string_lossy().into_owned() };
-        let rust_mode = match mode {
-            CAesCbcMode::FixedIv => AesCbcMode::FixedIv,
-            CAesCbcMode::RandomIv => AesCbcMode::RandomIv,
+        let token_str = unsafe { CStr::from_ptr(token).to_string_lossy() };
+        let options = match unsafe { validation.as_ref() } {
+            Some(v) => JwtValidation {
+                leeway: v.leeway_secs,
+                audience: optional_c_string(v.audience),
+                issuer: optional_c_string(v.issuer),
+                require_exp: v.require_exp,
+                now: None,
+            },
+            None => JwtValidation::default(),
+        };
+
+        let result = match alg {
+            JwsAlgorithm::RS256 => RsaPublicKey::from_pem(key_slice)
+                .and_then(|rsa| jwt_verify(&token_str, alg, &JwtVerifier::Rsa(&rsa), &options)),
+            JwsAlgorithm::ES256 | JwsAlgorithm::EdDSA => {
+                let algorithm = alg
+                    .signature_algorithm()
+                    .unwrap_or(SignatureAlgorithm::Ed25519);
+                VerifyingKey::from_bytes(algorithm, key_slice, KeyFormat::Pem).and_then(
+                    |verifying| {
+                        jwt_verify(&token_str, alg, &JwtVerifier::Key(&verifying), &options)
+                    },
+                )
+            }
+            _ => jwt_verify(&token_str, alg, &JwtVerifier::Hmac(key_slice), &options),
         };
 
-        let result = encrypt_cbc_192(key_slice, &plaintext_str, rust_mode);
         match result {
-            Ok(ciphertext) => {
-                let c_str = match CString::new(ciphertext) {
-                    Ok(s) => s,
-                    Err(_) => return CryptoErrorCode::Base64EncodeError,
-                };
-                unsafe { *ciphertext_out = c_str.into_raw() };
+            Ok(claims) => match CString::new(claims.to_string()) {
+                Ok(c_str) => {
+                    unsafe { *out_claims_json = c_str.into_raw() };
+                    CryptoErrorCode::Success
+                }
+                Err(_) => CryptoErrorCode::InvalidToken,
+            },
+            Err(e) => crypto_error_to_code(&e),
+        }
+    }
+
+    // C接口：解析证书（PEM 或 DER），以 JSON 返回主题、SAN、有效期和指纹，需用 free_c_string 释放
+    #[unsafe(no_mangle)]
+    pub extern "C" fn x509_certificate_info(
+        cert: *const u8,
+        cert_len: usize,
+        out_json: *mut *mut c_char,
+    ) -> CryptoErrorCode {
+        if cert.is_null() || out_json.is_null() {
+            return CryptoErrorCode::NullPointerError;
+        }
+
+        let cert_slice = unsafe { std::slice::from_raw_parts(cert, cert_len) };
+        let info = match Certificate::from_bytes(cert_slice).and_then(|c| c.info()) {
+            Ok(info) => info,
+            Err(e) => return crypto_error_to_code(&e),
+        };
+        let json = match serde_json::to_string(&info) {
+            Ok(j) => j,
+            Err(e) => return crypto_error_to_code(&CryptoError::CertificateError(e.to_string())),
+        };
+        match CString::new(json) {
+            Ok(c_str) => {
+                unsafe { *out_json = c_str.into_raw() };
                 CryptoErrorCode::Success
             }
-            Err(e) => crypto_error_to_code(&e),
+            Err(_) => CryptoErrorCode::CertificateError,
         }
     }
 
-    // C接口：AES-CBC-192解密
+    // C接口：离线验证证书链
+    // leaf 为 PEM 或 DER；intermediates 为 PEM 证书包，可为空；ca_bundle 为 PEM 格式的受信任 CA；
+    // at_time 为验证时间（Unix 秒），小于等于 0 时使用当前时间
     #[unsafe(no_mangle)]
-    pub extern "C" fn aes_cbc192_decrypt(
-        key: *const u8,
-        key_len: usize,
-        ciphertext: *const c_char,
-        plaintext_out: *mut *mut c_char,
+    pub extern "C" fn x509_verify_chain(
+        leaf: *const u8,
+        leaf_len: usize,
+        intermediates: *const u8,
+        intermediates_len: usize,
+        ca_bundle: *const u8,
+        ca_bundle_len: usize,
+        at_time: i64,
+    ) -> CryptoErrorCode {
+        if leaf.is_null() || ca_bundle.is_null() {
+            return CryptoErrorCode::NullPointerError;
+        }
+
+        let leaf_slice = unsafe { std::slice::from_raw_parts(leaf, leaf_len) };
+        let ca_slice = unsafe { std::slice::from_raw_parts(ca_bundle, ca_bundle_len) };
+        let intermediates_slice = if intermediates.is_null() || intermediates_len == 0 {
+            None
+        } else {
+            Some(unsafe { std::slice::from_raw_parts(intermediates, intermediates_len) })
+        };
+
+        let result = (|| {
+            let leaf = Certificate::from_bytes(leaf_slice)?;
+            let roots = Certificate::bundle_from_pem(ca_slice)?;
+            let chain = match intermediates_slice {
+                Some(pem) => Certificate::bundle_from_pem(pem)?,
+                None => Vec::new(),
+            };
+            let time = if at_time > 0 { Some(at_time) } else { None };
+            verify_certificate_chain(&leaf, &chain, &roots, time)
+        })();
+
+        match result {
+            Ok(()) => CryptoErrorCode::Success,
+            Err(e) => crypto_error_to_code(&e),
+        }
+    }
+
+    // C接口：用 PEM 私钥（RSA / P-256 / Ed25519）生成 CSR，需用 free_c_string 释放
+    // dns_names 可为空；subject 为 OpenSSL 单行格式，例如 "CN=device-1,O=FirmNetter"
+    #[unsafe(no_mangle)]
+    pub extern "C" fn x509_create_csr(
+        private_key_pem: *const u8,
+        private_key_len: usize,
+        subject: *const c_char,
+        dns_names: *const *const c_char,
+        dns_count: usize,
+        out_pem: *mut *mut c_char,
     ) -> CryptoErrorCode {
-        if key.is_null() || ciphertext.is_null() || plaintext_out.is_null() {
+        if private_key_pem.is_null() || subject.is_null() || out_pem.is_null() {
             return CryptoErrorCode::NullPointerError;
         }
-
-        if key_len != AES_192_KEY_LEN {
-            return CryptoErrorCode::UnsupportedKeyLength;
+        if dns_names.is_null() && dns_count != 0 {
+            return CryptoErrorCode::NullPointerError;
         }
 
-        let key_slice = unsafe { std::slice::from_raw_parts(key, key_len) };
-        let ciphertext_str = unsafe { CStr::from_ptr(ciphertext).to_string_lossy().into_owned() };
+        let key_slice = unsafe { std::slice::from_raw_parts(private_key_pem, private_key_len) };
+        let subject_str = unsafe { CStr::from_ptr(subject).to_string_lossy() };
+        let mut request = CsrRequest::default();
+        for part in subject_str.split(',') {
+            match part.split_once('=') {
+                Some((field, value)) => request
+                    .subject
+                    .push((field.trim().to_string(), value.trim().to_string())),
+                None => return CryptoErrorCode::CertificateError,
+            }
+        }
+        if dns_count > 0 {
+            let names = unsafe { std::slice::from_raw_parts(dns_names, dns_count) };
+            for &name in names {
+                match optional_c_string(name) {
+                    Some(n) => request.dns_names.push(n),
+                    None => return CryptoErrorCode::NullPointerError,
+                }
+            }
+        }
 
-        let result = decrypt_cbc_192(key_slice, &ciphertext_str);
+        let result = PKey::private_key_from_pem(key_slice)
+            .map_err(|e| CryptoError::InvalidKey(e.to_string()))
+            .and_then(|pkey| build_csr(&pkey, &request));
         match result {
-            Ok(plaintext) => {
-                let c_str = match CString::new(plaintext) {
-                    Ok(s) => s,
-                    Err(e) => {
-                        return crypto_error_to_code(&CryptoError::Utf8DecodingFailed(
-                            e.to_string(),
-                        ));
-                    }
-                };
-                unsafe { *plaintext_out = c_str.into_raw() };
-                CryptoErrorCode::Success
-            }
+            Ok(pem) => write_pem_string(pem, out_pem),
             Err(e) => crypto_error_to_code(&e),
         }
     }
 
-    // C接口：Base64编码
+    // C接口：常量时间比较，相等返回 true
     #[unsafe(no_mangle)]
-    pub extern "C" fn base64_encode_c(
-        data: *const u8,
-        data_len: usize,
-        out_str: *mut *mut c_char,
+    pub extern "C" fn constant_time_eq_c(
+        a: *const u8,
+        a_len: usize,
+        b: *const u8,
+        b_len: usize,
+    ) -> bool {
+        match (optional_slice(a, a_len), optional_slice(b, b_len)) {
+            (Some(a_slice), Some(b_slice)) => constant_time_eq(a_slice, b_slice),
+            _ => false,
+        }
+    }
+
+    // C接口：计算口令哈希（PHC 字符串），params 为空时使用默认参数，需用 free_c_string 释放
+    #[unsafe(no_mangle)]
+    pub extern "C" fn password_hash_c(
+        password: *const u8,
+        password_len: usize,
+        params: *const CKdfParams,
+        out_phc: *mut *mut c_char,
     ) -> CryptoErrorCode {
-        if data.is_null() || out_str.is_null() {
+        if password.is_null() || out_phc.is_null() {
             return CryptoErrorCode::NullPointerError;
         }
 
-        let data_slice = unsafe { std::slice::from_raw_parts(data, data_len) };
-        let encoded = base64_encode(data_slice);
-        let c_str = match CString::new(encoded) {
-            Ok(s) => s,
-            Err(_) => return CryptoErrorCode::Base64EncodeError,
+        let password_slice = unsafe { std::slice::from_raw_parts(password, password_len) };
+        let rust_params = if params.is_null() {
+            KdfParams::default()
+        } else {
+            unsafe { *params }.into()
         };
-
-        unsafe { *out_str = c_str.into_raw() };
-        CryptoErrorCode::Success
+        match hash_password(password_slice, &rust_params) {
+            Ok(phc) => match CString::new(phc) {
+                Ok(c_str) => {
+                    unsafe { *out_phc = c_str.into_raw() };
+                    CryptoErrorCode::Success
+                }
+                Err(_) => CryptoErrorCode::InvalidPasswordHash,
+            },
+            Err(e) => crypto_error_to_code(&e),
+        }
     }
 
-    // C接口：Base64解码
+    // C接口：校验口令
+    // 口令正确返回 Success，错误返回 PasswordMismatch，哈希串无效返回 InvalidPasswordHash；
+    // params 为当前哈希策略（为空时使用默认参数），out_needs_rehash 可为空
     #[unsafe(no_mangle)]
-    pub extern "C" fn base64_decode_c(
-        data_str: *const c_char,
-        out_data: *mut *mut u8,
-        out_len: *mut usize,
+    pub extern "C" fn password_verify_c(
+        password: *const u8,
+        password_len: usize,
+        phc: *const c_char,
+        params: *const CKdfParams,
+        out_needs_rehash: *mut bool,
     ) -> CryptoErrorCode {
-        if data_str.is_null() || out_data.is_null() || out_len.is_null() {
+        if password.is_null() || phc.is_null() {
             return CryptoErrorCode::NullPointerError;
         }
 
-        let data = unsafe { CStr::from_ptr(data_str).to_string_lossy().into_owned() };
-        let decoded = match base64_decode(&data) {
-            Ok(d) => d,
-            Err(e) => return crypto_error_to_code(&e),
+        let password_slice = unsafe { std::slice::from_raw_parts(password, password_len) };
+        let phc_str = unsafe { CStr::from_ptr(phc).to_string_lossy() };
+        let rust_params = if params.is_null() {
+            KdfParams::default()
+        } else {
+            unsafe { *params }.into()
         };
-
-        unsafe {
-            *out_len = decoded.len();
-            let mut buf = vec![0u8; decoded.len()].into_boxed_slice();
-            buf.copy_from_slice(&decoded);
-            *out_data = buf.as_mut_ptr();
-            std::mem::forget(buf); // 转移所有权给C端
+        match verify_password(password_slice, &phc_str, &rust_params) {
+            Ok(result) => {
+                if !out_needs_rehash.is_null() {
+                    unsafe {
+                        *out_needs_rehash = result == PasswordVerification::ValidNeedsRehash;
+                    }
+                }
+                CryptoErrorCode::Success
+            }
+            Err(e) => crypto_error_to_code(&e),
         }
-        CryptoErrorCode::Success
     }
 
     // C接口：释放C字符串
@@ -630,6 +6170,28 @@ pub mod safe {
         }
     }
 
+    // C接口：清零后释放存放密钥或明文的字节缓冲区，可替代 free_byte_buffer
+    #[unsafe(no_mangle)]
+    pub extern "C" fn free_secret_buffer(buf: *mut u8, len: usize) {
+        unsafe {
+            if !buf.is_null() {
+                let mut data = Vec::from_raw_parts(buf, len, len);
+                data.zeroize();
+            }
+        }
+    }
+
+    // C接口：清零后释放存放密钥的C字符串（如 generate_aes256_key_base64 的结果）
+    #[unsafe(no_mangle)]
+    pub extern "C" fn free_secret_string(s: *mut c_char) {
+        unsafe {
+            if !s.is_null() {
+                let mut data = CString::from_raw(s).into_bytes_with_nul();
+                data.zeroize();
+            }
+        }
+    }
+
     // 测试代码（保持不变）
     #[cfg(test)]
     mod tests {
@@ -646,11 +6208,18 @@ pub mod safe {
         }
 
         #[test]
+        #[allow(deprecated)]
         fn test_cbc_192_fixed_iv() {
             let key = generate_key::<AES_192_KEY_LEN>().unwrap();
             let plaintext = "Fixed IV Test";
 
-            let ciphertext = encrypt_cbc_192(&key, plaintext, AesCbcMode::FixedIv).unwrap();
+            // 新的固定IV加密需要显式选择旧格式接口
+            assert!(matches!(
+                encrypt_cbc_192(&key, plaintext, AesCbcMode::FixedIv),
+                Err(CryptoError::FixedIvEncryptionDisabled)
+            ));
+            let ciphertext =
+                base64_encode(&encrypt_cbc_legacy_fixed_iv(&key, plaintext.as_bytes()).unwrap());
             let decrypted = decrypt_cbc_192(&key, &ciphertext).unwrap();
             assert_eq!(plaintext, decrypted);
         }
@@ -666,11 +6235,990 @@ pub mod safe {
         }
 
         #[test]
+        #[allow(deprecated)]
         fn test_invalid_key_length() {
             let key = "shortkey".as_bytes(); // 10字节
             let plaintext = "Test";
 
             assert!(encrypt_cbc_192(key, plaintext, AesCbcMode::FixedIv).is_err());
         }
+        #[test]
+        fn test_pbkdf2_known_vector() {
+            // RFC 7914 第11节 PBKDF2-HMAC-SHA256 测试向量
+            let params = KdfParams::Pbkdf2Sha256 { iterations: 1 };
+            let key = derive_key_from_password(b"passwd", b"salt", &params, 64).unwrap();
+            assert_eq!(
+                hex::encode(key),
+                "55ac046e56e3089fec1691c22544b605f94185216dde0465e68b9d57c20dacbc\
+                 49ca9cccf179b645991664b39d77ef317c71b845b1e30bd509112041d3a19783"
+            );
+        }
+
+        #[test]
+        fn test_password_encryption_roundtrip() {
+            let all_params = [
+                KdfParams::Pbkdf2Sha256 { iterations: 1000 },
+                KdfParams::Scrypt {
+                    log_n: 10,
+                    r: 8,
+                    p: 1,
+                },
+                KdfParams::Argon2id {
+                    iterations: 1,
+                    memory_kib: 1024,
+                    lanes: 1,
+                },
+            ];
+
+            for params in all_params {
+                let ciphertext =
+                    encrypt_with_password_to_base64(b"correct horse", b"secret export", &params)
+                        .unwrap();
+                let decrypted =
+                    decrypt_with_password_from_base64(b"correct horse", &ciphertext).unwrap();
+                assert_eq!(decrypted, b"secret export");
+
+                assert!(matches!(
+                    decrypt_with_password_from_base64(b"wrong horse", &ciphertext),
+                    Err(CryptoError::TagVerificationFailed)
+                ));
+            }
+        }
+
+        #[test]
+        fn test_password_decrypt_rejects_excessive_params() {
+            let params = KdfParams::Pbkdf2Sha256 { iterations: 1000 };
+            let mut ciphertext = encrypt_with_password(b"pw", b"data", &params).unwrap();
+            ciphertext[2..6].copy_from_slice(&u32::MAX.to_be_bytes());
+
+            assert!(matches!(
+                decrypt_with_password(b"pw", &ciphertext),
+                Err(CryptoError::InvalidKdfParameters(_))
+            ));
+
+            // Argon2id：4 GiB 内存、超出上限的 lanes 都在派生前被拒绝
+            let params = KdfParams::Argon2id {
+                iterations: 1,
+                memory_kib: 64,
+                lanes: 1,
+            };
+            let ciphertext = encrypt_with_password(b"pw", b"data", &params).unwrap();
+            for words in [
+                [MAX_ARGON2_ITERATIONS, 4 * 1024 * 1024, 1],
+                [1, MAX_ARGON2_MEMORY_KIB + 1, 1],
+                [1, MAX_ARGON2_MEMORY_KIB, MAX_ARGON2_LANES + 1],
+                [MAX_ARGON2_ITERATIONS + 1, 64, 1],
+            ] {
+                let mut tampered = ciphertext.clone();
+                for (i, word) in words.iter().enumerate() {
+                    tampered[2 + i * 4..6 + i * 4].copy_from_slice(&word.to_be_bytes());
+                }
+                assert!(matches!(
+                    decrypt_with_password(b"pw", &tampered),
+                    Err(CryptoError::InvalidKdfParameters(_))
+                ));
+            }
+        }
+
+        #[test]
+        fn test_kdf_params_limits() {
+            let rejected = [
+                // 16 GiB
+                KdfParams::Scrypt {
+                    log_n: 24,
+                    r: 8,
+                    p: 1,
+                },
+                KdfParams::Scrypt {
+                    log_n: 20,
+                    r: 1024,
+                    p: 1,
+                },
+                KdfParams::Scrypt {
+                    log_n: 10,
+                    r: 1,
+                    p: 1 << 26,
+                },
+                KdfParams::Argon2id {
+                    iterations: u32::MAX,
+                    memory_kib: 64,
+                    lanes: 1,
+                },
+                KdfParams::Argon2id {
+                    iterations: 1,
+                    memory_kib: 64,
+                    lanes: 1 << 29,
+                },
+                KdfParams::Argon2id {
+                    iterations: 1,
+                    memory_kib: MAX_ARGON2_MEMORY_KIB,
+                    lanes: MAX_ARGON2_LANES + 1,
+                },
+                KdfParams::Argon2id {
+                    iterations: 1,
+                    memory_kib: 8,
+                    lanes: 2,
+                },
+            ];
+            for params in rejected {
+                assert!(
+                    matches!(params.validate(), Err(CryptoError::InvalidKdfParameters(_))),
+                    "{:?}",
+                    params
+                );
+            }
+
+            for algorithm in [
+                KdfAlgorithm::Pbkdf2Sha256,
+                KdfAlgorithm::Scrypt,
+                KdfAlgorithm::Argon2id,
+            ] {
+                assert!(KdfParams::default_for(algorithm).validate().is_ok());
+            }
+            assert!(
+                KdfParams::Scrypt {
+                    log_n: 10,
+                    r: 8,
+                    p: MAX_SCRYPT_PARALLELISM
+                }
+                .validate()
+                .is_ok()
+            );
+
+            // 密文头部中的 scrypt 参数超限时不会尝试分配内存
+            let params = KdfParams::Scrypt {
+                log_n: 10,
+                r: 8,
+                p: 1,
+            };
+            let mut ciphertext = encrypt_with_password(b"pw", b"data", &params).unwrap();
+            ciphertext[2..6].copy_from_slice(&24u32.to_be_bytes());
+            assert!(matches!(
+                decrypt_with_password(b"pw", &ciphertext),
+                Err(CryptoError::InvalidKdfParameters(_))
+            ));
+        }
+
+        #[test]
+        fn test_hkdf_rfc5869_case1() {
+            let ikm = [0x0bu8; 22];
+            let salt = hex::decode("000102030405060708090a0b0c").unwrap();
+            let info = hex::decode("f0f1f2f3f4f5f6f7f8f9").unwrap();
+
+            let prk = hkdf_extract(HkdfHash::Sha256, &salt, &ikm).unwrap();
+            assert_eq!(
+                hex::encode(&prk),
+                "077709362c2e32df0ddc3f0dc47bba6390b6c73bb50f9c3122ec844ad7c2b3e5"
+            );
+
+            let expected = "3cb25f25faacd57a90434f64d0362f2a2d2d0a90cf1a5a4c5db02d56ecc4c5bf\
+                            34007208d5b887185865";
+            let okm = hkdf_expand(HkdfHash::Sha256, &prk, &info, 42).unwrap();
+            assert_eq!(hex::encode(&okm), expected);
+            let okm = hkdf(HkdfHash::Sha256, &salt, &ikm, &info, 42).unwrap();
+            assert_eq!(hex::encode(&okm), expected);
+        }
+
+        #[test]
+        fn test_key_hierarchy() {
+            let master = generate_key::<AES_256_KEY_LEN>().unwrap();
+            let hierarchy = KeyHierarchy::new(&master, b"").unwrap();
+
+            let a = hierarchy.derive_key::<32>("license", b"device-a").unwrap();
+            let b = hierarchy.derive_key::<32>("license", b"device-b").unwrap();
+            let a_again = hierarchy.derive_key::<32>("license", b"device-a").unwrap();
+            assert_eq!(a, a_again);
+            assert_ne!(a, b);
+
+            // 不同长度的密钥相互独立，而不是同一输出的前缀
+            let short = hierarchy.derive_key::<16>("license", b"device-a").unwrap();
+            assert_ne!(&a[..16], &short[..]);
+
+            let child = hierarchy.child("product", b"x").unwrap();
+            assert_ne!(
+                child.derive_key::<24>("license", b"device-a").unwrap()[..],
+                a[..24]
+            );
+
+            assert!(hierarchy.derive_key::<20>("license", b"").is_err());
+        }
+
+        #[test]
+        fn test_ed25519_rfc8032_vector() {
+            let secret =
+                hex::decode("9d61b19deffd5a60ba844af492ec2cc44449c5697b326919703bac031cae7f60")
+                    .unwrap();
+            let key = SigningKey::from_bytes(SignatureAlgorithm::Ed25519, &secret, KeyFormat::Raw)
+                .unwrap();
+            assert_eq!(
+                hex::encode(
+                    key.verifying_key()
+                        .unwrap()
+                        .to_bytes(KeyFormat::Raw)
+                        .unwrap()
+                ),
+                "d75a980182b10ab7d54bfed3c964073a0ee172f3daa62325af021a68f707511a"
+            );
+            assert_eq!(
+                hex::encode(key.sign(b"").unwrap()),
+                "e5564300c360ac729086e2cc806e828a84877f1eb8e5d974d873e065224901555\
+                 fb8821590a33bacc61e39701cf9b46bd25bf5f0595bbe24655141438e7a100b"
+            );
+        }
+
+        #[test]
+        fn test_signature_roundtrip_and_key_formats() {
+            for algorithm in [SignatureAlgorithm::Ed25519, SignatureAlgorithm::EcdsaP256] {
+                let key = SigningKey::generate(algorithm).unwrap();
+                let signature = key.sign(b"firmware").unwrap();
+
+                for format in [KeyFormat::Pem, KeyFormat::Der, KeyFormat::Raw] {
+                    let private = key.to_bytes(format).unwrap();
+                    let public = key.verifying_key().unwrap().to_bytes(format).unwrap();
+                    let imported = SigningKey::from_bytes(algorithm, &private, format).unwrap();
+                    let verifier = VerifyingKey::from_bytes(algorithm, &public, format).unwrap();
+
+                    verifier.verify(b"firmware", &signature).unwrap();
+                    verifier
+                        .verify(b"firmware", &imported.sign(b"firmware").unwrap())
+                        .unwrap();
+                    assert!(matches!(
+                        verifier.verify(b"firmwarf", &signature),
+                        Err(CryptoError::SignatureVerificationFailed)
+                    ));
+                }
+            }
+
+            let ed_pem = SigningKey::generate(SignatureAlgorithm::Ed25519)
+                .unwrap()
+                .to_bytes(KeyFormat::Pem)
+                .unwrap();
+            assert!(matches!(
+                SigningKey::from_bytes(SignatureAlgorithm::EcdsaP256, &ed_pem, KeyFormat::Pem),
+                Err(CryptoError::InvalidKey(_))
+            ));
+        }
+
+        #[test]
+        fn test_verify_file_signature() {
+            let path = std::env::temp_dir()
+                .join(format!("firm_netter_sig_test_{}.bin", std::process::id()));
+            let content: Vec<u8> = (0..200_000u32).map(|i| i as u8).collect();
+            std::fs::write(&path, &content).unwrap();
+
+            for algorithm in [SignatureAlgorithm::Ed25519, SignatureAlgorithm::EcdsaP256] {
+                let key = SigningKey::generate(algorithm).unwrap();
+                let verifier = key.verifying_key().unwrap();
+                let signature = key.sign(&content).unwrap();
+
+                verifier.verify_file(&path, &signature).unwrap();
+                verifier
+                    .verify(&content, &key.sign_file(&path).unwrap())
+                    .unwrap();
+                assert!(
+                    verifier
+                        .verify_file(&path, &key.sign(b"other").unwrap())
+                        .is_err()
+                );
+            }
+
+            std::fs::remove_file(&path).unwrap();
+        }
+
+        #[test]
+        fn test_hybrid_encryption_roundtrip() {
+            for algorithm in [
+                KeyAgreementAlgorithm::X25519,
+                KeyAgreementAlgorithm::EcdhP256,
+            ] {
+                let backend = HybridPrivateKey::generate(algorithm).unwrap();
+                let public_pem = backend.public_key().unwrap().to_pem().unwrap();
+
+                // 设备端只持有公钥 PEM
+                let device_key = HybridPublicKey::from_pem(&public_pem).unwrap();
+                assert_eq!(device_key.algorithm(), algorithm);
+                let report = hybrid_encrypt_to_base64(&device_key, b"{\"cpu\":\"x\"}").unwrap();
+
+                let restored = HybridPrivateKey::from_pem(&backend.to_pem().unwrap()).unwrap();
+                let plaintext = hybrid_decrypt_from_base64(&restored, &report).unwrap();
+                assert_eq!(plaintext, b"{\"cpu\":\"x\"}");
+
+                let other = HybridPrivateKey::generate(algorithm).unwrap();
+                assert!(matches!(
+                    hybrid_decrypt_from_base64(&other, &report),
+                    Err(CryptoError::TagVerificationFailed)
+                ));
+            }
+        }
+
+        #[test]
+        fn test_rsa_oaep_and_pss() {
+            let key = RsaPrivateKey::generate(2048).unwrap();
+            assert_eq!(key.bits(), 2048);
+            let public = key.public_key().unwrap();
+
+            let session_key = generate_key::<AES_256_KEY_LEN>().unwrap();
+            let wrapped = public.encrypt_oaep(&session_key, RsaHash::Sha256).unwrap();
+            assert_eq!(
+                key.decrypt_oaep(&wrapped, RsaHash::Sha256).unwrap(),
+                session_key
+            );
+            assert!(key.decrypt_oaep(&wrapped, RsaHash::Sha1).is_err());
+
+            let too_long = vec![0u8; public.max_oaep_plaintext_len(RsaHash::Sha256) + 1];
+            assert!(matches!(
+                public.encrypt_oaep(&too_long, RsaHash::Sha256),
+                Err(CryptoError::RsaPlaintextTooLong { max: 190, .. })
+            ));
+
+            let signature = key.sign_pss(b"request body", RsaHash::Sha256).unwrap();
+            public
+                .verify_pss(b"request body", &signature, RsaHash::Sha256)
+                .unwrap();
+            assert!(matches!(
+                public.verify_pss(b"request bodz", &signature, RsaHash::Sha256),
+                Err(CryptoError::SignatureVerificationFailed)
+            ));
+
+            assert!(matches!(
+                RsaPrivateKey::generate(1024),
+                Err(CryptoError::UnsupportedRsaKeySize { bits: 1024 })
+            ));
+        }
+
+        #[test]
+        fn test_rsa_key_import_export() {
+            let key = RsaPrivateKey::generate(2048).unwrap();
+            let public = key.public_key().unwrap();
+
+            let pem = key.to_pem(Some(b"partner")).unwrap();
+            assert!(String::from_utf8_lossy(&pem).contains("ENCRYPTED PRIVATE KEY"));
+            let restored = RsaPrivateKey::from_pem(&pem, Some(b"partner")).unwrap();
+            assert!(matches!(
+                RsaPrivateKey::from_pem(&pem, Some(b"wrong")),
+                Err(CryptoError::KeyDecryptionFailed)
+            ));
+
+            let der = restored.to_der(Some(b"partner")).unwrap();
+            let restored = RsaPrivateKey::from_der(&der, Some(b"partner")).unwrap();
+            let restored = RsaPrivateKey::from_der(&restored.to_der(None).unwrap(), None).unwrap();
+
+            let public_der = RsaPublicKey::from_der(&public.to_der().unwrap()).unwrap();
+            let public_pem = RsaPublicKey::from_pem(&public_der.to_pem().unwrap()).unwrap();
+            let signature = restored.sign_pss(b"x", RsaHash::Sha512).unwrap();
+            public_pem
+                .verify_pss(b"x", &signature, RsaHash::Sha512)
+                .unwrap();
+
+            let ed_pem = SigningKey::generate(SignatureAlgorithm::Ed25519)
+                .unwrap()
+                .to_bytes(KeyFormat::Pem)
+                .unwrap();
+            assert!(RsaPrivateKey::from_pem(&ed_pem, None).is_err());
+        }
+
+        #[test]
+        #[allow(deprecated)]
+        fn test_cbc_all_key_sizes_binary() {
+            let plaintext: Vec<u8> = (0..=255u8).collect();
+            for key in [
+                generate_key::<AES_128_KEY_LEN>().unwrap().to_vec(),
+                generate_key::<AES_192_KEY_LEN>().unwrap().to_vec(),
+                generate_key::<AES_256_KEY_LEN>().unwrap().to_vec(),
+            ] {
+                let ciphertext = encrypt_cbc(&key, &plaintext, AesCbcMode::RandomIv).unwrap();
+                assert_eq!(
+                    decrypt_cbc(&key, &ciphertext, AesCbcMode::RandomIv).unwrap(),
+                    plaintext
+                );
+
+                let legacy = encrypt_cbc_legacy_fixed_iv(&key, &plaintext).unwrap();
+                assert_eq!(
+                    decrypt_cbc(&key, &legacy, AesCbcMode::FixedIv).unwrap(),
+                    plaintext
+                );
+            }
+            assert!(encrypt_cbc(b"shortkey", b"x", AesCbcMode::RandomIv).is_err());
+        }
+
+        #[test]
+        fn test_cbc_hmac_detects_tampering() {
+            let key = generate_key::<AES_256_KEY_LEN>().unwrap();
+            let mac_key = generate_key::<AES_256_KEY_LEN>().unwrap();
+
+            let mut ciphertext = encrypt_cbc_hmac(&key, &mac_key, b"\x00\x01binary").unwrap();
+            assert_eq!(
+                decrypt_cbc_hmac(&key, &mac_key, &ciphertext).unwrap(),
+                b"\x00\x01binary"
+            );
+
+            ciphertext[AES_BLOCK_SIZE] ^= 1;
+            assert!(matches!(
+                decrypt_cbc_hmac(&key, &mac_key, &ciphertext),
+                Err(CryptoError::TagVerificationFailed)
+            ));
+        }
+
+        #[test]
+        fn test_cbc_192_compat_format() {
+            let key = generate_key::<AES_192_KEY_LEN>().unwrap();
+            let legacy = encrypt_cbc_192(&key, "legacy", AesCbcMode::RandomIv).unwrap();
+            let parts: Vec<&str> = legacy.splitn(3, '|').collect();
+
+            let mut raw = base64_decode(parts[1]).unwrap();
+            raw.extend(base64_decode(parts[2]).unwrap());
+            assert_eq!(
+                decrypt_cbc(&key, &raw, AesCbcMode::RandomIv).unwrap(),
+                b"legacy"
+            );
+        }
+
+        #[test]
+        fn test_migrate_legacy_batch() {
+            let old_key = generate_key::<AES_192_KEY_LEN>().unwrap();
+            let new_key = generate_key::<AES_256_KEY_LEN>().unwrap();
+
+            let fixed =
+                base64_encode(&encrypt_cbc_legacy_fixed_iv(&old_key, b"fixed row").unwrap());
+            let random = encrypt_cbc_192(&old_key, "random row", AesCbcMode::RandomIv).unwrap();
+            let items = vec![fixed, "not base64!".to_string(), random];
+
+            let report = migrate_legacy_batch(&old_key, Some(&new_key), &items);
+            assert_eq!(report.total(), 3);
+            assert_eq!(report.failed.len(), 1);
+            assert_eq!(report.failed[0].0, 1);
+
+            let plaintexts: Vec<Vec<u8>> = report
+                .migrated
+                .iter()
+                .map(|(_, c)| decrypt_from_base64(&new_key, c).unwrap())
+                .collect();
+            assert_eq!(
+                plaintexts,
+                vec![b"fixed row".to_vec(), b"random row".to_vec()]
+            );
+
+            // 未提供新密钥时沿用旧密钥
+            let same_key = migrate_legacy_ciphertext(&old_key, None, &items[2]).unwrap();
+            assert_eq!(
+                decrypt_from_base64(&old_key, &same_key).unwrap(),
+                b"random row"
+            );
+        }
+
+        #[test]
+        fn test_secret_key() {
+            let key = SecretKey::generate(AES_256_KEY_LEN).unwrap();
+            assert_eq!(key.len(), AES_256_KEY_LEN);
+            assert!(SecretKey::generate(20).is_err());
+
+            // Debug 输出不包含密钥内容
+            let debug = format!("{:?}", key);
+            assert!(debug.contains("[REDACTED]"));
+            assert!(!debug.contains(&format!("{:?}", key.expose_secret())));
+
+            let restored = SecretKey::from_base64(&key.to_base64()).unwrap();
+            assert_eq!(key, restored);
+            assert_ne!(key, SecretKey::generate(AES_256_KEY_LEN).unwrap());
+            assert_ne!(key, SecretKey::from_slice(&key.expose_secret()[..16]));
+
+            let ciphertext = encrypt(key.expose_secret(), b"secret").unwrap();
+            assert_eq!(
+                decrypt(restored.expose_secret(), &ciphertext).unwrap(),
+                b"secret"
+            );
+
+            // 受 RLIMIT_MEMLOCK 限制，锁定失败时只要求报告错误
+            let mut locked = key.clone();
+            match locked.lock_memory() {
+                Ok(()) => assert!(locked.is_locked()),
+                Err(e) => assert!(matches!(e, CryptoError::MemoryLockFailed(_))),
+            }
+        }
+
+        #[test]
+        fn test_keystore_rotation_and_persistence() {
+            let master = generate_key::<AES_256_KEY_LEN>().unwrap();
+            let path = std::env::temp_dir().join(format!("fn_keystore_{}.bin", std::process::id()));
+
+            let mut keystore = Keystore::with_master_key(&master).unwrap();
+            let v1 = keystore.add_key("orders", AES_128_KEY_LEN).unwrap();
+            assert!(keystore.add_key("orders", AES_128_KEY_LEN).is_err());
+            let old = keystore.encrypt("orders", b"old data").unwrap();
+            assert_eq!(Keystore::ciphertext_key_id(&old).unwrap(), v1);
+
+            let v2 = keystore.rotate_key("orders").unwrap();
+            assert_eq!(v2.version, 2);
+            let new = keystore.encrypt_to_base64("orders", b"new data").unwrap();
+
+            let list = keystore.list();
+            assert_eq!(list.len(), 2);
+            assert_eq!(list[0].status, KeyStatus::DecryptOnly);
+            assert_eq!(list[1].status, KeyStatus::Active);
+            assert_eq!(list[1].key_len, AES_128_KEY_LEN);
+
+            keystore.save(&path).unwrap();
+            assert!(Keystore::open_with_password(&path, b"wrong").is_err());
+            let wrong_key = generate_key::<AES_256_KEY_LEN>().unwrap();
+            assert!(Keystore::open_with_master_key(&path, &wrong_key).is_err());
+
+            let mut reopened = Keystore::open_with_master_key(&path, &master).unwrap();
+            std::fs::remove_file(&path).unwrap();
+            assert_eq!(reopened.list(), list);
+            assert_eq!(reopened.decrypt(&old).unwrap(), b"old data");
+            assert_eq!(reopened.decrypt_from_base64(&new).unwrap(), b"new data");
+
+            reopened.revoke_key(&v1).unwrap();
+            assert!(matches!(
+                reopened.decrypt(&old),
+                Err(CryptoError::KeyRevoked(_))
+            ));
+            assert!(reopened.encrypt("missing", b"x").is_err());
+        }
+
+        #[test]
+        fn test_keystore_password_protection() {
+            let path =
+                std::env::temp_dir().join(format!("fn_keystore_pw_{}.bin", std::process::id()));
+            let params = KdfParams::Pbkdf2Sha256 { iterations: 1000 };
+
+            let mut keystore = Keystore::with_password(b"passphrase", params).unwrap();
+            let legacy = generate_key::<AES_256_KEY_LEN>().unwrap();
+            keystore.import_key("config", &legacy).unwrap();
+            let ciphertext = keystore.encrypt("config", b"payload").unwrap();
+            keystore.save(&path).unwrap();
+
+            let reopened = Keystore::open_with_password(&path, b"passphrase").unwrap();
+            assert!(Keystore::open_with_password(&path, b"other").is_err());
+            std::fs::remove_file(&path).unwrap();
+            assert_eq!(reopened.decrypt(&ciphertext).unwrap(), b"payload");
+            let (_, key) = reopened.active_key("config").unwrap();
+            assert_eq!(key.expose_secret(), legacy);
+        }
+
+        #[test]
+        fn test_keystore_binds_key_id() {
+            // 同一把旧密钥导入为两个名称，改写密文中的密钥 ID 也不能解密
+            let master = generate_key::<AES_256_KEY_LEN>().unwrap();
+            let legacy = generate_key::<AES_256_KEY_LEN>().unwrap();
+            let mut keystore = Keystore::with_master_key(&master).unwrap();
+            keystore.import_key("billing", &legacy).unwrap();
+            keystore.import_key("reports", &legacy).unwrap();
+
+            let ciphertext = keystore.encrypt("billing", b"invoice").unwrap();
+            let mut swapped = ciphertext.clone();
+            swapped[2..2 + "billing".len()].copy_from_slice(b"reports");
+            assert_eq!(
+                Keystore::ciphertext_key_id(&swapped).unwrap().name,
+                "reports"
+            );
+            assert!(matches!(
+                keystore.decrypt(&swapped),
+                Err(CryptoError::TagVerificationFailed)
+            ));
+            assert_eq!(keystore.decrypt(&ciphertext).unwrap(), b"invoice");
+
+            // 加载时拒绝重复的密钥 ID
+            let entry = format!(
+                r#"{{"name":"billing","version":1,"status":"active","created_at":0,"key":"{}"}}"#,
+                base64_encode(&legacy)
+            );
+            let json = format!("[{},{}]", entry, entry);
+            let mut data = KEYSTORE_MAGIC.to_vec();
+            data.push(KEYSTORE_FILE_VERSION);
+            data.push(0);
+            data.extend(encrypt(&master, json.as_bytes()).unwrap());
+            let path =
+                std::env::temp_dir().join(format!("fn_keystore_dup_{}.bin", std::process::id()));
+            std::fs::write(&path, &data).unwrap();
+            let result = Keystore::open_with_master_key(&path, &master);
+            std::fs::remove_file(&path).unwrap();
+            assert!(matches!(result, Err(CryptoError::InvalidKeystore(_))));
+        }
+
+        #[test]
+        fn test_codecs_rfc4648_vectors() {
+            let cases: [(&str, &str, &str); 4] = [
+                ("f", "MY======", "66"),
+                ("fo", "MZXQ====", "666f"),
+                ("foob", "MZXW6YQ=", "666f6f62"),
+                ("foobar", "MZXW6YTBOI======", "666f6f626172"),
+            ];
+            for (plain, base32, hex) in cases {
+                assert_eq!(encode(Encoding::Base32, plain.as_bytes()), base32);
+                assert_eq!(decode(Encoding::Base32, base32).unwrap(), plain.as_bytes());
+                assert_eq!(
+                    decode(Encoding::Base32, &base32.to_lowercase().replace('=', "")).unwrap(),
+                    plain.as_bytes()
+                );
+                assert_eq!(hex_encode(plain.as_bytes()), hex);
+                assert_eq!(hex_decode(&hex.to_uppercase()).unwrap(), plain.as_bytes());
+            }
+            assert!(decode(Encoding::Base32, "MZXW6Y1=").is_err());
+            assert!(hex_decode("abc").is_err());
+
+            let data = [0xfbu8, 0xff, 0xbf];
+            assert_eq!(encode(Encoding::Base64, &data), "+/+/");
+            assert_eq!(encode(Encoding::Base64Url, &data), "-_-_");
+            assert_eq!(encode(Encoding::Base64UrlNoPad, b"fo"), "Zm8");
+            assert_eq!(encode(Encoding::Base64NoPad, b"fo"), "Zm8");
+            assert_eq!(decode(Encoding::Base64UrlNoPad, "-_-_").unwrap(), data);
+
+            let long = vec![0xabu8; 100];
+            let mime = encode(Encoding::Base64Mime, &long);
+            assert_eq!(mime.split("\r\n").next().unwrap().len(), MIME_LINE_LEN);
+            assert_eq!(decode(Encoding::Base64Mime, &mime).unwrap(), long);
+        }
+
+        #[test]
+        fn test_codec_streaming_matches_one_shot() {
+            let data: Vec<u8> = (0..CODEC_STREAM_CHUNK * 2 + 7).map(|i| i as u8).collect();
+            for encoding in [
+                Encoding::Base64,
+                Encoding::Base64UrlNoPad,
+                Encoding::Base64Mime,
+                Encoding::Hex,
+                Encoding::Base32,
+            ] {
+                let mut encoded = Vec::new();
+                encode_stream(encoding, &mut &data[..], &mut encoded).unwrap();
+                assert_eq!(
+                    String::from_utf8(encoded.clone()).unwrap(),
+                    encode(encoding, &data),
+                    "{:?}",
+                    encoding
+                );
+
+                let mut decoded = Vec::new();
+                decode_stream(encoding, &mut &encoded[..], &mut decoded).unwrap();
+                assert_eq!(decoded, data, "{:?}", encoding);
+            }
+        }
+
+        #[test]
+        fn test_jwt_rfc7515_hs256_example() {
+            // RFC 7515 附录 A.1 的示例令牌（exp 为 2011 年，需指定校验时间）
+            let token = "eyJ0eXAiOiJKV1QiLA0KICJhbGciOiJIUzI1NiJ9.\
+                eyJpc3MiOiJqb2UiLA0KICJleHAiOjEzMDA4MTkzODAsDQogImh0dHA6Ly9leGFtcGxlLmNvbS9pc19yb290Ijp0cnVlfQ.\
+                dBjftJeZ4CVP-mB92K27uhbUJU1p1r_wW1gFWFOEjXk";
+            let key = decode(
+                Encoding::Base64UrlNoPad,
+                "AyM1SysPpbyDfgZld3umj1qzKObwVMkoqQ-EstJQLr_T-1qS0gZH75aKtMN3Yj0iPS4hcgUuTwjAzZr1Z9CAow",
+            )
+            .unwrap();
+            let validation = JwtValidation {
+                issuer: Some("joe".into()),
+                now: Some(1300819000),
+                ..Default::default()
+            };
+
+            let claims = jwt_verify(
+                token,
+                JwsAlgorithm::HS256,
+                &JwtVerifier::Hmac(&key),
+                &validation,
+            )
+            .unwrap();
+            assert_eq!(claims["http://example.com/is_root"], true);
+
+            let expired = JwtValidation {
+                now: Some(1300819380 + 61),
+                ..validation.clone()
+            };
+            assert!(matches!(
+                jwt_verify(
+                    token,
+                    JwsAlgorithm::HS256,
+                    &JwtVerifier::Hmac(&key),
+                    &expired
+                ),
+                Err(CryptoError::TokenExpired)
+            ));
+            // 算法必须与调用方期望的一致
+            assert!(
+                jwt_verify(
+                    token,
+                    JwsAlgorithm::HS512,
+                    &JwtVerifier::Hmac(&key),
+                    &validation
+                )
+                .is_err()
+            );
+        }
+
+        #[test]
+        fn test_jwt_asymmetric_roundtrip_and_claims() {
+            let now = 1_700_000_000u64;
+            let claims = serde_json::json!({
+                "sub": "device-1",
+                "iss": "backend",
+                "aud": ["devices", "admin"],
+                "iat": now,
+                "nbf": now,
+                "exp": now + 600,
+            });
+            let validation = JwtValidation {
+                audience: Some("devices".into()),
+                issuer: Some("backend".into()),
+                now: Some(now + 10),
+                ..Default::default()
+            };
+
+            for (alg, sig_alg) in [
+                (JwsAlgorithm::ES256, SignatureAlgorithm::EcdsaP256),
+                (JwsAlgorithm::EdDSA, SignatureAlgorithm::Ed25519),
+            ] {
+                let signing = SigningKey::generate(sig_alg).unwrap();
+                let verifying = signing.verifying_key().unwrap();
+                let token = jwt_sign(alg, &JwtSigner::Key(&signing), &claims).unwrap();
+                let verified =
+                    jwt_verify(&token, alg, &JwtVerifier::Key(&verifying), &validation).unwrap();
+                assert_eq!(verified, claims);
+                if alg == JwsAlgorithm::ES256 {
+                    let signature = token.rsplit('.').next().unwrap();
+                    assert_eq!(
+                        decode(Encoding::Base64UrlNoPad, signature).unwrap().len(),
+                        64
+                    );
+                }
+            }
+
+            let rsa = RsaPrivateKey::generate(2048).unwrap();
+            let token = jwt_sign(JwsAlgorithm::RS256, &JwtSigner::Rsa(&rsa), &claims).unwrap();
+            let public = rsa.public_key().unwrap();
+            let verifier = JwtVerifier::Rsa(&public);
+            jwt_verify(&token, JwsAlgorithm::RS256, &verifier, &validation).unwrap();
+
+            let wrong_aud = JwtValidation {
+                audience: Some("other".into()),
+                ..validation.clone()
+            };
+            assert!(matches!(
+                jwt_verify(&token, JwsAlgorithm::RS256, &verifier, &wrong_aud),
+                Err(CryptoError::InvalidClaim(_))
+            ));
+            let early = JwtValidation {
+                now: Some(now - 120),
+                ..validation.clone()
+            };
+            assert!(matches!(
+                jwt_verify(&token, JwsAlgorithm::RS256, &verifier, &early),
+                Err(CryptoError::TokenNotYetValid)
+            ));
+            // 允许的时钟偏差内仍然有效
+            let skewed = JwtValidation {
+                now: Some(now - 30),
+                ..validation.clone()
+            };
+            jwt_verify(&token, JwsAlgorithm::RS256, &verifier, &skewed).unwrap();
+
+            let mut tampered = token.clone();
+            tampered.insert(token.find('.').unwrap() + 1, 'e');
+            assert!(jwt_verify(&tampered, JwsAlgorithm::RS256, &verifier, &validation).is_err());
+        }
+
+        // 测试用：签发一张证书，issuer 为空时自签名
+        fn issue_test_certificate(
+            cn: &str,
+            key: &PKey<Private>,
+            issuer: Option<(&X509, &PKey<Private>)>,
+            is_ca: bool,
+        ) -> X509 {
+            use openssl::x509::X509Builder;
+            use openssl::x509::extension::BasicConstraints;
+
+            let mut name = X509NameBuilder::new().unwrap();
+            name.append_entry_by_text("CN", cn).unwrap();
+            let name = name.build();
+
+            let mut builder = X509Builder::new().unwrap();
+            builder.set_version(2).unwrap();
+            let serial = BigNum::from_u32(1000).unwrap().to_asn1_integer().unwrap();
+            builder.set_serial_number(&serial).unwrap();
+            builder.set_subject_name(&name).unwrap();
+            builder
+                .set_issuer_name(issuer.map_or(&name, |(cert, _)| cert.subject_name()))
+                .unwrap();
+            builder.set_pubkey(key).unwrap();
+            builder
+                .set_not_before(&Asn1Time::from_unix(1_600_000_000).unwrap())
+                .unwrap();
+            builder
+                .set_not_after(&Asn1Time::from_unix(1_900_000_000).unwrap())
+                .unwrap();
+            if is_ca {
+                let constraints = BasicConstraints::new().critical().ca().build().unwrap();
+                builder.append_extension(constraints).unwrap();
+            } else {
+                let san = SubjectAlternativeName::new()
+                    .dns("device.example.com")
+                    .ip("10.0.0.1")
+                    .build(&builder.x509v3_context(issuer.map(|(c, _)| c.as_ref()), None))
+                    .unwrap();
+                builder.append_extension(san).unwrap();
+            }
+            let signer = issuer.map_or(key, |(_, k)| k);
+            let digest = if signer.id() == Id::ED25519 {
+                MessageDigest::null()
+            } else {
+                MessageDigest::sha256()
+            };
+            builder.sign(signer, digest).unwrap();
+            builder.build()
+        }
+
+        #[test]
+        fn test_certificate_parse_and_chain_verification() {
+            let ca_key = SigningKey::generate(SignatureAlgorithm::EcdsaP256).unwrap();
+            let ca = issue_test_certificate("Test Root", &ca_key.pkey, None, true);
+            let leaf_key = SigningKey::generate(SignatureAlgorithm::EcdsaP256).unwrap();
+            let leaf = issue_test_certificate(
+                "device-1",
+                &leaf_key.pkey,
+                Some((&ca, &ca_key.pkey)),
+                false,
+            );
+
+            let root = Certificate::from_pem(&ca.to_pem().unwrap()).unwrap();
+            let cert = Certificate::from_bytes(&leaf.to_der().unwrap()).unwrap();
+            assert_eq!(cert.common_name().as_deref(), Some("device-1"));
+            assert_eq!(cert.subject(), "CN=device-1");
+            assert_eq!(cert.issuer(), "CN=Test Root");
+            assert_eq!(
+                cert.subject_alt_names(),
+                vec![
+                    SubjectAltName::Dns("device.example.com".into()),
+                    SubjectAltName::Ip("10.0.0.1".parse().unwrap()),
+                ]
+            );
+            assert_eq!(cert.not_before().unwrap(), 1_600_000_000);
+            assert_eq!(cert.not_after().unwrap(), 1_900_000_000);
+            assert_eq!(cert.serial_number().unwrap(), "03E8");
+
+            let info = cert.info().unwrap();
+            let pin = CertificatePin::parse(&info.fingerprint_sha256).unwrap();
+            assert!(pin.matches(&cert));
+            assert!(!pin.matches(&root));
+            let spki_pin = format!("sha256/{}", base64_encode(&cert.spki_sha256().unwrap()));
+            assert!(CertificatePin::parse(&spki_pin).unwrap().matches(&cert));
+
+            verify_certificate_chain(&cert, &[], std::slice::from_ref(&root), Some(1_700_000_000))
+                .unwrap();
+            assert!(matches!(
+                verify_certificate_chain(&cert, &[], &[root], Some(1_950_000_000)),
+                Err(CryptoError::CertificateVerificationFailed(_))
+            ));
+            let other_key = SigningKey::generate(SignatureAlgorithm::Ed25519).unwrap();
+            let other_root = issue_test_certificate("Other Root", &other_key.pkey, None, true);
+            let other_root = Certificate::from_der(&other_root.to_der().unwrap()).unwrap();
+            assert!(
+                verify_certificate_chain(&cert, &[], &[other_root], Some(1_700_000_000)).is_err()
+            );
+        }
+
+        #[test]
+        fn test_create_csr() {
+            let mut request = CsrRequest::new("device-1");
+            request.subject.push(("O".into(), "FirmNetter".into()));
+            request.dns_names.push("device.example.com".into());
+            request.ip_addresses.push("192.168.1.10".parse().unwrap());
+
+            let ed = SigningKey::generate(SignatureAlgorithm::Ed25519).unwrap();
+            let rsa = RsaPrivateKey::generate(2048).unwrap();
+            for key in [CsrKey::Signing(&ed), CsrKey::Rsa(&rsa)] {
+                let pem = create_csr(&key, &request).unwrap();
+                let req = openssl::x509::X509Req::from_pem(&pem).unwrap();
+                let public = req.public_key().unwrap();
+                assert!(req.verify(&public).unwrap());
+                assert_eq!(
+                    x509_name_to_string(req.subject_name()),
+                    "CN=device-1, O=FirmNetter"
+                );
+                assert_eq!(req.extensions().unwrap().len(), 1);
+            }
+            assert!(create_csr(&CsrKey::Signing(&ed), &CsrRequest::default()).is_err());
+        }
+
+        #[test]
+        fn test_password_hash_scrypt_rfc7914_vector() {
+            // RFC 7914 第 12 节：N=1024, r=8, p=16, 盐 "NaCl"
+            let expected = hex_decode(
+                "fdbabe1c9d3472007856e7190d01e9fe7c6ad7cbc8237830e77376634b373162\
+                 2eaf30d92e22a3886ff109279d9830dac727afb94a83ee6d8360cbdfa2cc0640",
+            )
+            .unwrap();
+            let phc = format!(
+                "$scrypt$ln=10,r=8,p=16${}${}",
+                encode(Encoding::Base64NoPad, b"NaCl"),
+                encode(Encoding::Base64NoPad, &expected)
+            );
+            let current = KdfParams::default_for(KdfAlgorithm::Scrypt);
+
+            assert_eq!(
+                verify_password(b"password", &phc, &current).unwrap(),
+                PasswordVerification::ValidNeedsRehash
+            );
+            assert!(matches!(
+                verify_password(b"passw0rd", &phc, &current),
+                Err(CryptoError::PasswordMismatch)
+            ));
+        }
+
+        #[test]
+        fn test_password_hash_roundtrip_and_rehash() {
+            let weak = KdfParams::Argon2id {
+                iterations: 1,
+                memory_kib: 64,
+                lanes: 1,
+            };
+            let stronger = KdfParams::Argon2id {
+                iterations: 2,
+                memory_kib: 64,
+                lanes: 1,
+            };
+
+            let phc = hash_password(b"hunter2", &weak).unwrap();
+            assert!(phc.starts_with("$argon2id$v=19$m=64,t=1,p=1$"));
+            assert_ne!(phc, hash_password(b"hunter2", &weak).unwrap());
+            assert_eq!(
+                verify_password(b"hunter2", &phc, &weak).unwrap(),
+                PasswordVerification::Valid
+            );
+            assert_eq!(
+                verify_password(b"hunter2", &phc, &stronger).unwrap(),
+                PasswordVerification::ValidNeedsRehash
+            );
+            assert!(matches!(
+                verify_password(b"hunter3", &phc, &weak),
+                Err(CryptoError::PasswordMismatch)
+            ));
+
+            let pbkdf2 = KdfParams::Pbkdf2Sha256 { iterations: 1000 };
+            assert!(hash_password(b"x", &pbkdf2).is_err());
+            // 存储的哈希串中超限的参数必须在派生之前被拒绝
+            let salt = encode(Encoding::Base64NoPad, b"0123456789abcdef");
+            let rejected = [
+                phc.replace("m=64", "m=4294967295"),
+                phc.replace("p=1$", "p=536870912$"),
+                phc.replace("t=1", "t=4294967295"),
+                format!("$scrypt$ln=20,r=1048576,p=1${}${}", salt, salt),
+                format!("$scrypt$ln=20,r=8,p=67108864${}${}", salt, salt),
+            ];
+            for hash in &rejected {
+                assert!(
+                    matches!(
+                        verify_password(b"hunter2", hash, &weak),
+                        Err(CryptoError::InvalidPasswordHash(_))
+                    ),
+                    "{}",
+                    hash
+                );
+            }
+            assert!(verify_password(b"hunter2", "plain-text", &weak).is_err());
+
+            assert!(constant_time_eq(b"abc", b"abc"));
+            assert!(!constant_time_eq(b"abc", b"abd"));
+            assert!(!constant_time_eq(b"abc", b"abcd"));
+        }
     }
 }
@@ -1,4 +1,5 @@
 pub mod web {
+    use crate::safe::safe::{Certificate, CertificatePin};
     use crossbeam::queue::ArrayQueue;
     use memmap2::MmapMut;
     use once_cell::sync::Lazy;
@@ -7,8 +8,14 @@ pub mod web {
     use rayon::iter::ParallelIterator;
     use rayon::iter::{IndexedParallelIterator, IntoParallelRefMutIterator};
     use reqwest::Url;
-    use reqwest::blocking::Client;
+    use reqwest::blocking::RequestBuilder;
+    use reqwest::blocking::{Client, Response};
     use reqwest::header::{ACCEPT_RANGES, CONTENT_LENGTH, CONTENT_TYPE, HeaderMap};
+    use reqwest::redirect::Policy;
+    use rustls::client::WebPkiServerVerifier;
+    use rustls::client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier};
+    use rustls::pki_types::{CertificateDer, ServerName, UnixTime};
+    use rustls::{ClientConfig, DigitallySignedStruct, RootCertStore, SignatureScheme};
     use serde::Serialize;
     use std::collections::HashMap;
     use std::ffi::{CStr, CString, c_char};
@@ -17,6 +24,7 @@ pub mod web {
     use std::os::raw::c_int;
     use std::path::Path;
     use std::ptr;
+    use std::sync::Arc;
     use std::time::Duration;
 
     // 全局HTTP客户端
@@ -39,6 +47,7 @@ pub mod web {
         BufferPoolEmpty,
         BufferPoolFull,
         InvalidArgument(String),
+        CertificatePinMismatch(String),
     }
 
     // WebError的Display实现
@@ -53,6 +62,7 @@ pub mod web {
                 Self::BufferPoolEmpty => write!(f, "Buffer pool is empty"),
                 Self::BufferPoolFull => write!(f, "Buffer pool is full"),
                 Self::InvalidArgument(e) => write!(f, "Invalid argument: {}", e),
+                Self::CertificatePinMismatch(e) => write!(f, "Certificate pin mismatch: {}", e),
             }
         }
     }
@@ -116,18 +126,8 @@ pub mod web {
         }
     }
 
-    /// 向指定的 URL 发送 HTTP POST 请求
-    pub fn web_post<T, B>(url: T, body: B, way: bool, raw_bytes: bool) -> Result<ResPost, WebError>
-    where
-        T: reqwest::IntoUrl,
-        B: Serialize,
-    {
-        let response = if way {
-            GLOBAL_CLIENT.post(url).json(&body).send()?
-        } else {
-            GLOBAL_CLIENT.post(url).form(&body).send()?
-        };
-
+    // 按 Content-Type 读取响应体
+    fn read_response(response: Response, raw_bytes: bool) -> Result<ResPost, WebError> {
         let status_code = response.status().as_u16() as i32;
         let content_type = response
             .headers()
@@ -149,6 +149,21 @@ pub mod web {
         Ok(ResPost::new(status_code, res_body))
     }
 
+    /// 向指定的 URL 发送 HTTP POST 请求
+    pub fn web_post<T, B>(url: T, body: B, way: bool, raw_bytes: bool) -> Result<ResPost, WebError>
+    where
+        T: reqwest::IntoUrl,
+        B: Serialize,
+    {
+        let response = if way {
+            GLOBAL_CLIENT.post(url).json(&body).send()?
+        } else {
+            GLOBAL_CLIENT.post(url).form(&body).send()?
+        };
+
+        read_response(response, raw_bytes)
+    }
+
     /// 带自定义头的POST请求
     pub fn web_post_headers<T, B>(
         url: T,
@@ -175,25 +190,209 @@ pub mod web {
 
         let response = request_builder.send()?;
 
-        let status_code = response.status().as_u16() as i32;
-        let content_type = response
-            .headers()
-            .get(CONTENT_TYPE)
-            .and_then(|ct| ct.to_str().ok())
-            .unwrap_or("");
+        read_response(response, raw_bytes)
+    }
 
-        let res_body = if raw_bytes {
-            ResponseBody::Bytes(response.bytes()?.to_vec())
-        } else {
-            match content_type {
-                t if t.contains("text/") || t.contains("json") => {
-                    ResponseBody::Text(response.text()?)
+    /// 固定服务器证书的 HTTP 客户端
+    ///
+    /// 证书固定在 TLS 握手阶段检查：服务器证书链必须由信任锚签发，且叶子证书匹配任一
+    /// `CertificatePin`，否则握手失败并返回 `WebError::CertificatePinMismatch`，
+    /// 请求头和请求体不会发出。只允许 HTTPS，且不跟随重定向，3xx 响应原样返回给调用方。
+    pub struct PinnedClient {
+        client: Client,
+    }
+
+    // 证书固定失败的原因，握手失败后从错误链中取回
+    #[derive(Debug)]
+    struct PinMismatch(String);
+
+    impl std::fmt::Display for PinMismatch {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            write!(f, "certificate pin mismatch: {}", self.0)
+        }
+    }
+
+    impl std::error::Error for PinMismatch {}
+
+    // 握手阶段的证书校验：先按信任锚验证证书链和主机名，再检查叶子证书的固定值
+    #[derive(Debug)]
+    struct PinnedCertVerifier {
+        inner: Arc<WebPkiServerVerifier>,
+        pins: Vec<CertificatePin>,
+    }
+
+    impl ServerCertVerifier for PinnedCertVerifier {
+        fn verify_server_cert(
+            &self,
+            end_entity: &CertificateDer<'_>,
+            intermediates: &[CertificateDer<'_>],
+            server_name: &ServerName<'_>,
+            ocsp_response: &[u8],
+            now: UnixTime,
+        ) -> Result<ServerCertVerified, rustls::Error> {
+            self.inner.verify_server_cert(
+                end_entity,
+                intermediates,
+                server_name,
+                ocsp_response,
+                now,
+            )?;
+            let certificate = Certificate::from_der(end_entity.as_ref()).map_err(|_| {
+                rustls::Error::InvalidCertificate(rustls::CertificateError::BadEncoding)
+            })?;
+            if self.pins.iter().any(|pin| pin.matches(&certificate)) {
+                Ok(ServerCertVerified::assertion())
+            } else {
+                Err(rustls::Error::InvalidCertificate(
+                    rustls::CertificateError::Other(rustls::OtherError(Arc::new(PinMismatch(
+                        certificate.subject(),
+                    )))),
+                ))
+            }
+        }
+
+        fn verify_tls12_signature(
+            &self,
+            message: &[u8],
+            cert: &CertificateDer<'_>,
+            dss: &DigitallySignedStruct,
+        ) -> Result<HandshakeSignatureValid, rustls::Error> {
+            self.inner.verify_tls12_signature(message, cert, dss)
+        }
+
+        fn verify_tls13_signature(
+            &self,
+            message: &[u8],
+            cert: &CertificateDer<'_>,
+            dss: &DigitallySignedStruct,
+        ) -> Result<HandshakeSignatureValid, rustls::Error> {
+            self.inner.verify_tls13_signature(message, cert, dss)
+        }
+
+        fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+            self.inner.supported_verify_schemes()
+        }
+    }
+
+    // 在错误链中查找握手阶段的证书固定失败
+    fn find_pin_mismatch(err: &reqwest::Error) -> Option<String> {
+        let mut current: Option<&(dyn std::error::Error + 'static)> = Some(err);
+        while let Some(e) = current {
+            if let Some(rustls::Error::InvalidCertificate(rustls::CertificateError::Other(other))) =
+                e.downcast_ref::<rustls::Error>()
+                && let Some(mismatch) = other.0.downcast_ref::<PinMismatch>()
+            {
+                return Some(mismatch.0.clone());
+            }
+            // io::Error::source 会跳过被包装的错误本身，需要用 get_ref 逐层展开
+            current = match e
+                .downcast_ref::<std::io::Error>()
+                .and_then(|io| io.get_ref())
+            {
+                Some(inner) => Some(inner),
+                None => e.source(),
+            };
+        }
+        None
+    }
+
+    impl PinnedClient {
+        /// 使用系统根证书验证证书链，同时检查证书固定
+        pub fn new(pins: Vec<CertificatePin>) -> Result<Self, WebError> {
+            Self::build(pins, &[])
+        }
+
+        /// 只信任给定的 CA（不再使用系统根证书），同时检查证书固定
+        pub fn with_trust_anchors(
+            pins: Vec<CertificatePin>,
+            roots: &[Certificate],
+        ) -> Result<Self, WebError> {
+            if roots.is_empty() {
+                return Err(WebError::InvalidArgument("no trust anchors given".into()));
+            }
+            Self::build(pins, roots)
+        }
+
+        fn build(pins: Vec<CertificatePin>, roots: &[Certificate]) -> Result<Self, WebError> {
+            if pins.is_empty() {
+                return Err(WebError::InvalidArgument(
+                    "no certificate pins given".into(),
+                ));
+            }
+            let mut store = RootCertStore::empty();
+            if roots.is_empty() {
+                store.add_parsable_certificates(rustls_native_certs::load_native_certs().certs);
+            } else {
+                for root in roots {
+                    let der = root
+                        .to_der()
+                        .map_err(|e| WebError::InvalidArgument(e.to_string()))?;
+                    store
+                        .add(CertificateDer::from(der))
+                        .map_err(|e| WebError::InvalidArgument(e.to_string()))?;
                 }
-                _ => ResponseBody::Bytes(response.bytes()?.to_vec()),
             }
-        };
 
-        Ok(ResPost::new(status_code, res_body))
+            let provider = Arc::new(rustls::crypto::ring::default_provider());
+            let inner =
+                WebPkiServerVerifier::builder_with_provider(Arc::new(store), provider.clone())
+                    .build()
+                    .map_err(|e| WebError::InvalidArgument(e.to_string()))?;
+            let config = ClientConfig::builder_with_provider(provider)
+                .with_safe_default_protocol_versions()
+                .map_err(|e| WebError::InvalidArgument(e.to_string()))?
+                .dangerous()
+                .with_custom_certificate_verifier(Arc::new(PinnedCertVerifier { inner, pins }))
+                .with_no_client_auth();
+
+            let client = Client::builder()
+                .pool_max_idle_per_host(20)
+                .timeout(Duration::from_secs(3000))
+                .https_only(true)
+                .redirect(Policy::none())
+                .use_preconfigured_tls(config)
+                .build()?;
+            Ok(PinnedClient { client })
+        }
+
+        // 发送请求，把握手阶段的证书固定失败转换为 CertificatePinMismatch
+        fn send(&self, request: RequestBuilder) -> Result<Response, WebError> {
+            request.send().map_err(|e| match find_pin_mismatch(&e) {
+                Some(subject) => WebError::CertificatePinMismatch(subject),
+                None => WebError::RequestError(e),
+            })
+        }
+
+        /// GET 请求
+        pub fn get<T: reqwest::IntoUrl>(
+            &self,
+            url: T,
+            raw_bytes: bool,
+        ) -> Result<ResPost, WebError> {
+            let response = self.send(self.client.get(url))?;
+            read_response(response, raw_bytes)
+        }
+
+        /// POST 请求，参数含义与 `web_post` 相同
+        pub fn post<T, B>(
+            &self,
+            url: T,
+            body: B,
+            way: bool,
+            raw_bytes: bool,
+        ) -> Result<ResPost, WebError>
+        where
+            T: reqwest::IntoUrl,
+            B: Serialize,
+        {
+            let request = if way {
+                self.client.post(url).json(&body)
+            } else {
+                self.client.post(url).form(&body)
+            };
+            let response = self.send(request)?;
+            read_response(response, raw_bytes)
+        }
     }
 
     // C接口结构体
@@ -840,6 +1039,7 @@ pub mod web {
         BufferPoolError = 5,
         MemoryAllocationFailed = 6,
         InvalidArgument = 7,
+        CertificatePinMismatch = 8,
     }
 
     // 测试函数
@@ -852,4 +1052,169 @@ pub mod web {
         println!("status code: {}", res.status_code);
         println!("body: {}", res.body)
     }
+
+    // 测试用：本地 HTTPS 服务器，证书由测试 CA 签发；每个连接的请求内容或握手失败通过通道上报
+    #[cfg(test)]
+    fn spawn_tls_server() -> (
+        u16,
+        Certificate,
+        Certificate,
+        std::sync::mpsc::Receiver<Option<String>>,
+    ) {
+        use openssl::asn1::Asn1Time;
+        use openssl::bn::BigNum;
+        use openssl::ec::{EcGroup, EcKey};
+        use openssl::hash::MessageDigest;
+        use openssl::nid::Nid;
+        use openssl::pkey::{PKey, Private};
+        use openssl::ssl::{SslAcceptor, SslMethod};
+        use openssl::x509::extension::{BasicConstraints, SubjectAlternativeName};
+        use openssl::x509::{X509, X509Builder, X509NameBuilder};
+        use std::io::Write;
+        use std::net::TcpListener;
+
+        fn ec_key() -> PKey<Private> {
+            let group = EcGroup::from_curve_name(Nid::X9_62_PRIME256V1).unwrap();
+            PKey::from_ec_key(EcKey::generate(&group).unwrap()).unwrap()
+        }
+
+        fn issue(cn: &str, key: &PKey<Private>, issuer: Option<(&X509, &PKey<Private>)>) -> X509 {
+            let mut name = X509NameBuilder::new().unwrap();
+            name.append_entry_by_text("CN", cn).unwrap();
+            let name = name.build();
+            let mut builder = X509Builder::new().unwrap();
+            builder.set_version(2).unwrap();
+            let serial = BigNum::from_u32(7).unwrap().to_asn1_integer().unwrap();
+            builder.set_serial_number(&serial).unwrap();
+            builder.set_subject_name(&name).unwrap();
+            builder
+                .set_issuer_name(issuer.map_or(&name, |(cert, _)| cert.subject_name()))
+                .unwrap();
+            builder.set_pubkey(key).unwrap();
+            builder
+                .set_not_before(&Asn1Time::days_from_now(0).unwrap())
+                .unwrap();
+            builder
+                .set_not_after(&Asn1Time::days_from_now(1).unwrap())
+                .unwrap();
+            match issuer {
+                None => {
+                    let constraints = BasicConstraints::new().critical().ca().build().unwrap();
+                    builder.append_extension(constraints).unwrap();
+                }
+                Some((ca, _)) => {
+                    let san = SubjectAlternativeName::new()
+                        .ip("127.0.0.1")
+                        .build(&builder.x509v3_context(Some(ca), None))
+                        .unwrap();
+                    builder.append_extension(san).unwrap();
+                }
+            }
+            builder
+                .sign(issuer.map_or(key, |(_, k)| k), MessageDigest::sha256())
+                .unwrap();
+            builder.build()
+        }
+
+        let ca_key = ec_key();
+        let ca = issue("Test Root", &ca_key, None);
+        let leaf_key = ec_key();
+        let leaf = issue("127.0.0.1", &leaf_key, Some((&ca, &ca_key)));
+
+        let mut acceptor = SslAcceptor::mozilla_intermediate_v5(SslMethod::tls()).unwrap();
+        acceptor.set_private_key(&leaf_key).unwrap();
+        acceptor.set_certificate(&leaf).unwrap();
+        let acceptor = acceptor.build();
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let (tx, rx) = std::sync::mpsc::channel();
+        std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                let Ok(mut tls) = acceptor.accept(stream.unwrap()) else {
+                    let _ = tx.send(None);
+                    continue;
+                };
+                let mut request = Vec::new();
+                let mut buf = [0u8; 4096];
+                while !request.windows(4).any(|w| w == b"\r\n\r\n") {
+                    match tls.read(&mut buf) {
+                        Ok(0) | Err(_) => break,
+                        Ok(n) => request.extend_from_slice(&buf[..n]),
+                    }
+                }
+                let request = String::from_utf8_lossy(&request).into_owned();
+                let response = if request.starts_with("GET /redirect ") {
+                    format!(
+                        "HTTP/1.1 307 Temporary Redirect\r\nLocation: https://127.0.0.1:{}/ok\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+                        port
+                    )
+                } else {
+                    "HTTP/1.1 200 OK\r\nContent-Length: 6\r\nConnection: close\r\n\r\npinned"
+                        .to_string()
+                };
+                let _ = tls.write_all(response.as_bytes());
+                let _ = tls.shutdown();
+                let _ = tx.send(Some(request));
+            }
+        });
+
+        let ca = Certificate::from_der(&ca.to_der().unwrap()).unwrap();
+        let leaf = Certificate::from_der(&leaf.to_der().unwrap()).unwrap();
+        (port, ca, leaf, rx)
+    }
+
+    #[test]
+    fn test_pinned_client() {
+        let timeout = Duration::from_secs(10);
+        let (port, ca, leaf, requests) = spawn_tls_server();
+        let url = |path: &str| format!("https://127.0.0.1:{}{}", port, path);
+
+        let pin = CertificatePin::SpkiSha256(leaf.spki_sha256().unwrap());
+        let client =
+            PinnedClient::with_trust_anchors(vec![pin], std::slice::from_ref(&ca)).unwrap();
+        let res = client.get(url("/ok"), false).unwrap();
+        assert_eq!(res.status_code, 200);
+        assert_eq!(res.body.to_string(), "pinned");
+        assert!(requests.recv_timeout(timeout).unwrap().is_some());
+
+        // 重定向不被跟随，3xx 原样返回
+        let res = client.get(url("/redirect"), false).unwrap();
+        assert_eq!(res.status_code, 307);
+        assert!(
+            requests
+                .recv_timeout(timeout)
+                .unwrap()
+                .unwrap()
+                .starts_with("GET /redirect ")
+        );
+
+        // 固定值不匹配时握手失败，请求体不会到达服务器
+        let other = CertificatePin::SpkiSha256([0u8; 32]);
+        let client =
+            PinnedClient::with_trust_anchors(vec![other], std::slice::from_ref(&ca)).unwrap();
+        let mut body = HashMap::new();
+        body.insert("license", "secret");
+        match client.post(url("/ok"), &body, true, false) {
+            Err(WebError::CertificatePinMismatch(subject)) => assert_eq!(subject, "CN=127.0.0.1"),
+            other => panic!("expected pin mismatch, got {:?}", other),
+        }
+        assert_eq!(requests.recv_timeout(timeout).unwrap(), None);
+
+        // 证书链不受信任时同样在握手阶段失败
+        let client =
+            PinnedClient::with_trust_anchors(vec![pin], std::slice::from_ref(&leaf)).unwrap();
+        assert!(matches!(
+            client.get(url("/ok"), false),
+            Err(WebError::RequestError(_))
+        ));
+        assert_eq!(requests.recv_timeout(timeout).unwrap(), None);
+
+        // 不允许明文 HTTP
+        assert!(
+            client
+                .get(format!("http://127.0.0.1:{}/ok", port), false)
+                .is_err()
+        );
+    }
 }